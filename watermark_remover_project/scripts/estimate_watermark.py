@@ -0,0 +1,96 @@
+#!/usr/bin/env python3
+
+import argparse
+import os
+import sys
+
+# Add project root to sys.path to allow finding the watermark_remover package
+# This assumes the script is in watermark_remover_project/scripts/
+project_root = os.path.abspath(os.path.join(os.path.dirname(__file__), ".."))
+if project_root not in sys.path:
+    sys.path.insert(0, project_root)
+
+import numpy as np
+from PIL import Image
+
+from watermark_remover.core.estimation import ANCHORS, estimate_watermark
+
+IMAGE_EXTENSIONS = (".png", ".jpg", ".jpeg", ".webp", ".bmp", ".tif", ".tiff")
+
+
+def main():
+    """
+    Main function to handle CLI arguments and estimate a watermark shared by a folder of images.
+    """
+    parser = argparse.ArgumentParser(description="Estimate a watermark shared by a set of images and write a reusable template plus one mask per image.")
+
+    parser.add_argument(
+        "-i", "--input-dir",
+        type=str,
+        required=True,
+        help="Folder of images carrying the same watermark (at least 3; more gives a cleaner estimate)."
+    )
+    parser.add_argument(
+        "-o", "--mask-dir",
+        type=str,
+        required=True,
+        help="Folder to write the per-image masks to, as <image name>.png. Pass it to remove_folder.py --mask-dir."
+    )
+    parser.add_argument(
+        "-t", "--template",
+        type=str,
+        default=None,
+        help="Optional path to save the watermark template (.npz) for reuse."
+    )
+    parser.add_argument(
+        "--anchor",
+        type=str,
+        choices=ANCHORS,
+        default="top_left",
+        help="How images of different sizes line up for the first estimate. (default: %(default)s)"
+    )
+    parser.add_argument(
+        "--margin",
+        type=int,
+        default=8,
+        help="Pixels kept around the watermark in the template. (default: %(default)s)"
+    )
+
+    args = parser.parse_args()
+
+    if not os.path.isdir(args.input_dir):
+        print(f"Error: Input folder not found at {args.input_dir}")
+        sys.exit(1)
+
+    filenames = sorted(name for name in os.listdir(args.input_dir) if name.lower().endswith(IMAGE_EXTENSIONS))
+
+    try:
+        images = []
+        for filename in filenames:
+            with Image.open(os.path.join(args.input_dir, filename)) as image:
+                images.append(np.asarray(image.convert("RGB")))
+
+        result = estimate_watermark(images, anchor=args.anchor, margin=args.margin)
+
+        os.makedirs(args.mask_dir, exist_ok=True)
+        for filename, mask, position, score in zip(filenames, result.masks, result.positions, result.scores):
+            mask_path = os.path.join(args.mask_dir, os.path.splitext(filename)[0] + ".png")
+            Image.fromarray(mask).save(mask_path)
+            print(f"{filename}: watermark at {position}, match score {score:.2f} -> {mask_path}")
+
+        if args.template:
+            template_dir = os.path.dirname(args.template)
+            if template_dir:
+                os.makedirs(template_dir, exist_ok=True)
+            result.template.save(args.template)
+            print(f"Watermark template saved to: {args.template}")
+
+    except (FileNotFoundError, ValueError) as e:
+        print(f"Error: {e}")
+        sys.exit(1)
+    except Exception as e:
+        print(f"An unexpected error occurred: {e}")
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,61 @@
+#!/usr/bin/env python3
+
+import argparse
+import os
+import sys
+
+# Add project root to sys.path to allow finding the watermark_remover package
+# This assumes the script is in watermark_remover_project/scripts/
+project_root = os.path.abspath(os.path.join(os.path.dirname(__file__), ".."))
+if project_root not in sys.path:
+    sys.path.insert(0, project_root)
+
+from watermark_remover.core.onnx_backend import export_onnx
+
+def main():
+    """
+    Exports a LaMa TorchScript file or checkpoint to ONNX for the lama_onnx backend.
+    Needs PyTorch; the workers running the exported model do not.
+    """
+    parser = argparse.ArgumentParser(description="Export a LaMa model to ONNX.")
+
+    parser.add_argument(
+        "--model-path",
+        type=str,
+        required=True,
+        help="Path to the LaMa TorchScript file or checkpoint."
+    )
+    parser.add_argument(
+        "-o", "--output",
+        type=str,
+        required=True,
+        help="Path to write the .onnx file."
+    )
+    parser.add_argument(
+        "--opset",
+        type=int,
+        default=17,
+        help="ONNX opset version (>= 17 for the FFT ops)."
+    )
+    parser.add_argument(
+        "--allow-pickle",
+        action="store_true",
+        help="Load checkpoints that need the full unpickler (e.g. Lightning checkpoints). "
+             "It can execute code from the file: only use with trusted models."
+    )
+
+    args = parser.parse_args()
+
+    try:
+        print(f"Exporting {args.model_path} -> {args.output} (opset {args.opset})...")
+        export_onnx(args.model_path, args.output, opset_version=args.opset, allow_pickle=args.allow_pickle)
+        print(f"Successfully exported ONNX model to: {args.output}")
+    except (FileNotFoundError, ValueError) as e:
+        print(f"Error: {e}")
+        sys.exit(1)
+    except Exception as e:
+        print(f"An unexpected error occurred: {e}")
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
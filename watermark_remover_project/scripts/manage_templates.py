@@ -0,0 +1,120 @@
+#!/usr/bin/env python3
+
+import argparse
+import json
+import os
+import sys
+
+# Add project root to sys.path to allow finding the watermark_remover package
+# This assumes the script is in watermark_remover_project/scripts/
+project_root = os.path.abspath(os.path.join(os.path.dirname(__file__), ".."))
+if project_root not in sys.path:
+    sys.path.insert(0, project_root)
+
+import numpy as np
+from PIL import Image
+
+from watermark_remover.config import settings
+from watermark_remover.core.templates import TemplateRegistry, get_template_registry, read_template_image, template_mask
+
+
+def add_template(registry, args, overwrite):
+    image, alpha = read_template_image(args.image)
+    if args.alpha:
+        with Image.open(args.alpha) as alpha_image:
+            alpha = np.asarray(alpha_image.convert("L"))
+    scale_range = (args.scale_min, args.scale_max) if args.scale_min or args.scale_max else None
+    if scale_range and None in scale_range:
+        raise ValueError("Give both --scale-min and --scale-max, or neither")
+    template = registry.add(args.name, image, alpha, scale_range=scale_range, overwrite=overwrite)
+    print(json.dumps(template.metadata(), indent=2))
+
+
+def main():
+    """
+    Main function to manage the watermark template library and match templates against images.
+    """
+    parser = argparse.ArgumentParser(description="Manage the library of known watermark logos.")
+    parser.add_argument(
+        "--templates-dir",
+        type=str,
+        default=None,
+        help="Local template library folder. (default: the configured storage, WATERMARK_REMOVER_STORAGE_BACKEND)"
+    )
+    subparsers = parser.add_subparsers(dest="command", required=True)
+
+    subparsers.add_parser("list", help="List registered templates.")
+
+    for command, description in (("add", "Register a new template."), ("update", "Replace an existing template.")):
+        subparser = subparsers.add_parser(command, help=description)
+        subparser.add_argument("name", type=str, help="Template name (letters, digits, '_', '-', '.').")
+        subparser.add_argument(
+            "-i", "--image",
+            type=str,
+            required=True,
+            help="Logo image; its transparency is used as alpha. A .npz from estimate_watermark.py also works."
+        )
+        subparser.add_argument("--alpha", type=str, default=None, help="Optional grayscale opacity image (white = opaque).")
+        subparser.add_argument("--scale-min", type=float, default=None, help="Smallest scale to search, relative to the logo size.")
+        subparser.add_argument("--scale-max", type=float, default=None, help="Largest scale to search, relative to the logo size.")
+
+    show = subparsers.add_parser("show", help="Show a template's metadata, optionally exporting its RGBA image.")
+    show.add_argument("name", type=str)
+    show.add_argument("-o", "--output", type=str, default=None, help="Optional path to save the RGBA template image to.")
+
+    delete = subparsers.add_parser("delete", help="Delete a template.")
+    delete.add_argument("name", type=str)
+
+    match = subparsers.add_parser("match", help="Find templates in an image and write the resulting mask.")
+    match.add_argument("-i", "--input", type=str, required=True, help="Image to search.")
+    match.add_argument("-o", "--output", type=str, required=True, help="Path to save the binary mask to.")
+    match.add_argument("-n", "--names", type=str, nargs="*", default=None, help="Templates to look for. (default: all)")
+    match.add_argument(
+        "--threshold",
+        type=float,
+        default=settings.template_match_threshold,
+        help=f"Minimum normalised correlation of a match. (default: {settings.template_match_threshold})"
+    )
+
+    args = parser.parse_args()
+    registry = TemplateRegistry(args.templates_dir) if args.templates_dir else get_template_registry()
+
+    try:
+        if args.command == "list":
+            templates = registry.list()
+            if not templates:
+                print("No templates registered")
+            for template in templates:
+                scale_range = template["scale_range"] or "default"
+                print(f"{template['name']}: {template['width']}x{template['height']}, scale range {scale_range}")
+        elif args.command in ("add", "update"):
+            if args.command == "update" and not registry.exists(args.name):
+                raise FileNotFoundError(f"Template not found: {args.name}")
+            add_template(registry, args, overwrite=args.command == "update")
+        elif args.command == "show":
+            template = registry.get(args.name)
+            print(json.dumps(template.metadata(), indent=2))
+            if args.output:
+                template.to_rgba().save(args.output)
+                print(f"Template image saved to: {args.output}")
+        elif args.command == "delete":
+            registry.delete(args.name)
+        elif args.command == "match":
+            with Image.open(args.input) as image:
+                rgb = np.asarray(image.convert("RGB"))
+            mask, matches = template_mask(rgb, registry, args.names, threshold=args.threshold)
+            output_dir = os.path.dirname(args.output)
+            if output_dir:
+                os.makedirs(output_dir, exist_ok=True)
+            Image.fromarray(mask).save(args.output)
+            print(f"{len(matches)} match(es); mask saved to: {args.output}")
+
+    except (FileNotFoundError, ValueError) as e:
+        print(f"Error: {e}")
+        sys.exit(1)
+    except Exception as e:
+        print(f"An unexpected error occurred: {e}")
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
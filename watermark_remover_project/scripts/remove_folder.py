@@ -0,0 +1,252 @@
+#!/usr/bin/env python3
+
+import argparse
+import os
+import sys
+
+# Add project root to sys.path to allow finding the watermark_remover package
+# This assumes the script is in watermark_remover_project/scripts/
+project_root = os.path.abspath(os.path.join(os.path.dirname(__file__), ".."))
+if project_root not in sys.path:
+    sys.path.insert(0, project_root)
+
+from PIL import Image
+
+from watermark_remover.config import settings
+from watermark_remover.core.backends import available_backends, create_backend
+from watermark_remover.core.refinement import QUALITY_PRESETS, with_quality
+from watermark_remover.core.tiling import with_tiling
+from watermark_remover.utils.mask_formats import import_mask
+from watermark_remover.utils.mask_refinement import MaskRefinement
+from watermark_remover.utils.regions import add_regions
+from watermark_remover.utils.image_io import save_image
+
+IMAGE_EXTENSIONS = (".png", ".jpg", ".jpeg", ".webp", ".bmp", ".tif", ".tiff")
+# Annotation formats also accepted as masks (see watermark_remover.utils.mask_formats).
+MASK_EXTENSIONS = (".json", ".svg")
+
+
+def find_mask(mask_dir, filename):
+    """Returns the mask in mask_dir with the same base name as filename (any image or annotation extension), if any."""
+    if not mask_dir:
+        return None
+    stem = os.path.splitext(filename)[0]
+    for extension in IMAGE_EXTENSIONS + MASK_EXTENSIONS:
+        candidate = os.path.join(mask_dir, stem + extension)
+        if os.path.exists(candidate):
+            return candidate
+    return None
+
+
+def main():
+    """
+    Main function to handle CLI arguments and process every image in a folder with batched inference.
+    """
+    parser = argparse.ArgumentParser(description="Remove watermarks from all images in a folder, batching similar-sized images.")
+
+    parser.add_argument(
+        "-i", "--input-dir",
+        type=str,
+        required=True,
+        help="Folder containing the input images."
+    )
+    parser.add_argument(
+        "-o", "--output-dir",
+        type=str,
+        required=True,
+        help="Folder to save the processed images to (same file names)."
+    )
+    parser.add_argument(
+        "-m", "--mask-dir",
+        type=str,
+        default=None,
+        help="Optional folder of masks named like the input images (e.g. photo.jpg -> photo.png): binary images, VOC palette PNGs, COCO .json or .svg. Images without a mask get one detected automatically."
+    )
+    parser.add_argument(
+        "--region",
+        type=str,
+        action="append",
+        default=None,
+        help='Mask shape as JSON (or a .json file) applied to every image, e.g. \'{"type": "rect", "anchor": "bottom_right", "units": "relative", "x": 0.02, "y": 0.02, "width": 0.25, "height": 0.1}\'. Repeatable; combined with masks from --mask-dir.'
+    )
+    parser.add_argument(
+        "--min-confidence",
+        type=float,
+        default=settings.detection_min_confidence,
+        help=f"Without a mask the watermark is detected automatically; below this detection confidence the image is left unchanged. (default: {settings.detection_min_confidence})"
+    )
+    parser.add_argument(
+        "-b", "--backend",
+        type=str,
+        choices=available_backends(),
+        default=settings.backend,
+        help=f"Inpainting backend to use. (default: {settings.backend})"
+    )
+    parser.add_argument(
+        "--model-path",
+        type=str,
+        default=settings.model_path,
+        help="Optional path to a local LaMa TorchScript file or checkpoint. Without it LaMa falls back to OpenCV inpainting."
+    )
+    parser.add_argument(
+        "--template",
+        type=str,
+        default=settings.template_path,
+        help="Watermark template (.npz from estimate_watermark.py) for the reverse_blend backend, which recovers the original pixels under semi-transparent watermarks."
+    )
+    parser.add_argument(
+        "--inpaint-radius",
+        type=int,
+        default=settings.inpaint_radius,
+        help=f"Neighbourhood radius for the OpenCV engines and LaMa's fallback. (default: {settings.inpaint_radius})"
+    )
+    parser.add_argument(
+        "-q", "--quality",
+        type=str,
+        choices=list(QUALITY_PRESETS),
+        default=settings.quality,
+        help=f"'fast' runs a single pass; 'balanced' and 'high' refine a coarse fill at increasing resolutions. (default: {settings.quality})"
+    )
+    parser.add_argument(
+        "--tile-size",
+        type=int,
+        default=0,
+        help="Maximum crop side for crop-around-mask processing; tiling runs images one at a time, so it is off by default here."
+    )
+    parser.add_argument(
+        "--batch-size",
+        type=int,
+        default=settings.batch_size,
+        help=f"Maximum number of images per forward pass. (default: {settings.batch_size})"
+    )
+    parser.add_argument(
+        "--memory-budget-mb",
+        type=int,
+        default=settings.memory_budget_mb,
+        help="Cap on the estimated memory of one forward pass in MB; 0 means no cap. (default: %(default)s)"
+    )
+    parser.add_argument(
+        "--chunk-size",
+        type=int,
+        default=32,
+        help="Number of images loaded into memory at a time. (default: %(default)s)"
+    )
+    parser.add_argument(
+        "--mask-dilate",
+        type=int,
+        default=settings.mask_dilate,
+        help=f"Grow the mask by this radius in pixels to cover anti-aliased edges and halos. (default: {settings.mask_dilate})"
+    )
+    parser.add_argument(
+        "--mask-close",
+        type=int,
+        default=settings.mask_close,
+        help=f"Morphological closing radius in pixels, joining broken strokes. (default: {settings.mask_close})"
+    )
+    parser.add_argument(
+        "--mask-min-area",
+        type=int,
+        default=settings.mask_min_area,
+        help=f"Drop mask components smaller than this many pixels. (default: {settings.mask_min_area})"
+    )
+    parser.add_argument(
+        "--mask-fill-holes",
+        action="store_true",
+        default=settings.mask_fill_holes,
+        help="Fill enclosed holes in the mask, e.g. the inside of letters."
+    )
+    parser.add_argument(
+        "--mask-feather",
+        type=int,
+        default=settings.mask_feather,
+        help=f"Width in pixels of a soft edge blending the result into the original around the mask. (default: {settings.mask_feather})"
+    )
+    parser.add_argument(
+        "--inpaint-alpha",
+        action="store_true",
+        help="Also inpaint the alpha channel of transparent images instead of keeping it untouched."
+    )
+    parser.add_argument(
+        "--strip-metadata",
+        action="store_true",
+        help="Do not copy EXIF/XMP metadata to the outputs. The ICC profile is always kept."
+    )
+    parser.add_argument(
+        "--jpeg-quality",
+        type=int,
+        default=None,
+        help="JPEG/WebP output quality (1-95). Defaults to each input's own quality for JPEG inputs."
+    )
+
+    args = parser.parse_args()
+
+    if not os.path.isdir(args.input_dir):
+        print(f"Error: Input folder not found at {args.input_dir}")
+        sys.exit(1)
+    if args.mask_dir and not os.path.isdir(args.mask_dir):
+        print(f"Error: Mask folder not found at {args.mask_dir}")
+        sys.exit(1)
+
+    filenames = sorted(name for name in os.listdir(args.input_dir) if name.lower().endswith(IMAGE_EXTENSIONS))
+    if not filenames:
+        print(f"No images found in {args.input_dir}")
+        return
+    os.makedirs(args.output_dir, exist_ok=True)
+
+    try:
+        refinement = MaskRefinement(dilate=args.mask_dilate, close=args.mask_close, min_area=args.mask_min_area,
+                                    fill_holes=args.mask_fill_holes, feather=args.mask_feather).validate()
+
+        print(f"Initializing backend '{args.backend}' (model: {args.model_path if args.model_path else 'none, classical fallback'})...")
+        backend = create_backend(
+            args.backend,
+            ignore_unknown_options=True,
+            **{
+                **settings.backend_options(),
+                "model_path": args.model_path,
+                "template_path": args.template,
+                "radius": args.inpaint_radius,
+                "fallback_radius": args.inpaint_radius,
+            },
+        )
+        backend = with_tiling(with_quality(backend, args.quality), args.tile_size)
+
+        for start in range(0, len(filenames), max(1, args.chunk_size)):
+            chunk = filenames[start:start + max(1, args.chunk_size)]
+            images = []
+            for filename in chunk:
+                image = Image.open(os.path.join(args.input_dir, filename))
+                image.load()
+                images.append(image)
+            masks = [find_mask(args.mask_dir, filename) for filename in chunk]
+            masks = [import_mask(mask, image.size, image_name=filename) if mask else None
+                     for mask, image, filename in zip(masks, images, chunk)]
+            if args.region:
+                masks = [add_regions(mask, args.region, image.size) for mask, image in zip(masks, images)]
+
+            results = backend.inpaint_batch(
+                images,
+                masks,
+                inpaint_alpha=args.inpaint_alpha,
+                max_batch_size=args.batch_size,
+                memory_budget_mb=args.memory_budget_mb or None,
+                min_confidence=args.min_confidence,
+                mask_refinement=refinement,
+            )
+            for filename, image, result in zip(chunk, images, results):
+                output_path = os.path.join(args.output_dir, filename)
+                save_image(result, output_path, source=image, keep_metadata=not args.strip_metadata,
+                           jpeg_quality=args.jpeg_quality)
+                print(f"Saved: {output_path}")
+
+        print(f"Successfully processed {len(filenames)} images. Outputs saved to: {args.output_dir}")
+
+    except (FileNotFoundError, ValueError) as e:
+        print(f"Error: {e}")
+        sys.exit(1)
+    except Exception as e:
+        print(f"An unexpected error occurred: {e}")
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
@@ -10,13 +10,27 @@ project_root = os.path.abspath(os.path.join(os.path.dirname(__file__), ".."))
 if project_root not in sys.path:
     sys.path.insert(0, project_root)
 
-from watermark_remover.core.lama_wrapper import LaMaWrapper
+import numpy as np
+from PIL import Image
+
+from watermark_remover.config import settings
+from watermark_remover.core.backends import available_backends, create_backend
+from watermark_remover.core.color_mask import color_mask
+from watermark_remover.core.refinement import QUALITY_PRESETS, with_quality
+from watermark_remover.core.templates import get_template_registry, template_mask
+from watermark_remover.core.text_detection import CORNERS, detect_text
+from watermark_remover.core.tiling import with_tiling
+from watermark_remover.utils.colors import COLOR_PRESETS
+from watermark_remover.utils.mask_formats import EXPORT_FORMATS, IMPORT_FORMATS, export_mask, format_for_mask_path, import_mask
+from watermark_remover.utils.mask_refinement import MaskRefinement
+from watermark_remover.utils.masks import load_mask
+from watermark_remover.utils.regions import add_regions
 
 def main():
     """
     Main function to handle CLI arguments and process the image.
     """
-    parser = argparse.ArgumentParser(description="Remove watermark from a single image using LaMa (placeholder).")
+    parser = argparse.ArgumentParser(description="Remove watermark from a single image using LaMa or another inpainting backend.")
     
     parser.add_argument(
         "-i", "--input",
@@ -30,11 +44,180 @@ def main():
         required=True,
         help="Path to save the processed image."
     )
+    parser.add_argument(
+        "-m", "--mask",
+        type=str,
+        default=None,
+        help="Optional path to a binary mask image (same size as the input); white pixels mark the watermark. Detected automatically when omitted."
+    )
+    parser.add_argument(
+        "--mask-format",
+        type=str,
+        choices=IMPORT_FORMATS,
+        default="auto",
+        help="Format of --mask: a binary image, COCO JSON (RLE or polygons), a VOC palette PNG or SVG paths. (default: auto, from the file)"
+    )
+    parser.add_argument(
+        "--export-mask",
+        type=str,
+        default=None,
+        help="Also save the mask used (given, built or detected, before refinement) to this path, e.g. to edit it in a labelling tool and pass it back with --mask."
+    )
+    parser.add_argument(
+        "--export-mask-format",
+        type=str,
+        choices=EXPORT_FORMATS,
+        default=None,
+        help="Format for --export-mask. (default: from the extension: .json is COCO RLE, .svg is SVG, anything else a binary PNG)"
+    )
+    parser.add_argument(
+        "--region",
+        type=str,
+        action="append",
+        default=None,
+        help='Mask shape as JSON (or a .json file), e.g. \'{"type": "rect", "anchor": "bottom_right", "x": 10, "y": 10, "width": 200, "height": 80}\'. Types: rect, polygon, ellipse; "units": "relative" for fractions of the image. Repeatable; combined with --mask if both are given, or limits --color-key.'
+    )
+    parser.add_argument(
+        "--color-key",
+        type=str,
+        nargs="+",
+        default=None,
+        help=f"Build the mask from pixels of these colours, for flat-coloured watermarks: #rrggbb or one of {', '.join(COLOR_PRESETS)}. With --region, only inside the region."
+    )
+    parser.add_argument(
+        "--color-tolerance",
+        type=float,
+        default=settings.color_key_tolerance,
+        help=f"With --color-key, largest CIE Lab distance to a key colour. (default: {settings.color_key_tolerance})"
+    )
+    parser.add_argument(
+        "--match-templates",
+        type=str,
+        nargs="*",
+        default=None,
+        help="Build the mask from logos in the template library (see manage_templates.py) instead of automatic detection. Give template names, or none to try all."
+    )
+    parser.add_argument(
+        "--detect-text",
+        action="store_true",
+        help="Build the mask from detected text overlays such as camera date stamps or captions (glyph-tight)."
+    )
+    parser.add_argument(
+        "--text-corners",
+        type=str,
+        nargs="+",
+        choices=CORNERS,
+        default=None,
+        help="With --detect-text, only keep text in these corners. (default: anywhere)"
+    )
+    parser.add_argument(
+        "--text-colors",
+        type=str,
+        nargs="+",
+        default=None,
+        help=f"With --detect-text, only keep text of these colours: #rrggbb or one of {', '.join(COLOR_PRESETS)} (e.g. orange for LED date stamps)."
+    )
+    parser.add_argument(
+        "--min-confidence",
+        type=float,
+        default=settings.detection_min_confidence,
+        help=f"Without a mask the watermark is detected automatically; below this detection confidence the image is left unchanged. (default: {settings.detection_min_confidence})"
+    )
+    parser.add_argument(
+        "-b", "--backend",
+        type=str,
+        choices=available_backends(),
+        default=settings.backend,
+        help=f"Inpainting backend to use. (default: {settings.backend})"
+    )
     parser.add_argument(
         "--model-path",
         type=str,
+        default=settings.model_path,
+        help="Optional path to a local LaMa TorchScript file or checkpoint. Without it LaMa falls back to OpenCV inpainting."
+    )
+    parser.add_argument(
+        "--template",
+        type=str,
+        default=settings.template_path,
+        help="Watermark template (.npz from estimate_watermark.py) for the reverse_blend backend, which recovers the original pixels under semi-transparent watermarks."
+    )
+    parser.add_argument(
+        "--inpaint-radius",
+        type=int,
+        default=settings.inpaint_radius,
+        help=f"Neighbourhood radius for the OpenCV engines and LaMa's fallback. (default: {settings.inpaint_radius})"
+    )
+    parser.add_argument(
+        "-q", "--quality",
+        type=str,
+        choices=list(QUALITY_PRESETS),
+        default=settings.quality,
+        help=f"'fast' runs a single pass; 'balanced' and 'high' refine a coarse fill at increasing resolutions. (default: {settings.quality})"
+    )
+    parser.add_argument(
+        "--tile-size",
+        type=int,
+        default=settings.tile_size,
+        help=f"Maximum crop side for crop-around-mask processing of large images; 0 processes the whole image at once. (default: {settings.tile_size})"
+    )
+    parser.add_argument(
+        "--tile-margin",
+        type=int,
+        default=settings.tile_margin,
+        help=f"Context margin in pixels kept around each mask region. (default: {settings.tile_margin})"
+    )
+    parser.add_argument(
+        "--tile-feather",
+        type=int,
+        default=settings.tile_feather,
+        help=f"Blend width in pixels between overlapping tiles. (default: {settings.tile_feather})"
+    )
+    parser.add_argument(
+        "--mask-dilate",
+        type=int,
+        default=settings.mask_dilate,
+        help=f"Grow the mask by this radius in pixels to cover anti-aliased edges and halos. (default: {settings.mask_dilate})"
+    )
+    parser.add_argument(
+        "--mask-close",
+        type=int,
+        default=settings.mask_close,
+        help=f"Morphological closing radius in pixels, joining broken strokes. (default: {settings.mask_close})"
+    )
+    parser.add_argument(
+        "--mask-min-area",
+        type=int,
+        default=settings.mask_min_area,
+        help=f"Drop mask components smaller than this many pixels. (default: {settings.mask_min_area})"
+    )
+    parser.add_argument(
+        "--mask-fill-holes",
+        action="store_true",
+        default=settings.mask_fill_holes,
+        help="Fill enclosed holes in the mask, e.g. the inside of letters."
+    )
+    parser.add_argument(
+        "--mask-feather",
+        type=int,
+        default=settings.mask_feather,
+        help=f"Width in pixels of a soft edge blending the result into the original around the mask. (default: {settings.mask_feather})"
+    )
+    parser.add_argument(
+        "--inpaint-alpha",
+        action="store_true",
+        help="Also inpaint the alpha channel of transparent images instead of keeping it untouched."
+    )
+    parser.add_argument(
+        "--strip-metadata",
+        action="store_true",
+        help="Do not copy EXIF/XMP metadata to the output. The ICC profile is always kept."
+    )
+    parser.add_argument(
+        "--jpeg-quality",
+        type=int,
         default=None,
-        help="Optional path to the LaMa model. (Currently ignored by placeholder)"
+        help="JPEG/WebP output quality (1-95). Defaults to the input's own quality for JPEG inputs."
     )
 
     args = parser.parse_args()
@@ -43,6 +226,9 @@ def main():
     if not os.path.exists(args.input):
         print(f"Error: Input image not found at {args.input}")
         sys.exit(1)
+    if args.mask and not os.path.exists(args.mask):
+        print(f"Error: Mask image not found at {args.mask}")
+        sys.exit(1)
 
     # Ensure output directory exists
     output_dir = os.path.dirname(args.output)
@@ -50,15 +236,74 @@ def main():
         os.makedirs(output_dir, exist_ok=True)
     
     try:
-        print(f"Initializing LaMaWrapper (model: {args.model_path if args.model_path else 'default/placeholder'})...")
-        lama_wrapper = LaMaWrapper(model_path=args.model_path)
+        refinement = MaskRefinement(dilate=args.mask_dilate, close=args.mask_close, min_area=args.mask_min_area,
+                                    fill_holes=args.mask_fill_holes, feather=args.mask_feather).validate()
+
+        print(f"Initializing backend '{args.backend}' (model: {args.model_path if args.model_path else 'none, classical fallback'})...")
+        backend = create_backend(
+            args.backend,
+            ignore_unknown_options=True,
+            **{
+                **settings.backend_options(),
+                "model_path": args.model_path,
+                "template_path": args.template,
+                "radius": args.inpaint_radius,
+                "fallback_radius": args.inpaint_radius,
+            },
+        )
+        backend = with_tiling(with_quality(backend, args.quality), args.tile_size, context_margin=args.tile_margin, feather=args.tile_feather)
         
+        mask = None
+        if args.mask:
+            with Image.open(args.input) as image:
+                mask = import_mask(args.mask, image.size, args.mask_format, image_name=args.input)
+        if args.color_key:
+            with Image.open(args.input) as image:
+                rgb = np.asarray(image.convert("RGB"))
+                keyed = color_mask(rgb, args.color_key, tolerance=args.color_tolerance, region=args.region)
+                mask = keyed if mask is None else np.maximum(load_mask(mask, image.size), keyed)
+        elif args.region:
+            with Image.open(args.input) as image:
+                mask = add_regions(mask, args.region, image.size)
+        if mask is None and args.match_templates is not None:
+            with Image.open(args.input) as image:
+                rgb = np.asarray(image.convert("RGB"))
+            mask, matches = template_mask(rgb, get_template_registry(), args.match_templates,
+                                          threshold=settings.template_match_threshold)
+            if not matches:
+                print("No registered template found in the image.")
+        elif mask is None and args.detect_text:
+            with Image.open(args.input) as image:
+                rgb = np.asarray(image.convert("RGB"))
+            mask = detect_text(rgb, corners=args.text_corners, colors=args.text_colors).mask
+
+        if args.export_mask:
+            with Image.open(args.input) as image:
+                # Resolve here so a detected mask is exported too; the engine then reuses it.
+                mask, confidence = backend.resolve_mask(image, mask, args.min_confidence)
+            export_format = args.export_mask_format or format_for_mask_path(args.export_mask)
+            export_dir = os.path.dirname(args.export_mask)
+            if export_dir:
+                os.makedirs(export_dir, exist_ok=True)
+            with open(args.export_mask, "wb") as f:
+                f.write(export_mask(mask, export_format, file_name=args.input, score=confidence))
+            print(f"Mask exported ({export_format}) to: {args.export_mask}")
+
         print(f"Processing image: {args.input} -> {args.output}")
-        processed_path = lama_wrapper.remove_watermark(args.input, args.output)
+        processed_path = backend.remove_watermark(
+            args.input,
+            args.output,
+            mask=mask,
+            inpaint_alpha=args.inpaint_alpha,
+            keep_metadata=not args.strip_metadata,
+            jpeg_quality=args.jpeg_quality,
+            min_confidence=args.min_confidence,
+            mask_refinement=refinement,
+        )
         
         print(f"Successfully processed image. Output saved to: {processed_path}")
 
-    except FileNotFoundError as e:
+    except (FileNotFoundError, ValueError) as e:
         print(f"Error: {e}")
         sys.exit(1)
     except Exception as e:
@@ -67,4 +312,3 @@ def main():
 
 if __name__ == "__main__":
     main()
-```
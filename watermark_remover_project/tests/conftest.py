@@ -0,0 +1,19 @@
+import os
+import sys
+
+# Make the watermark_remover package importable when running `pytest` from the project root.
+project_root = os.path.abspath(os.path.join(os.path.dirname(__file__), ".."))
+if project_root not in sys.path:
+    sys.path.insert(0, project_root)
+
+import pytest
+
+
+@pytest.fixture
+def tiny_generator():
+    """A randomly-initialised LaMa generator small enough to run on CPU in tests."""
+    torch = pytest.importorskip("torch")
+    from watermark_remover.core.lama_arch import FFCResNetGenerator
+
+    torch.manual_seed(0)
+    return FFCResNetGenerator(ngf=8, n_downsampling=2, n_blocks=1).eval()
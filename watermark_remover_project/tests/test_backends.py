@@ -0,0 +1,114 @@
+import sys
+
+import numpy as np
+import pytest
+
+from watermark_remover.core import backends
+from watermark_remover.core.backends import available_backends, create_backend, register_backend
+
+FAKE_MODULE = '''
+from watermark_remover.core.backends import InpaintingBackend
+
+
+class ConstantBackend(InpaintingBackend):
+    def __init__(self, value=0, radius=3):
+        self.value = value
+        self.radius = radius
+
+    def predict(self, image, mask):
+        result = image.copy()
+        result[mask > 0] = self.value
+        return result
+'''
+
+
+@pytest.fixture
+def registry(monkeypatch, tmp_path):
+    """A copy of the backend registry, plus an importable module that is not imported yet."""
+    monkeypatch.setattr(backends, "_REGISTRY", dict(backends._REGISTRY))
+    (tmp_path / "fake_backend_module.py").write_text(FAKE_MODULE)
+    monkeypatch.syspath_prepend(str(tmp_path))
+    monkeypatch.delitem(sys.modules, "fake_backend_module", raising=False)
+    yield backends._REGISTRY
+    sys.modules.pop("fake_backend_module", None)
+
+
+def test_import_path_is_resolved_on_first_use(registry):
+    register_backend("constant", "fake_backend_module:ConstantBackend", value=7)
+
+    assert "constant" in available_backends()
+    assert "fake_backend_module" not in sys.modules
+
+    backend = create_backend("constant")
+    assert "fake_backend_module" in sys.modules
+    assert backend.name == "constant"
+    assert backend.value == 7
+    # Caller options override the registered defaults.
+    assert create_backend("constant", value=9).value == 9
+
+
+def test_unknown_options(registry):
+    register_backend("constant", "fake_backend_module:ConstantBackend")
+
+    with pytest.raises(TypeError):
+        create_backend("constant", model_path="model.pt")
+    backend = create_backend("constant", ignore_unknown_options=True, model_path="model.pt", radius=5)
+    assert backend.radius == 5
+
+
+def test_unknown_backend(registry):
+    with pytest.raises(ValueError, match="Unknown inpainting backend 'nope'") as error:
+        create_backend("nope")
+    assert "opencv_telea" in str(error.value)
+
+
+def test_builtin_backends_registered():
+    assert {"lama", "lama_onnx", "opencv_telea", "opencv_ns", "patchmatch"} <= set(available_backends())
+
+
+def _stripes():
+    # Vertical stripes, 4 px of each colour: easy to continue by copying, hard to diffuse.
+    x = np.arange(64)
+    row = np.where((x // 4) % 2 == 0, 30, 190).astype(np.uint8)
+    return np.repeat(np.tile(row, (64, 1))[..., None], 3, axis=2)
+
+
+def test_patchmatch_continues_texture():
+    pytest.importorskip("cv2")
+    from watermark_remover.core.patchmatch import PatchMatchBackend
+
+    image = _stripes()
+    mask = np.zeros((64, 64), dtype=np.uint8)
+    mask[26:38, 26:38] = 255
+    damaged = image.copy()
+    damaged[mask > 0] = 255
+
+    result = PatchMatchBackend(seed=1).predict(damaged, mask)
+
+    assert result.shape == image.shape and result.dtype == np.uint8
+    np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
+    # Averaging (diffusion) would give ~110 everywhere, 80 off on average; copied stripes are close.
+    assert np.abs(result[mask > 0].astype(int) - image[mask > 0]).mean() < 40
+    # Same seed, same output.
+    np.testing.assert_array_equal(result, PatchMatchBackend(seed=1).predict(damaged, mask))
+
+
+def test_patchmatch_without_source_patches_falls_back_to_diffusion():
+    cv2 = pytest.importorskip("cv2")
+    from watermark_remover.core.patchmatch import PatchMatchBackend
+
+    image = _stripes()[:20, :20].copy()
+    mask = np.full((20, 20), 255, dtype=np.uint8)
+    mask[0, :] = 0
+
+    result = PatchMatchBackend().predict(image, mask)
+
+    np.testing.assert_array_equal(result, cv2.inpaint(image, mask, 3, cv2.INPAINT_TELEA))
+
+
+def test_patchmatch_rejects_even_patch_size():
+    pytest.importorskip("cv2")
+    from watermark_remover.core.patchmatch import PatchMatchBackend
+
+    with pytest.raises(ValueError, match="odd"):
+        PatchMatchBackend(patch_size=6)
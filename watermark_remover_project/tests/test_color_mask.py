@@ -0,0 +1,78 @@
+import numpy as np
+import pytest
+
+pytest.importorskip("cv2")
+
+from watermark_remover.core.color_mask import color_mask
+from watermark_remover.utils.colors import COLOR_PRESETS, lab_distance, parse_color, to_lab
+
+
+def test_parse_color():
+    assert parse_color("Orange") == COLOR_PRESETS["orange"]
+    assert parse_color(" #D01020 ") == (208, 16, 32)
+    assert parse_color([255, 0, 128]) == (255, 0, 128)
+    assert parse_color(np.uint8([1, 2, 3])) == (1, 2, 3)
+
+
+@pytest.mark.parametrize("color, message", [
+    ("mauve", "Unknown colour"),
+    ("#12345", "Unknown colour"),
+    ("#gg0000", "Unknown colour"),
+    ((255, 255), "3 components"),
+    ((300, 0, 0), "0-255"),
+    ((0, -1, 0), "0-255"),
+    ((0, "red", 0), "integers"),
+])
+def test_parse_color_rejects_invalid(color, message):
+    with pytest.raises(ValueError, match=message):
+        parse_color(color)
+
+
+def test_lab_distance():
+    lab = to_lab(np.uint8([[255, 255, 255], [0, 0, 0]]))
+    np.testing.assert_allclose(lab[0], [100, 0, 0], atol=1)
+    np.testing.assert_allclose(lab[1], [0, 0, 0], atol=1)
+
+    distances = lab_distance(np.uint8([[255, 255, 255], [250, 250, 250], [200, 200, 200]]), (255, 255, 255))
+    assert distances[0] == pytest.approx(0, abs=1e-3)
+    assert distances[1] < 5 < 15 < distances[2]
+
+
+def _image():
+    """Grey image with a white block on the left and a near-white block on the right."""
+    image = np.full((40, 80, 3), 100, dtype=np.uint8)
+    image[10:30, 10:30] = 255
+    image[10:30, 50:70] = 245
+    return image
+
+
+def test_tolerance_selects_similar_colours():
+    strict = color_mask(_image(), ["white"], tolerance=1, grow=0)
+    loose = color_mask(_image(), ["#ffffff"], tolerance=12, grow=0)
+
+    assert np.count_nonzero(strict) == 400 and strict[20, 20] == 255
+    assert np.count_nonzero(loose) == 800
+    assert (color_mask(_image(), [(100, 100, 100)], tolerance=1, grow=0) > 0).sum() == 40 * 80 - 800
+
+
+def test_region_restricts_selection_and_growth():
+    region = np.zeros((40, 80), dtype=np.uint8)
+    region[:, :40] = 255
+
+    mask = color_mask(_image(), ["white"], tolerance=12, region=region, grow=2)
+
+    assert mask[20, 60] == 0
+    assert mask[20, 8] == 255 and mask[20, 7] == 0
+    assert not mask[:, 40:].any()
+    relative = color_mask(_image(), ["white"], region={"type": "rect", "units": "relative", "x": 0, "y": 0,
+                                                       "width": 0.5, "height": 1}, grow=0)
+    assert np.count_nonzero(relative) == 400
+
+
+def test_color_mask_rejects_invalid_input():
+    with pytest.raises(ValueError, match="at least one colour"):
+        color_mask(_image(), [])
+    with pytest.raises(ValueError, match="must not be negative"):
+        color_mask(_image(), ["white"], tolerance=-1)
+    with pytest.raises(ValueError, match="does not match image size"):
+        color_mask(_image(), ["white"], region=np.zeros((10, 10), dtype=np.uint8))
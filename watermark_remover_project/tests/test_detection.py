@@ -0,0 +1,82 @@
+import tracemalloc
+
+import numpy as np
+import pytest
+
+cv2 = pytest.importorskip("cv2")
+
+from watermark_remover.core.detection import DetectionResult, detect_watermark
+
+
+def _background(height=256, width=384):
+    """A smooth colourful gradient: no small structures, no text."""
+    y, x = np.mgrid[0:height, 0:width].astype(np.float32)
+    return np.stack([60 + 100 * x / width, 80 + 60 * y / height, 170 - 80 * x / width], axis=-1).astype(np.uint8)
+
+
+def _stamped():
+    image = _background()
+    cv2.putText(image, "SAMPLE", (40, 200), cv2.FONT_HERSHEY_SIMPLEX, 1.5, (255, 255, 255), 3, cv2.LINE_AA)
+    text = (np.abs(image.astype(int) - _background()).max(axis=2) > 60)
+    return image, text
+
+
+def test_clean_image_is_not_detected():
+    result = detect_watermark(_background(), periodic=False)
+
+    assert not result.detected
+    assert result.confidence < 0.35
+    assert result.mask.shape == (256, 384)
+
+
+def test_text_stamp_is_detected():
+    image, text = _stamped()
+
+    result = detect_watermark(image, periodic=False)
+
+    assert result.detected
+    assert 0 <= result.confidence <= 1
+    assert set(result.cues) == {"contrast", "strokes", "overlay"}
+    inside = result.mask > 0
+    assert inside[text].mean() > 0.8
+    # The mask stays around the text rather than spreading over the image.
+    assert inside.mean() < 0.2
+
+
+def test_mask_returned_at_input_size():
+    image, _ = _stamped()
+    large = cv2.resize(image, (1152, 768), interpolation=cv2.INTER_NEAREST)
+
+    result = detect_watermark(large, max_side=384, periodic=False)
+
+    assert result.mask.shape == (768, 1152)
+    assert result.mask.dtype == np.uint8
+    assert set(np.unique(result.mask)) <= {0, 255}
+
+
+def test_min_confidence_decides_detected():
+    image, _ = _stamped()
+
+    assert not detect_watermark(image, min_confidence=1.01, periodic=False).detected
+    # A confident but empty proposal is never "detected".
+    assert not DetectionResult(mask=np.zeros((4, 4), dtype=np.uint8), confidence=0.9).detected
+
+
+def test_large_image_detection_stays_within_bounded_memory():
+    # A 4000x3000 scan tiled with a cross every 100 px: the periodic detector finds its lattice.
+    cell = np.full((100, 100, 3), 90, dtype=np.uint8)
+    cell[45:55, 20:80] = 230
+    cell[20:80, 45:55] = 230
+    image = np.tile(cell, (30, 40, 1))
+
+    tracemalloc.start()
+    try:
+        result = detect_watermark(image)
+        _, peak = tracemalloc.get_traced_memory()
+    finally:
+        tracemalloc.stop()
+
+    assert "periodic" in result.cues
+    assert result.mask.shape == (3000, 4000)
+    # Full-resolution float64 working copies would need several times the 36 MB image.
+    assert peak < 4 * image.nbytes
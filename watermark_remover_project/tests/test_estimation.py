@@ -0,0 +1,76 @@
+import numpy as np
+import pytest
+
+cv2 = pytest.importorskip("cv2")
+
+from watermark_remover.core.estimation import WatermarkTemplate, estimate_watermark, poisson_reconstruct
+
+ALPHA = 0.5
+# (x, y, width, height) of the logo in every image.
+LOGO = (48, 32, 40, 24)
+
+
+def _logo_mask(height=96, width=128):
+    x, y, w, h = LOGO
+    mask = np.zeros((height, width), dtype=bool)
+    mask[y:y + h, x:x + w] = True
+    mask[y + 5:y + h - 5, x + 5:x + w - 5] = False
+    return mask
+
+
+def _photo_set(count=8):
+    rng = np.random.default_rng(3)
+    logo = _logo_mask()
+    images = []
+    for _ in range(count):
+        photo = cv2.GaussianBlur(rng.integers(0, 256, size=(96, 128, 3), dtype=np.uint8), (0, 0), 1.5)
+        marked = photo.astype(np.float32)
+        marked[logo] = ALPHA * 255 + (1 - ALPHA) * marked[logo]
+        images.append(np.rint(marked).astype(np.uint8))
+    return images
+
+
+def test_poisson_reconstruct_recovers_image():
+    rng = np.random.default_rng(0)
+    image = np.zeros((20, 24))
+    image[1:-1, 1:-1] = rng.normal(size=(18, 22))
+    gx = np.zeros_like(image)
+    gy = np.zeros_like(image)
+    gx[:, :-1] = image[:, 1:] - image[:, :-1]
+    gy[:-1] = image[1:] - image[:-1]
+
+    np.testing.assert_allclose(poisson_reconstruct(gx, gy), image, atol=1e-8)
+
+
+def test_estimates_shared_watermark(tmp_path):
+    images = _photo_set()
+
+    result = estimate_watermark(images)
+
+    logo = _logo_mask()
+    assert len(set(result.positions)) == 1
+    assert min(result.scores) > 0.5
+    for mask in result.masks:
+        assert mask.shape == logo.shape
+        assert (mask[logo] > 0).mean() > 0.9
+    # Opacity recovered from how much the logo damps the variation between photos.
+    template = result.template
+    x, y = result.positions[0]
+    alpha = np.zeros(logo.shape, dtype=np.float32)
+    alpha[y:y + template.alpha.shape[0], x:x + template.alpha.shape[1]] = template.alpha
+    assert abs(float(np.median(alpha[logo])) - ALPHA) < 0.2
+
+    path = template.save(str(tmp_path / "template.npz"))
+    loaded = WatermarkTemplate.load(path)
+    np.testing.assert_array_equal(loaded.mask, template.mask)
+    np.testing.assert_array_equal(loaded.alpha, template.alpha)
+    assert loaded.size == template.size
+
+
+def test_rejects_bad_input():
+    images = _photo_set(3)
+
+    with pytest.raises(ValueError, match="at least 3"):
+        estimate_watermark(images[:2])
+    with pytest.raises(ValueError, match="Unknown anchor"):
+        estimate_watermark(images, anchor="middle")
@@ -0,0 +1,192 @@
+import io
+
+import numpy as np
+import pytest
+from PIL import Image, ImageCms
+
+from watermark_remover.core.backends import InpaintingBackend
+
+FILL_COLOR = (10, 200, 30)
+
+
+class FillBackend(InpaintingBackend):
+    """Paints masked pixels a constant colour, so expected outputs are easy to compute."""
+
+    def predict(self, image, mask):
+        result = image.copy()
+        result[mask > 0] = FILL_COLOR
+        return result
+
+
+@pytest.fixture
+def mask():
+    mask = np.zeros((24, 32), dtype=np.uint8)
+    mask[4:12, 6:20] = 255
+    return mask
+
+
+def _gradient(channels, dtype=np.uint8, scale=1):
+    y, x = np.mgrid[0:24, 0:32]
+    planes = [((x * 7 + y * 3 + 50 * c) % 256) * scale for c in range(channels)]
+    data = np.stack(planes, axis=-1).astype(dtype)
+    return data[..., 0] if channels == 1 else data
+
+
+def _process(tmp_path, image, mask, name="input.png", output_name=None, save_params=None, **kwargs):
+    input_path = tmp_path / name
+    image.save(input_path, **(save_params or {}))
+    output_path = tmp_path / (output_name or f"output{input_path.suffix}")
+    FillBackend().remove_watermark(str(input_path), str(output_path), mask=mask, **kwargs)
+    return Image.open(output_path)
+
+
+def test_rgb(tmp_path, mask):
+    data = _gradient(3)
+    result = _process(tmp_path, Image.fromarray(data), mask)
+
+    assert result.mode == "RGB"
+    expected = data.copy()
+    expected[mask > 0] = FILL_COLOR
+    np.testing.assert_array_equal(np.asarray(result), expected)
+
+
+def test_rgba_keeps_alpha_untouched(tmp_path, mask):
+    data = _gradient(4)
+    result = _process(tmp_path, Image.fromarray(data), mask)
+
+    assert result.mode == "RGBA"
+    result = np.asarray(result)
+    np.testing.assert_array_equal(result[..., 3], data[..., 3])
+    np.testing.assert_array_equal(result[mask > 0][:, :3], np.tile(FILL_COLOR, (int((mask > 0).sum()), 1)))
+    np.testing.assert_array_equal(result[mask == 0], data[mask == 0])
+
+
+def test_rgba_inpaint_alpha(tmp_path, mask):
+    data = _gradient(4)
+    result = np.asarray(_process(tmp_path, Image.fromarray(data), mask, inpaint_alpha=True))
+
+    # The alpha plane went through the engine too: its first channel is FILL_COLOR[0].
+    assert (result[..., 3][mask > 0] == FILL_COLOR[0]).all()
+    np.testing.assert_array_equal(result[..., 3][mask == 0], data[..., 3][mask == 0])
+
+
+def test_grayscale(tmp_path, mask):
+    data = _gradient(1)
+    result = _process(tmp_path, Image.fromarray(data), mask)
+
+    assert result.mode == "L"
+    result = np.asarray(result)
+    np.testing.assert_array_equal(result[mask == 0], data[mask == 0])
+    fill_gray = np.asarray(Image.new("RGB", (1, 1), FILL_COLOR).convert("L"))[0, 0]
+    assert (result[mask > 0] == fill_gray).all()
+
+
+def test_palette(tmp_path, mask):
+    source = Image.fromarray(_gradient(3)).quantize(colors=16)
+    result = _process(tmp_path, source, mask)
+
+    assert result.mode == "P"
+    assert result.getpalette() == source.getpalette()
+    np.testing.assert_array_equal(np.asarray(result)[mask == 0], np.asarray(source)[mask == 0])
+
+
+def test_palette_with_transparency(tmp_path, mask):
+    source = Image.fromarray(_gradient(3)).quantize(colors=16)
+    source.putpixel((7, 5), 3)
+    source.info["transparency"] = 3
+
+    result = _process(tmp_path, source, mask, save_params={"transparency": 3})
+
+    assert result.mode == "P"
+    assert result.info.get("transparency") == 3
+    # A transparent pixel under the mask stays transparent when alpha is not inpainted.
+    assert np.asarray(result)[5, 7] == 3
+
+
+def test_sixteen_bit(tmp_path, mask):
+    data = _gradient(1, dtype=np.uint16, scale=257)
+    source = Image.fromarray(data)
+    result = _process(tmp_path, source, mask)
+
+    assert result.mode in ("I;16", "I")
+    result = np.asarray(result).astype(np.int64)
+    # Unmasked pixels keep their full 16-bit precision.
+    np.testing.assert_array_equal(result[mask == 0], data[mask == 0])
+    fill_gray = int(np.asarray(Image.new("RGB", (1, 1), FILL_COLOR).convert("L"))[0, 0])
+    assert (result[mask > 0] == fill_gray * 257).all()
+
+
+def test_cmyk_jpeg(tmp_path, mask):
+    source = Image.fromarray(_gradient(3)).convert("CMYK")
+    result = _process(tmp_path, source, mask, name="input.jpg")
+
+    assert result.mode == "CMYK"
+    assert result.size == source.size
+
+
+def test_icc_profile_is_embedded(tmp_path, mask):
+    icc_profile = ImageCms.ImageCmsProfile(ImageCms.createProfile("sRGB")).tobytes()
+    result = _process(tmp_path, Image.fromarray(_gradient(3)), mask, save_params={"icc_profile": icc_profile},
+                      keep_metadata=False)
+
+    assert result.info.get("icc_profile") == icc_profile
+
+
+def test_exif_is_optional(tmp_path, mask):
+    exif = Image.Exif()
+    exif[0x010F] = "TestMake"  # Make
+    params = {"exif": exif.tobytes(), "quality": 80}
+
+    kept = _process(tmp_path, Image.fromarray(_gradient(3)), mask, name="input.jpg", save_params=params)
+    assert kept.getexif().get(0x010F) == "TestMake"
+
+    stripped = _process(tmp_path, Image.fromarray(_gradient(3)), mask, name="input.jpg",
+                        output_name="stripped.jpg", save_params=params, keep_metadata=False)
+    assert 0x010F not in stripped.getexif()
+
+
+def test_jpeg_quality_is_preserved(tmp_path, mask):
+    result = _process(tmp_path, Image.fromarray(_gradient(3)), mask, name="input.jpg",
+                      save_params={"quality": 30})
+
+    with Image.open(tmp_path / "input.jpg") as source:
+        assert result.quantization == source.quantization
+
+
+def test_explicit_jpeg_quality(tmp_path, mask):
+    low = _process(tmp_path, Image.fromarray(_gradient(3)), mask, name="input.jpg",
+                   output_name="low.jpg", save_params={"quality": 95}, jpeg_quality=20)
+
+    buffer = io.BytesIO()
+    Image.fromarray(_gradient(3)).save(buffer, "JPEG", quality=20)
+    assert low.quantization == Image.open(buffer).quantization
+
+
+def test_inpaint_in_memory_inputs(mask):
+    data = _gradient(3)
+    buffer = io.BytesIO()
+    Image.fromarray(data).save(buffer, "PNG")
+    mask_buffer = io.BytesIO()
+    Image.fromarray(mask).save(mask_buffer, "PNG")
+    expected = data.copy()
+    expected[mask > 0] = FILL_COLOR
+
+    for image_input in (Image.fromarray(data), data, buffer.getvalue()):
+        for mask_input in (mask, mask_buffer.getvalue()):
+            result = FillBackend().inpaint(image_input, mask_input)
+            assert isinstance(result, Image.Image)
+            np.testing.assert_array_equal(np.asarray(result), expected)
+
+
+def test_inpaint_rejects_undecodable_bytes(mask):
+    with pytest.raises(ValueError, match="decode"):
+        FillBackend().inpaint(b"not an image", mask)
+
+
+def test_load_mask_thresholds_at_mid_grey():
+    from watermark_remover.utils.masks import load_mask
+
+    edge = np.array([[0, 127, 128, 255]], dtype=np.uint8)
+    np.testing.assert_array_equal(load_mask(edge, (4, 1)), [[0, 0, 255, 255]])
+    # Non-uint8 masks keep every non-zero pixel.
+    np.testing.assert_array_equal(load_mask(edge.astype(np.float32) / 255, (4, 1)), [[0, 255, 255, 255]])
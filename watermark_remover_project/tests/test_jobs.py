@@ -0,0 +1,189 @@
+import io
+import json
+import os
+import zipfile
+
+import numpy as np
+import pytest
+from PIL import Image
+
+pytest.importorskip("cv2")
+pytest.importorskip("fastapi")
+pytest.importorskip("celery")
+pytest.importorskip("httpx")
+from fastapi.testclient import TestClient
+
+from watermark_remover.api.main import _attachment, app
+from watermark_remover.config import settings
+from watermark_remover.tasks.celery_app import celery_app
+from watermark_remover.tasks.processing import remove_watermark_task
+
+BACKGROUND = (120, 120, 120)
+
+
+@pytest.fixture
+def client(tmp_path, monkeypatch):
+    """API client whose jobs run eagerly, in-process, against a temporary job folder."""
+    monkeypatch.setattr(settings, "jobs_dir", str(tmp_path / "jobs"))
+    previous = celery_app.conf.task_always_eager
+    celery_app.conf.task_always_eager = True
+    yield TestClient(app)
+    celery_app.conf.task_always_eager = previous
+
+
+def _png(array):
+    buffer = io.BytesIO()
+    Image.fromarray(array).save(buffer, format="PNG")
+    return buffer.getvalue()
+
+
+@pytest.fixture
+def upload():
+    image = np.full((24, 32, 3), BACKGROUND, dtype=np.uint8)
+    image[8:16, 10:20] = (220, 30, 30)
+    mask = np.zeros((24, 32), dtype=np.uint8)
+    mask[6:18, 8:22] = 255
+    return _png(image), _png(mask)
+
+
+def _submit(client, image, mask, **data):
+    files = {"file": ("photo.png", image, "image/png"), "mask": ("mask.png", mask, "image/png")}
+    return client.post("/v1/jobs", files=files, data={"backend": "opencv_telea", **data})
+
+
+def test_job_runs_and_returns_result(client, upload):
+    response = _submit(client, *upload)
+    assert response.status_code == 202
+    job_id = response.json()["id"]
+
+    status = client.get(f"/v1/jobs/{job_id}").json()
+    assert status["status"] == "succeeded"
+    assert status["progress"] == 1.0
+    assert status["result_url"] == f"/v1/jobs/{job_id}/result"
+
+    result = client.get(f"/v1/jobs/{job_id}/result")
+    assert result.status_code == 200
+    assert result.headers["content-type"] == "image/png"
+    output = np.asarray(Image.open(io.BytesIO(result.content)).convert("RGB")).astype(int)
+    assert output.shape == (24, 32, 3)
+    # The red square is filled from the uniform background.
+    assert np.abs(output[8:16, 10:20] - BACKGROUND).max() < 10
+
+
+def test_job_result_with_non_latin_name(client, upload):
+    image, mask = upload
+    files = {"file": ("照片.png", image, "image/png"), "mask": ("mask.png", mask, "image/png")}
+    job_id = client.post("/v1/jobs", files=files, data={"backend": "opencv_telea"}).json()["id"]
+
+    result = client.get(f"/v1/jobs/{job_id}/result")
+
+    assert result.status_code == 200
+    assert result.headers["content-disposition"] == "attachment; filename*=utf-8''%E7%85%A7%E7%89%87.png"
+
+
+def test_failed_job_reports_error(client, upload):
+    image, _ = upload
+    wrong_size_mask = _png(np.full((10, 10), 255, dtype=np.uint8))
+    job_id = _submit(client, image, wrong_size_mask).json()["id"]
+
+    status = client.get(f"/v1/jobs/{job_id}").json()
+    assert status["status"] == "failed"
+    assert "does not match" in status["error"]
+    assert client.get(f"/v1/jobs/{job_id}/result").status_code == 409
+
+
+def test_invalid_options_are_rejected_before_queueing(client, upload):
+    response = _submit(client, *upload, quality="ultra")
+    assert response.status_code == 400
+    assert not os.path.exists(settings.jobs_dir) or not os.listdir(settings.jobs_dir)
+
+
+def test_undecodable_upload_is_rejected(client, upload):
+    _, mask = upload
+    assert _submit(client, b"not an image", mask).status_code == 400
+
+
+@pytest.mark.parametrize("job_id", ["0" * 32, "..%2Fetc", "unknown"])
+def test_unknown_job(client, job_id):
+    assert client.get(f"/v1/jobs/{job_id}").status_code == 404
+    assert client.get(f"/v1/jobs/{job_id}/result").status_code == 404
+
+
+def test_deleted_job_fails_without_raising(client):
+    # A job deleted while queued must not fail the task, or a batch's chord callback never runs.
+    state = remove_watermark_task.delay("0" * 32).get()
+
+    assert state["status"] == "failed"
+    assert "not found" in state["error"].lower()
+
+
+def _zip(entries):
+    buffer = io.BytesIO()
+    with zipfile.ZipFile(buffer, "w") as archive:
+        for name, data in entries.items():
+            archive.writestr(name, data)
+    return buffer.getvalue()
+
+
+def test_batch_of_files_and_archive(client, upload):
+    image, _ = upload
+    archive = _zip({"set/a.png": image, "set/readme.txt": b"hello", "__MACOSX/set/._a.png": b"junk"})
+    files = [
+        ("files", ("photo.png", image, "image/png")),
+        ("files", ("broken.png", b"not an image", "image/png")),
+        ("files", ("more.zip", archive, "application/zip")),
+    ]
+    # A region mask applies to every image of the batch.
+    region = json.dumps({"type": "rect", "x": 8, "y": 6, "width": 14, "height": 12})
+    response = client.post("/v1/batches", files=files, data={"backend": "opencv_telea", "region": region})
+    assert response.status_code == 202
+    batch_id = response.json()["id"]
+
+    status = client.get(f"/v1/batches/{batch_id}").json()
+    assert status["status"] == "succeeded"
+    assert status["progress"] == 1.0
+    by_name = {entry["filename"]: entry for entry in status["files"]}
+    assert by_name["photo.png"]["status"] == "succeeded"
+    assert by_name["broken.png"]["status"] == "failed"
+    assert by_name["set/readme.txt"]["status"] == "skipped"
+
+    result = client.get(f"/v1/batches/{batch_id}/result")
+    assert result.status_code == 200
+    assert result.headers["content-type"] == "application/zip"
+    with zipfile.ZipFile(io.BytesIO(result.content)) as archive:
+        assert sorted(archive.namelist()) == ["manifest.json", "photo.png", "set/a.png"]
+        manifest = json.loads(archive.read("manifest.json"))
+        output = np.asarray(Image.open(io.BytesIO(archive.read("set/a.png"))).convert("RGB")).astype(int)
+    assert (manifest["succeeded"], manifest["failed"], manifest["skipped"]) == (2, 1, 1)
+    assert "broken.png" in [entry["filename"] for entry in manifest["files"] if entry["error"]]
+    assert np.abs(output[8:16, 10:20] - BACKGROUND).max() < 10
+
+
+def test_batch_without_images_is_rejected(client):
+    files = [("files", ("notes.txt", b"hello", "text/plain"))]
+    assert client.post("/v1/batches", files=files).status_code == 400
+
+
+def test_unknown_batch(client):
+    assert client.get(f"/v1/batches/{'0' * 32}").status_code == 404
+    assert client.get(f"/v1/batches/{'0' * 32}/result").status_code == 404
+
+
+@pytest.mark.parametrize("filename, disposition", [
+    ("photo.png", 'attachment; filename="photo.png"'),
+    ("照片.png", "attachment; filename*=utf-8''%E7%85%A7%E7%89%87.png"),
+    ('a"b\r\nX-Injected: 1.png', "attachment; filename*=utf-8''a%22b%0D%0AX-Injected%3A%201.png"),
+    (None, "attachment"),
+])
+def test_attachment_header(filename, disposition):
+    assert _attachment(filename) == disposition
+
+
+def test_single_image_download_with_non_latin_name(client, upload):
+    image, mask = upload
+    files = {"file": ("照片.png", image, "image/png"), "mask": ("mask.png", mask, "image/png")}
+
+    response = client.post("/v1/remove_watermark_single/", files=files, data={"backend": "opencv_telea"})
+
+    assert response.status_code == 200
+    assert response.headers["content-disposition"] == "attachment; filename*=utf-8''%E7%85%A7%E7%89%87.png"
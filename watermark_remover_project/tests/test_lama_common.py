@@ -0,0 +1,60 @@
+import numpy as np
+import pytest
+
+from watermark_remover.core.lama_common import BUCKET_MODULO, MEMORY_BYTES_PER_PIXEL, predict_bucketed
+
+SIZES = [(30, 40), (100, 70), (50, 60), (120, 128), (60, 64)]
+
+
+@pytest.fixture
+def images_and_masks():
+    # Each image is a distinct flat colour, so results can be matched to their inputs.
+    images = [np.full((height, width, 3), 20 + 40 * index, dtype=np.uint8)
+              for index, (height, width) in enumerate(SIZES)]
+    masks = []
+    for height, width in SIZES:
+        mask = np.zeros((height, width), dtype=np.uint8)
+        mask[height // 4:height // 2, width // 4:width // 2] = 255
+        masks.append(mask)
+    return images, masks
+
+
+class InvertingModel:
+    """Stands in for LaMa: returns the inverted input and records each batch's shape."""
+
+    def __init__(self):
+        self.batches = []
+
+    def __call__(self, image_batch, mask_batch):
+        assert image_batch.shape[0] == mask_batch.shape[0]
+        assert image_batch.shape[2:] == mask_batch.shape[2:]
+        self.batches.append(image_batch.shape)
+        return 1.0 - image_batch
+
+
+def test_results_in_input_order_with_original_sizes(images_and_masks):
+    images, masks = images_and_masks
+    model = InvertingModel()
+
+    results = predict_bucketed(images, masks, model, max_batch_size=2)
+
+    for image, mask, result in zip(images, masks, results):
+        assert result.shape == image.shape
+        assert result.dtype == np.uint8
+        np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
+        np.testing.assert_array_equal(result[mask > 0], 255 - image[mask > 0])
+    # Two buckets: 64x64 (three images, batches of two) and 128x128 (two images).
+    assert sorted(model.batches) == [(1, 3, 64, 64), (2, 3, 64, 64), (2, 3, 128, 128)]
+
+
+def test_memory_budget_limits_batch_size(images_and_masks):
+    images, masks = images_and_masks
+    model = InvertingModel()
+    # Room for one 64x64 image per pass, but not two.
+    budget_mb = 1.5 * BUCKET_MODULO * BUCKET_MODULO * MEMORY_BYTES_PER_PIXEL / (1024 * 1024)
+
+    results = predict_bucketed(images, masks, model, max_batch_size=4, memory_budget_mb=int(budget_mb) + 1)
+
+    assert len(results) == len(images)
+    # 128x128 images exceed the budget but still run, alone.
+    assert [shape[0] for shape in model.batches] == [1] * len(images)
@@ -0,0 +1,137 @@
+import numpy as np
+import pytest
+from PIL import Image
+
+torch = pytest.importorskip("torch")
+
+from watermark_remover.core.lama_arch import LaMaInpaintModel
+from watermark_remover.core.lama_common import pad_to_modulo
+from watermark_remover.core.lama_wrapper import LaMaWrapper
+
+
+@pytest.fixture
+def image_and_mask():
+    rng = np.random.default_rng(0)
+    # Deliberately not a multiple of 8 to exercise padding.
+    image = rng.integers(0, 256, size=(30, 21, 3), dtype=np.uint8)
+    mask = np.zeros((30, 21), dtype=np.uint8)
+    mask[10:20, 5:15] = 255
+    return image, mask
+
+
+@pytest.fixture
+def checkpoint_path(tiny_generator, tmp_path):
+    # Same layout as a LaMa Lightning checkpoint: generator weights under "generator.".
+    state_dict = {f"generator.{key}": value for key, value in tiny_generator.state_dict().items()}
+    path = tmp_path / "tiny-lama.ckpt"
+    torch.save({"state_dict": state_dict}, path)
+    return str(path)
+
+
+def test_pad_to_modulo():
+    padded = pad_to_modulo(np.zeros((30, 21, 3)))
+    assert padded.shape == (32, 24, 3)
+    assert pad_to_modulo(np.zeros((16, 8))).shape == (16, 8)
+
+
+def test_predict_from_checkpoint(tiny_generator, checkpoint_path, image_and_mask):
+    image, mask = image_and_mask
+    wrapper = LaMaWrapper(model_path=checkpoint_path)
+
+    result = wrapper.predict(image, mask)
+
+    assert result.shape == image.shape
+    assert result.dtype == np.uint8
+    np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
+
+    # Matches running the generator directly on the padded input.
+    image_tensor = torch.from_numpy(pad_to_modulo(image)).permute(2, 0, 1)[None].float() / 255
+    mask_tensor = torch.from_numpy(pad_to_modulo((mask > 0).astype(np.float32)))[None, None]
+    with torch.no_grad():
+        expected = LaMaInpaintModel(tiny_generator)(image_tensor, mask_tensor)
+    expected = (expected[0].permute(1, 2, 0).clamp(0, 1) * 255).round().byte().numpy()[:30, :21]
+    assert np.abs(result[mask > 0].astype(int) - expected[mask > 0].astype(int)).max() <= 1
+
+
+def test_predict_from_torchscript(tiny_generator, checkpoint_path, image_and_mask, tmp_path):
+    image, mask = image_and_mask
+    model = LaMaInpaintModel(tiny_generator).eval()
+    example = (torch.rand(1, 3, 32, 24), torch.zeros(1, 1, 32, 24))
+    script_path = str(tmp_path / "tiny-lama.pt")
+    torch.jit.trace(model, example).save(script_path)
+
+    scripted = LaMaWrapper(model_path=script_path).predict(image, mask)
+    eager = LaMaWrapper(model_path=checkpoint_path).predict(image, mask)
+
+    assert np.abs(scripted.astype(int) - eager.astype(int)).max() <= 1
+
+
+def test_remove_watermark_writes_rgb_of_original_size(checkpoint_path, image_and_mask, tmp_path):
+    image, _ = image_and_mask
+    input_path = tmp_path / "input.png"
+    output_path = tmp_path / "out" / "output.png"
+    Image.fromarray(image).save(input_path)
+
+    result_path = LaMaWrapper(model_path=checkpoint_path).remove_watermark(str(input_path), str(output_path))
+
+    with Image.open(result_path) as result:
+        assert result.mode == "RGB"
+        assert result.size == (21, 30)
+
+
+def test_pickled_checkpoint_needs_opt_in(tiny_generator, tmp_path, image_and_mask):
+    import argparse
+
+    # Lightning checkpoints pickle their hyper-parameters, which the weights-only unpickler rejects.
+    path = str(tmp_path / "lightning.ckpt")
+    torch.save({"state_dict": tiny_generator.state_dict(), "hyper_parameters": argparse.Namespace(lr=0.001)}, path)
+
+    with pytest.raises(ValueError, match="LAMA_ALLOW_PICKLE"):
+        LaMaWrapper(model_path=path)
+
+    image, mask = image_and_mask
+    assert LaMaWrapper(model_path=path, allow_pickle=True).predict(image, mask).shape == image.shape
+
+
+def test_missing_model_path_raises(tmp_path):
+    with pytest.raises(FileNotFoundError):
+        LaMaWrapper(model_path=str(tmp_path / "missing.pt"))
+
+
+def test_remove_watermark_with_mask(checkpoint_path, image_and_mask, tmp_path):
+    image, mask = image_and_mask
+    input_path = tmp_path / "input.png"
+    mask_path = tmp_path / "mask.png"
+    Image.fromarray(image).save(input_path)
+    Image.fromarray(mask).save(mask_path)
+    wrapper = LaMaWrapper(model_path=checkpoint_path)
+
+    for mask_input in (str(mask_path), mask, Image.fromarray(mask)):
+        output_path = tmp_path / "output.png"
+        wrapper.remove_watermark(str(input_path), str(output_path), mask=mask_input)
+        result = np.asarray(Image.open(output_path))
+        np.testing.assert_array_equal(result, wrapper.predict(image, mask))
+
+
+def test_remove_watermark_rejects_mismatched_mask(checkpoint_path, image_and_mask, tmp_path):
+    image, _ = image_and_mask
+    input_path = tmp_path / "input.png"
+    Image.fromarray(image).save(input_path)
+
+    with pytest.raises(ValueError, match="does not match"):
+        LaMaWrapper(model_path=checkpoint_path).remove_watermark(
+            str(input_path), str(tmp_path / "output.png"), mask=np.zeros((10, 10), dtype=np.uint8)
+        )
+
+
+def test_falls_back_to_opencv_without_weights(image_and_mask):
+    pytest.importorskip("cv2")
+    from watermark_remover.core.opencv_backend import OpenCVBackend
+
+    image, mask = image_and_mask
+    wrapper = LaMaWrapper(fallback_method="ns", fallback_radius=5)
+
+    result = wrapper.predict(image, mask)
+
+    np.testing.assert_array_equal(result, OpenCVBackend(method="ns", radius=5).predict(image, mask))
+    np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
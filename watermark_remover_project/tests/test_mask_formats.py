@@ -0,0 +1,234 @@
+import io
+import json
+
+import numpy as np
+import pytest
+from PIL import Image
+
+pytest.importorskip("cv2")
+
+from watermark_remover.utils.mask_formats import (decode_rle, detect_mask_format, encode_rle, export_mask,
+                                                  import_mask, is_voc_image, parse_svg_path, read_coco, read_svg,
+                                                  read_voc, to_coco, to_svg, to_voc_png)
+
+SIZE = (40, 30)
+
+
+def _ring():
+    mask = np.zeros((30, 40), dtype=np.uint8)
+    mask[5:25, 8:32] = 255
+    mask[11:19, 14:26] = 0
+    return mask
+
+
+def _speckled():
+    rng = np.random.default_rng(0)
+    mask = (rng.random((30, 40)) > 0.7).astype(np.uint8) * 255
+    mask[0, 0] = 255
+    return mask
+
+
+@pytest.mark.parametrize("compress", [True, False])
+def test_rle_round_trip(compress):
+    for mask in (_ring(), _speckled(), np.zeros((30, 40), dtype=np.uint8)):
+        rle = encode_rle(mask, compress=compress)
+
+        assert rle["size"] == [30, 40]
+        np.testing.assert_array_equal(decode_rle(rle, SIZE), mask)
+
+
+def test_rle_starting_on_foreground():
+    mask = _speckled()
+
+    counts = encode_rle(mask, compress=False)["counts"]
+
+    # Runs always start with background, so a leading foreground pixel gives an empty first run.
+    assert counts[0] == 0 and counts[1] >= 1
+    np.testing.assert_array_equal(decode_rle(encode_rle(mask)), mask)
+    np.testing.assert_array_equal(decode_rle({"size": [2, 2], "counts": [0, 1, 3]}), [[255, 0], [0, 0]])
+
+
+@pytest.mark.parametrize("rle, message", [
+    ({"counts": [4]}, "needs 'size'"),
+    ({"size": [2, 2]}, "needs 'size'"),
+    ({"size": [2, 2], "counts": [1, 2]}, "cover 3 pixels, expected 4"),
+    ({"size": [2, 2], "counts": [5, -1]}, "cover"),
+    ({"size": [2, 2], "counts": "4n"}, "Truncated"),
+])
+def test_decode_rle_rejects_malformed(rle, message):
+    with pytest.raises(ValueError, match=message):
+        decode_rle(rle)
+
+
+def test_rle_size_checked_before_decoding():
+    # 60 bytes that would otherwise allocate a 10 GB mask.
+    bomb = {"size": [100000, 100000], "counts": [10000000000]}
+
+    with pytest.raises(ValueError, match="does not match image size 40x30"):
+        decode_rle(bomb, SIZE)
+    with pytest.raises(ValueError, match="does not match image size"):
+        import_mask(json.dumps(bomb).encode(), SIZE, file_name="mask.json")
+
+
+@pytest.mark.parametrize("polygons", [False, True])
+def test_coco_round_trip(polygons):
+    mask = np.zeros((30, 40), dtype=np.uint8)
+    mask[5:25, 8:32] = 255
+
+    document = to_coco(mask, "photos/photo.png", polygons=polygons, score=0.876)
+
+    annotation = document["annotations"][0]
+    assert document["images"][0] == {"id": 1, "file_name": "photo.png", "width": 40, "height": 30}
+    assert annotation["bbox"] == [8, 5, 24, 20]
+    assert annotation["area"] == 480
+    assert annotation["score"] == 0.876
+    np.testing.assert_array_equal(read_coco(json.dumps(document), SIZE), mask)
+    np.testing.assert_array_equal(read_coco(annotation, SIZE), mask)
+    np.testing.assert_array_equal(read_coco(annotation["segmentation"], SIZE), mask)
+
+
+def test_coco_rle_keeps_holes():
+    np.testing.assert_array_equal(read_coco(to_coco(_ring()), SIZE), _ring())
+    assert to_coco(np.zeros((30, 40), dtype=np.uint8))["annotations"] == []
+
+
+def test_read_coco_picks_image_by_name():
+    first, second = to_coco(_ring(), "a.png"), to_coco(_speckled(), "b.png")
+    second["images"][0]["id"] = second["annotations"][0]["image_id"] = 2
+    document = {"images": first["images"] + second["images"],
+                "annotations": first["annotations"] + second["annotations"], "categories": first["categories"]}
+
+    np.testing.assert_array_equal(read_coco(document, SIZE, image_name="dir/b.png"), _speckled())
+    with pytest.raises(ValueError, match="several images"):
+        read_coco(document, SIZE)
+    with pytest.raises(ValueError, match="no image named 'c.png'"):
+        read_coco(document, SIZE, image_name="c.png")
+
+
+@pytest.mark.parametrize("document, message", [
+    ("{not json", "Invalid COCO JSON"),
+    ('"mask"', "Unrecognised COCO annotation"),
+    ({"segmentation": 5}, "Unsupported COCO segmentation"),
+    ([[0, 0, 10]], "at least 3 points"),
+    ({"images": [{"id": 1, "width": 10, "height": 10}], "annotations": []}, "COCO image size 10x10"),
+])
+def test_read_coco_rejects_malformed(document, message):
+    with pytest.raises(ValueError, match=message):
+        read_coco(document, SIZE)
+
+
+def test_voc_round_trip():
+    data = to_voc_png(_ring())
+
+    with Image.open(io.BytesIO(data)) as image:
+        assert is_voc_image(image)
+        np.testing.assert_array_equal(read_voc(image), _ring())
+    assert detect_mask_format(data) == "voc"
+    np.testing.assert_array_equal(import_mask(data, SIZE), _ring())
+
+
+def test_voc_void_is_not_watermark():
+    indices = np.zeros((30, 40), dtype=np.uint8)
+    indices[:10] = 1
+    indices[10:20] = 255
+    indices[20:] = 7
+    image = Image.fromarray(indices, mode="P")
+
+    mask = read_voc(image)
+
+    assert (mask[:10] == 255).all() and (mask[10:20] == 0).all() and (mask[20:] == 255).all()
+
+
+def test_svg_round_trip():
+    mask = _ring()
+
+    document = to_svg(mask)
+
+    assert 'fill-rule="evenodd"' in document
+    result = read_svg(document, SIZE)
+    # The hole survives and the ring body is filled; only the hole's edge may differ by a pixel.
+    assert (result[13:17, 16:24] == 0).all()
+    assert (result[6:10, 9:31] == 255).all() and (result[20:24, 9:31] == 255).all()
+    assert not result[:4].any() and not result[26:].any()
+    assert np.count_nonzero(result != mask) < 0.1 * np.count_nonzero(mask)
+
+
+def test_read_svg_shapes_transforms_and_view_box():
+    document = """<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 15">
+      <defs><rect x="0" y="0" width="20" height="15"/></defs>
+      <rect x="1" y="1" width="4" height="4"/>
+      <rect x="10" y="1" width="4" height="4" fill="none"/>
+      <g transform="translate(10 8)"><circle cx="2" cy="2" r="1.5"/></g>
+    </svg>"""
+
+    mask = read_svg(document, SIZE)
+
+    # The 20x15 viewBox is scaled by 2 onto the 40x30 image.
+    assert mask[5, 5] == 255 and (mask[2:10, 2:10] == 255).all()
+    assert not mask[:, 20:30][:12].any()
+    assert mask[20, 24] == 255
+    assert mask[0, 0] == 0 and mask[29, 39] == 0
+
+
+@pytest.mark.parametrize("document, message", [
+    ("<svg", "Invalid SVG"),
+    ("<html/>", "Expected an <svg> document"),
+    ('<svg><path d="M 0 0 L 10"/></svg>', "Malformed SVG path"),
+])
+def test_read_svg_rejects_malformed(document, message):
+    with pytest.raises(ValueError, match=message):
+        read_svg(document, SIZE)
+
+
+def test_parse_svg_path_relative_and_closepath():
+    polygons = parse_svg_path("m 10 10 l 20 0 l 0 20 z m 5 0 h 5 v 5 H 15 Z M 0 0 10 0 10 10")
+
+    assert len(polygons) == 3
+    np.testing.assert_allclose(polygons[0], [[10, 10], [30, 10], [30, 30]])
+    # After z the current point is back at the subpath start, so the relative moveto starts at (15, 10).
+    np.testing.assert_allclose(polygons[1], [[15, 10], [20, 10], [20, 15], [15, 15]])
+    # Coordinate pairs after a moveto are implicit line-tos.
+    np.testing.assert_allclose(polygons[2], [[0, 0], [10, 0], [10, 10]])
+
+
+def test_parse_svg_path_arcs_and_curves():
+    arc, = parse_svg_path("M 0 10 A 10 10 0 0 1 20 10 Z")
+    distances = np.hypot(arc[:, 0] - 10, arc[:, 1] - 10)
+
+    np.testing.assert_allclose(distances, 10, atol=1e-6)
+    # With the sweep flag set the arc runs through the top of the circle.
+    assert arc[:, 1].min() == pytest.approx(0, abs=1e-6)
+    np.testing.assert_allclose(arc[-1], [20, 10], atol=1e-9)
+
+    curve, = parse_svg_path("M 0 0 c 0 10 20 10 20 0 s 20 -10 20 0 z")
+    np.testing.assert_allclose(curve[-1], [40, 0], atol=1e-9)
+    assert curve[:, 1].max() == pytest.approx(7.5) and curve[:, 1].min() == pytest.approx(-7.5)
+
+
+@pytest.mark.parametrize("d, message", [
+    ("10 10 L 20 20", "must start with a command"),
+    ("M 10", "Malformed SVG path"),
+    ("M 0 0 A 5 5 0 0 1", "Malformed SVG path"),
+])
+def test_parse_svg_path_rejects_malformed(d, message):
+    with pytest.raises(ValueError, match=message):
+        parse_svg_path(d)
+
+
+@pytest.mark.parametrize("mask_format", ["png", "coco", "voc", "svg"])
+def test_export_import_round_trip(mask_format):
+    data = export_mask(_ring(), mask_format)
+
+    result = import_mask(data, SIZE)
+
+    if mask_format == "svg":
+        assert np.count_nonzero(result != _ring()) < 0.1 * np.count_nonzero(_ring())
+    else:
+        np.testing.assert_array_equal(result, _ring())
+
+
+def test_unknown_formats_raise():
+    with pytest.raises(ValueError, match="Unknown mask format 'tiff'"):
+        export_mask(_ring(), "tiff")
+    with pytest.raises(ValueError, match="Unknown mask format 'tiff'"):
+        import_mask(b"", SIZE, mask_format="tiff")
@@ -0,0 +1,87 @@
+import numpy as np
+import pytest
+
+pytest.importorskip("cv2")
+
+from watermark_remover.utils.mask_refinement import MaskRefinement, blend, fill_holes, remove_small_components
+
+
+def _ring():
+    mask = np.zeros((20, 20), dtype=np.uint8)
+    mask[4:16, 4:16] = 255
+    mask[7:13, 7:13] = 0
+    return mask
+
+
+def test_fill_holes_fills_enclosed_background_only():
+    mask = _ring()
+    # A notch open to the image border is not a hole.
+    mask[0:10, 18] = 255
+    mask[0:10, 19] = 0
+
+    filled = fill_holes(mask)
+
+    assert (filled[7:13, 7:13] == 255).all()
+    assert filled[0, 19] == 0
+    np.testing.assert_array_equal(filled[mask > 0], 255)
+    assert (filled[:4, :4] == 0).all()
+
+
+def test_remove_small_components():
+    mask = np.zeros((20, 20), dtype=np.uint8)
+    mask[2:7, 2:7] = 255
+    mask[15, 15] = mask[16, 16] = mask[17, 17] = 255
+
+    assert (remove_small_components(mask, 4) == 0)[15:18, 15:18].all()
+    # The diagonal pixels form one 8-connected component of three.
+    np.testing.assert_array_equal(remove_small_components(mask, 3), mask)
+    assert (remove_small_components(mask, 26) == 0).all()
+
+
+def test_identity_and_validation():
+    assert MaskRefinement().is_identity
+    assert not MaskRefinement(feather=2).is_identity
+    with pytest.raises(ValueError, match="'dilate' must not be negative"):
+        MaskRefinement(dilate=-1).apply(_ring())
+
+    refined, weight = MaskRefinement().apply(_ring())
+    np.testing.assert_array_equal(refined, _ring())
+    assert weight is None
+
+
+def test_close_joins_broken_stroke():
+    mask = np.zeros((20, 30), dtype=np.uint8)
+    mask[9:12, 2:14] = 255
+    mask[9:12, 16:28] = 255
+
+    refined, _ = MaskRefinement(close=2).apply(mask)
+
+    assert (refined[10, 2:28] == 255).all()
+
+
+def test_speckle_is_removed_before_dilation():
+    mask = np.zeros((30, 30), dtype=np.uint8)
+    mask[10:20, 10:20] = 255
+    mask[2, 2] = 255
+
+    refined, _ = MaskRefinement(min_area=4, dilate=2).apply(mask)
+
+    assert (refined[:5, :5] == 0).all()
+    assert refined[15, 8] == 255 and refined[15, 7] == 0
+    assert refined[8, 8] == 0
+
+
+def test_feather_band_and_blend():
+    mask = np.zeros((20, 40), dtype=np.uint8)
+    mask[:, :10] = 255
+
+    refined, weight = MaskRefinement(feather=3).apply(mask)
+
+    np.testing.assert_allclose(weight[5, 8:15], [1, 1, 0.75, 0.5, 0.25, 0, 0])
+    np.testing.assert_array_equal(refined[5, 8:15] > 0, [True] * 5 + [False] * 2)
+
+    original = np.zeros((20, 40, 3), dtype=np.uint8)
+    inpainted = np.full((20, 40, 3), 200, dtype=np.uint8)
+    mixed = blend(original, inpainted, weight)
+    np.testing.assert_array_equal(mixed[5, 8:15, 0], [200, 200, 150, 100, 50, 0, 0])
+    assert blend(original, inpainted, None) is inpainted
@@ -0,0 +1,42 @@
+import numpy as np
+import pytest
+
+torch = pytest.importorskip("torch")
+pytest.importorskip("onnxruntime")
+pytest.importorskip("onnxscript")
+
+from watermark_remover.core.backends import create_backend
+from watermark_remover.core.lama_wrapper import LaMaWrapper
+from watermark_remover.core.onnx_backend import LaMaOnnxBackend, export_onnx
+
+
+@pytest.fixture
+def model_paths(tiny_generator, tmp_path):
+    checkpoint_path = str(tmp_path / "tiny-lama.ckpt")
+    torch.save(tiny_generator.state_dict(), checkpoint_path)
+    onnx_path = export_onnx(checkpoint_path, str(tmp_path / "tiny-lama.onnx"))
+    return checkpoint_path, onnx_path
+
+
+def test_onnx_matches_pytorch(model_paths):
+    checkpoint_path, onnx_path = model_paths
+    rng = np.random.default_rng(0)
+    # Different from the 64x64 export example to check the dynamic axes.
+    image = rng.integers(0, 256, size=(45, 38, 3), dtype=np.uint8)
+    mask = np.zeros((45, 38), dtype=np.uint8)
+    mask[12:30, 8:25] = 255
+
+    expected = LaMaWrapper(model_path=checkpoint_path).predict(image, mask)
+    result = LaMaOnnxBackend(onnx_path, intra_op_num_threads=1).predict(image, mask)
+
+    assert result.shape == image.shape
+    np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
+    assert np.abs(result.astype(int) - expected.astype(int)).max() <= 2
+
+
+def test_onnx_backend_from_registry(model_paths):
+    _, onnx_path = model_paths
+    backend = create_backend("lama_onnx", model_path=onnx_path, intra_op_num_threads=2)
+
+    assert backend.name == "lama_onnx"
+    assert backend.session.get_session_options().intra_op_num_threads == 2
@@ -0,0 +1,83 @@
+import numpy as np
+import pytest
+
+cv2 = pytest.importorskip("cv2")
+
+from watermark_remover.core import periodic
+from watermark_remover.core.periodic import detect_periodic_pattern
+from watermark_remover.core.reverse_blend import PeriodicBlendBackend
+
+PERIOD = 64
+ALPHA = 0.5
+
+
+def _cross_cell():
+    """One lattice cell of the overlay: a cross of 4 px strokes."""
+    cell = np.zeros((PERIOD, PERIOD), dtype=bool)
+    cell[30:34, 16:48] = True
+    cell[16:48, 30:34] = True
+    return cell
+
+
+def _tiled(size=512):
+    rng = np.random.default_rng(2)
+    photo = cv2.GaussianBlur(rng.integers(0, 256, size=(size, size, 3), dtype=np.uint8), (0, 0), 1)
+    photo = (40 + photo.astype(np.float32) * 160 / 255)
+    overlay = np.tile(_cross_cell(), (size // PERIOD, size // PERIOD))
+    marked = photo.copy()
+    marked[overlay] = ALPHA * 255 + (1 - ALPHA) * marked[overlay]
+    return np.rint(photo).astype(np.uint8), np.rint(marked).astype(np.uint8), overlay
+
+
+def test_detects_period_of_tiled_overlay():
+    photo, marked, overlay = _tiled()
+
+    pattern = detect_periodic_pattern(marked)
+
+    assert pattern.confidence > 0.3
+    assert pattern.has_blend_estimate
+    first, second = pattern.period
+    vectors = sorted(np.abs([first, second]).tolist())
+    # One vector along each axis, one repeat long.
+    assert vectors[0] == pytest.approx([0, PERIOD], abs=1)
+    assert vectors[1] == pytest.approx([PERIOD, 0], abs=1)
+    assert (pattern.mask[overlay] > 0).mean() > 0.9
+    assert float(np.median(pattern.alpha[overlay])) == pytest.approx(ALPHA, abs=0.15)
+
+
+def test_periodic_blend_recovers_image():
+    photo, marked, overlay = _tiled()
+    backend = PeriodicBlendBackend()
+
+    mask, _ = backend.resolve_mask(marked)
+    result = backend.predict(marked, mask)
+
+    before = np.abs(marked[overlay].astype(int) - photo[overlay]).mean()
+    after = np.abs(result[overlay].astype(int) - photo[overlay]).mean()
+    assert after < before / 3
+    np.testing.assert_array_equal(result[mask == 0], marked[mask == 0])
+
+
+def test_periodic_blend_folds_once(monkeypatch):
+    _, marked, _ = _tiled()
+    calls = []
+    fold = periodic._fold
+    monkeypatch.setattr(periodic, "_fold", lambda *args: calls.append(1) or fold(*args))
+    backend = PeriodicBlendBackend()
+
+    mask, _ = backend.resolve_mask(marked)
+    assert calls == []
+    backend.predict(marked, mask)
+    # The full-resolution fold only runs where the estimate is used.
+    assert calls == [1]
+
+
+def test_blend_estimate_can_be_skipped():
+    _, marked, _ = _tiled()
+
+    pattern = detect_periodic_pattern(marked, blend_estimate=False)
+
+    assert pattern.period is not None
+    assert not pattern.has_blend_estimate
+    with pytest.raises(ValueError, match="No reverse-blend estimate"):
+        pattern.to_watermark_template()
@@ -0,0 +1,70 @@
+import numpy as np
+import pytest
+
+pytest.importorskip("cv2")
+
+from watermark_remover.core.backends import InpaintingBackend
+from watermark_remover.core.refinement import QUALITY_PRESETS, MultiScaleRefiner, with_quality
+
+FILL = 180
+
+
+class FillBackend(InpaintingBackend):
+    def __init__(self):
+        self.sizes = []
+
+    def predict(self, image, mask):
+        self.sizes.append((image.shape[1], image.shape[0]))
+        result = image.copy()
+        result[mask > 0] = FILL
+        return result
+
+
+def test_level_sizes():
+    refiner = MultiScaleRefiner(FillBackend(), base_size=100, scale_step=2.0)
+
+    assert refiner.level_sizes(400, 300) == [(75, 100), (150, 200), (300, 400)]
+    # Images already within the base size get a single pass.
+    assert refiner.level_sizes(80, 60) == [(60, 80)]
+
+
+def test_refined_fill_keeps_known_pixels():
+    image = np.full((64, 48, 3), 100, dtype=np.uint8)
+    mask = np.zeros((64, 48), dtype=np.uint8)
+    mask[8:56, 8:40] = 255
+    backend = FillBackend()
+
+    result = MultiScaleRefiner(backend, base_size=16, scale_step=2.0).predict(image, mask)
+
+    # The engine runs once per level, coarsest first.
+    assert backend.sizes == [(12, 16), (24, 32), (48, 64)]
+    np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
+    # Far from the hole's edge, structure and detail agree with the engine's fill.
+    assert np.abs(result[20:44, 18:30].astype(int) - FILL).max() <= 6
+
+
+def test_empty_mask_skips_engine():
+    backend = FillBackend()
+    image = np.zeros((64, 64, 3), dtype=np.uint8)
+
+    result = MultiScaleRefiner(backend, base_size=16).predict(image, np.zeros((64, 64), dtype=np.uint8))
+
+    np.testing.assert_array_equal(result, image)
+    assert backend.sizes == []
+
+
+def test_quality_presets():
+    backend = FillBackend()
+
+    assert with_quality(backend, "fast") is backend
+    for quality in ("balanced", "high"):
+        refiner = with_quality(backend, quality)
+        assert isinstance(refiner, MultiScaleRefiner)
+        assert (refiner.base_size, refiner.scale_step) == tuple(QUALITY_PRESETS[quality].values())
+
+
+def test_unknown_quality_preset_raises():
+    with pytest.raises(ValueError, match="Unknown quality preset 'ultra'"):
+        with_quality(FillBackend(), "ultra")
+    with pytest.raises(ValueError, match="scale_step"):
+        MultiScaleRefiner(FillBackend(), scale_step=1.0)
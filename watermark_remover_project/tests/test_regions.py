@@ -0,0 +1,95 @@
+import json
+
+import numpy as np
+import pytest
+
+pytest.importorskip("cv2")
+
+from watermark_remover.utils.regions import add_regions, parse_regions, region_mask
+
+SIZE = (200, 100)
+
+
+def _box(mask):
+    """(left, top, right, bottom) of a mask's non-zero pixels, right/bottom exclusive."""
+    ys, xs = np.nonzero(mask)
+    return xs.min(), ys.min(), xs.max() + 1, ys.max() + 1
+
+
+@pytest.mark.parametrize("anchor, box", [
+    ("top_left", (10, 5, 50, 25)),
+    ("top_right", (150, 5, 190, 25)),
+    ("bottom_left", (10, 75, 50, 95)),
+    ("bottom_right", (150, 75, 190, 95)),
+])
+def test_rect_anchors(anchor, box):
+    mask = region_mask({"type": "rect", "anchor": anchor, "x": 10, "y": 5, "width": 40, "height": 20}, SIZE)
+
+    assert mask.shape == (100, 200)
+    assert _box(mask) == box
+    assert np.count_nonzero(mask) == 40 * 20
+
+
+def test_relative_units():
+    pixels = region_mask({"type": "rect", "x": 140, "y": 85, "width": 60, "height": 15}, SIZE)
+    relative = region_mask({"type": "rect", "units": "relative", "x": 0.7, "y": 0.85, "width": 0.3, "height": 0.15},
+                           SIZE)
+
+    np.testing.assert_array_equal(relative, pixels)
+    # The same specification scales with the image.
+    assert _box(region_mask({"type": "rectangle", "units": "relative", "x": 0.7, "y": 0.85,
+                             "width": 0.3, "height": 0.15}, (400, 200))) == (280, 170, 400, 200)
+
+
+def test_polygon_and_ellipse():
+    triangle = region_mask({"type": "polygon", "anchor": "bottom_right", "points": [[0, 0], [40, 0], [0, 40]]}, SIZE)
+    assert triangle[99, 199] == 255 and triangle[90, 190] == 255
+    assert triangle[61, 161] == 0
+
+    ellipse = region_mask({"type": "ellipse", "units": "relative", "cx": 0.5, "cy": 0.5, "rx": 0.2, "ry": 0.2}, SIZE)
+    assert _box(ellipse) == (60, 30, 141, 71)
+    rotated = region_mask({"type": "ellipse", "cx": 100, "cy": 50, "rx": 40, "ry": 10, "angle": 90}, SIZE)
+    assert _box(rotated) == (90, 10, 111, 91)
+
+
+def test_parse_regions_accepts_json_lists_and_files(tmp_path):
+    shapes = [{"type": "rect", "x": 0, "y": 0, "width": 10, "height": 10},
+              {"type": "rect", "anchor": "bottom_right", "x": 0, "y": 0, "width": 10, "height": 10}]
+    path = tmp_path / "regions.json"
+    path.write_text(json.dumps({"shapes": shapes}))
+
+    assert len(parse_regions(json.dumps(shapes))) == 2
+    assert len(parse_regions({"shapes": shapes})) == 2
+    np.testing.assert_array_equal(region_mask(str(path), SIZE), region_mask(shapes, SIZE))
+    # Repeated CLI flags: several specifications combine into one mask.
+    assert np.count_nonzero(region_mask([json.dumps(shape) for shape in shapes], SIZE)) == 200
+    with pytest.raises(FileNotFoundError):
+        parse_regions(str(tmp_path / "missing.json"))
+
+
+@pytest.mark.parametrize("spec, message", [
+    ("{not json", "Invalid region JSON"),
+    ("[]", "no shapes"),
+    ("42", "must be a shape or a list"),
+    ({"type": "star"}, "Unknown region type"),
+    ({"type": "rect", "anchor": "middle", "x": 0, "y": 0, "width": 1, "height": 1}, "Unknown region anchor"),
+    ({"type": "rect", "units": "cm", "x": 0, "y": 0, "width": 1, "height": 1}, "Unknown region units"),
+    ({"type": "rect", "x": 0, "y": 0, "width": 1}, "needs 'height'"),
+    ({"type": "rect", "x": "left", "y": 0, "width": 1, "height": 1}, "must be a number"),
+    ({"type": "ellipse", "cx": 0, "cy": 0, "rx": -1, "ry": 1}, "at least 0"),
+    ({"type": "polygon", "points": [[0, 0], [1, 1]]}, "at least 3"),
+    ([{"type": "rect", "x": 0, "y": 0, "width": 1, "height": 1}, "rect"], "must be a JSON object"),
+])
+def test_invalid_specifications(spec, message):
+    with pytest.raises(ValueError, match=message):
+        parse_regions(spec)
+
+
+def test_add_regions_extends_mask():
+    base = np.zeros((100, 200), dtype=np.uint8)
+    base[50:60, 50:60] = 255
+
+    combined = add_regions(base, {"type": "rect", "x": 0, "y": 0, "width": 10, "height": 10}, SIZE)
+
+    assert np.count_nonzero(combined) == 200
+    assert add_regions(None, {"type": "rect", "x": 0, "y": 0, "width": 10, "height": 10}, SIZE).sum() == 100 * 255
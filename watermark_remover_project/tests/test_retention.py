@@ -0,0 +1,145 @@
+import io
+import time
+
+import numpy as np
+import pytest
+from PIL import Image
+
+pytest.importorskip("cv2")
+pytest.importorskip("fastapi")
+pytest.importorskip("celery")
+pytest.importorskip("httpx")
+from fastapi.testclient import TestClient
+
+from watermark_remover.api.main import app
+from watermark_remover.config import settings
+from watermark_remover.storage import LocalStorage
+from watermark_remover.tasks.celery_app import celery_app
+from watermark_remover.tasks.jobs import INPUT, RESULT, JobStore, get_job_store
+from watermark_remover.tasks.retention import cleanup, cleanup_task
+
+TTL = 3600
+MAX_AGE = 7200
+
+
+@pytest.fixture
+def store(tmp_path, monkeypatch):
+    monkeypatch.setattr(settings, "jobs_dir", str(tmp_path / "jobs"))
+    monkeypatch.setattr(settings, "result_ttl", TTL)
+    monkeypatch.setattr(settings, "job_max_age", MAX_AGE)
+    return JobStore(LocalStorage(settings.jobs_dir))
+
+
+@pytest.fixture
+def client(store):
+    previous = celery_app.conf.task_always_eager
+    celery_app.conf.task_always_eager = True
+    yield TestClient(app)
+    celery_app.conf.task_always_eager = previous
+
+
+def _finished_job(store, data=b"x" * 100):
+    job = store.create(data, "photo.png")
+    store.write(job.id, RESULT, data)
+    return store.update(job.id, status="succeeded")
+
+
+def _png():
+    buffer = io.BytesIO()
+    Image.fromarray(np.full((16, 16, 3), 120, dtype=np.uint8)).save(buffer, format="PNG")
+    return buffer.getvalue()
+
+
+def _backdate(store, job_id, seconds):
+    job = store.get(job_id)
+    job.created_at -= seconds
+    job.updated_at -= seconds
+    store._write_metadata(job)
+
+
+def test_cleanup_deletes_expired_results(store):
+    job = _finished_job(store)
+    now = time.time()
+
+    assert cleanup(store, now=now + TTL - 10)["jobs_deleted"] == 0
+    metrics = cleanup(store, now=now + TTL + 10)
+    assert metrics["jobs_deleted"] == 1
+    assert metrics["bytes_reclaimed"] >= 200
+    assert metrics["folders_kept"] == 0
+    with pytest.raises(FileNotFoundError):
+        store.get(job.id)
+
+
+def test_cleanup_keeps_unfinished_jobs_until_max_age(store):
+    running = store.update(store.create(b"x", "photo.png").id, status="running")
+    # An interrupted upload: artifacts without a state.
+    store.storage.write(f"{'f' * 32}/{INPUT}", b"x" * 50)
+    now = time.time()
+
+    metrics = cleanup(store, now=now + TTL + 10)
+    assert (metrics["jobs_deleted"], metrics["orphans_deleted"], metrics["folders_kept"]) == (0, 0, 2)
+    metrics = cleanup(store, now=now + MAX_AGE + 10)
+    assert (metrics["jobs_deleted"], metrics["orphans_deleted"], metrics["folders_kept"]) == (1, 1, 0)
+    with pytest.raises(FileNotFoundError):
+        store.get(running.id)
+
+
+def test_batch_jobs_live_as_long_as_the_batch(store):
+    job = _finished_job(store)
+    batch = store.create_batch([job.id], ["photo.png"])
+    _backdate(store, job.id, TTL + 10)
+    now = time.time()
+
+    # The job alone would have expired, but its batch is still running.
+    assert cleanup(store, now=now)["jobs_deleted"] == 0
+    store.update_batch(batch.id, status="succeeded")
+    metrics = cleanup(store, now=now + TTL + 10)
+    assert (metrics["batches_deleted"], metrics["jobs_deleted"]) == (1, 1)
+
+
+def test_expired_result_gives_410(client, store):
+    job = _finished_job(store)
+    assert client.get(f"/v1/jobs/{job.id}").json()["expires_at"] == pytest.approx(job.updated_at + TTL)
+    _backdate(store, job.id, TTL + 10)
+    assert client.get(f"/v1/jobs/{job.id}/result").status_code == 410
+
+
+def test_result_deleted_after_download(client, store):
+    region = '{"type": "rect", "x": 2, "y": 2, "width": 4, "height": 4}'
+    response = client.post("/v1/jobs", files={"file": ("photo.png", _png(), "image/png")},
+                           data={"backend": "opencv_telea", "region": region})
+    job_id = response.json()["id"]
+    assert store.read(job_id, RESULT) is not None
+
+    assert settings.delete_after_download
+    assert client.get(f"/v1/jobs/{job_id}/result").status_code == 200
+    assert client.get(f"/v1/jobs/{job_id}").status_code == 404
+    assert store.read(job_id, RESULT) is None
+    assert list(store.storage.list(f"{job_id}/")) == []
+
+
+def test_result_kept_after_download_when_disabled(client, store, monkeypatch):
+    monkeypatch.setattr(settings, "delete_after_download", False)
+    region = '{"type": "rect", "x": 2, "y": 2, "width": 4, "height": 4}'
+    response = client.post("/v1/jobs", files={"file": ("photo.png", _png(), "image/png")},
+                           data={"backend": "opencv_telea", "region": region})
+    job_id = response.json()["id"]
+
+    assert client.get(f"/v1/jobs/{job_id}/result").status_code == 200
+    assert client.get(f"/v1/jobs/{job_id}/result").status_code == 200
+    assert store.read(job_id, RESULT) is not None
+
+
+def test_cleanup_task_records_metrics(client, store):
+    job = _finished_job(store)
+    _backdate(store, job.id, TTL + 10)
+    assert client.get("/v1/cleanup").json() == {}
+
+    cleanup_task.delay()
+    cleanup_task.delay()
+    stats = client.get("/v1/cleanup").json()
+    assert stats["last_run"]["jobs_deleted"] == 0
+    assert stats["totals"]["runs"] == 2
+    assert stats["totals"]["jobs_deleted"] == 1
+    assert stats["totals"]["bytes_reclaimed"] > 0
+    assert get_job_store().storage.read("cleanup.json") is not None
@@ -0,0 +1,100 @@
+import numpy as np
+import pytest
+
+pytest.importorskip("cv2")
+
+from watermark_remover.core.backends import InpaintingBackend
+from watermark_remover.core.estimation import WatermarkTemplate, _gradients, _magnitude
+from watermark_remover.core.reverse_blend import ReverseBlendBackend
+
+ALPHA = 0.4
+COLOUR = 240
+# Top-left corner of the logo in the test image.
+POSITION = (30, 20)
+
+
+class RecordingBackend(InpaintingBackend):
+    def __init__(self):
+        self.masks = []
+
+    def predict(self, image, mask):
+        self.masks.append(mask.copy())
+        result = image.copy()
+        result[mask > 0] = 0
+        return result
+
+
+def _template():
+    """A 20x32 ring logo, with a fully opaque corner that cannot be recovered, in a clear 2 px border."""
+    alpha = np.zeros((24, 36), dtype=np.float32)
+    alpha[2:22, 2:34] = ALPHA
+    alpha[7:17, 7:29] = 0
+    alpha[2:5, 2:5] = 1.0
+    watermark = np.full((24, 36, 3), COLOUR, dtype=np.float32) * (alpha > 0)[..., None]
+    stamped_on_grey = alpha[..., None] * watermark + (1 - alpha[..., None]) * 128
+    return WatermarkTemplate(alpha=alpha, watermark=watermark, mask=((alpha > 0) * 255).astype(np.uint8),
+                             gradient=_magnitude(*_gradients(stamped_on_grey)))
+
+
+def _photo():
+    y, x = np.mgrid[0:64, 0:96].astype(np.float32)
+    return np.stack([40 + x, 60 + 2 * y, 200 - x], axis=-1).astype(np.uint8)
+
+
+def _stamp(photo, template):
+    x, y = POSITION
+    height, width = template.alpha.shape
+    marked = photo.astype(np.float32)
+    alpha = template.alpha[..., None]
+    region = marked[y:y + height, x:x + width]
+    marked[y:y + height, x:x + width] = alpha * template.watermark + (1 - alpha) * region
+    return np.rint(marked).astype(np.uint8)
+
+
+def test_recovers_blended_pixels():
+    template = _template()
+    photo = _photo()
+    marked = _stamp(photo, template)
+    fallback = RecordingBackend()
+    backend = ReverseBlendBackend(template, fallback=fallback)
+
+    assert backend.locate(marked)[0] == POSITION
+    mask, _ = backend.resolve_mask(marked)
+    result = backend.predict(marked, mask)
+
+    x, y = POSITION
+    opaque = np.zeros(mask.shape, dtype=bool)
+    opaque[y + 2:y + 5, x + 2:x + 5] = True
+    recoverable = (mask > 0) & ~opaque
+    # Rounding the blended image to uint8 costs at most 0.5 / (1 - alpha) on the way back.
+    assert np.abs(result[recoverable].astype(int) - photo[recoverable]).max() <= 1
+    np.testing.assert_array_equal(result[mask == 0], marked[mask == 0])
+    # Only the opaque corner is left to the fallback.
+    assert len(fallback.masks) == 1
+    np.testing.assert_array_equal(fallback.masks[0] > 0, opaque)
+
+
+def test_match_threshold_is_separate_from_detection_confidence():
+    template = _template()
+    marked = _stamp(_photo(), template)
+
+    # A fixed position matches with score 1.0, whatever the detection confidence asks for.
+    mask, score = ReverseBlendBackend(template, position=POSITION).resolve_mask(marked, min_confidence=1.5)
+    assert score == 1.0
+    assert mask.any()
+
+    mask, _ = ReverseBlendBackend(template, position=POSITION, match_threshold=1.01).resolve_mask(marked)
+    assert not mask.any()
+
+
+def test_image_smaller_than_template_is_left_unchanged():
+    small = _photo()[:16, :16]
+    fallback = RecordingBackend()
+    backend = ReverseBlendBackend(_template(), fallback=fallback)
+
+    mask, score = backend.resolve_mask(small)
+    result = backend.predict(small, np.full((16, 16), 255, dtype=np.uint8))
+
+    assert score == -1.0 and not mask.any()
+    np.testing.assert_array_equal(result, small)
+    assert fallback.masks == []
@@ -0,0 +1,179 @@
+import io
+import os
+from concurrent.futures import ThreadPoolExecutor
+from datetime import datetime, timezone
+
+import numpy as np
+import pytest
+
+from watermark_remover.config import settings
+from watermark_remover.storage import LocalStorage, S3Storage, get_storage, get_template_storage
+
+
+class FakeS3Client:
+    """In-memory stand-in for the parts of a boto3 S3 client that S3Storage uses."""
+
+    PAGE_SIZE = 2
+
+    class exceptions:
+        class NoSuchKey(Exception):
+            pass
+
+    def __init__(self):
+        self.objects = {}
+        self.delete_calls = 0
+
+    def get_object(self, Bucket, Key):
+        if (Bucket, Key) not in self.objects:
+            raise self.exceptions.NoSuchKey(Key)
+        return {"Body": io.BytesIO(self.objects[Bucket, Key])}
+
+    def put_object(self, Bucket, Key, Body):
+        self.objects[Bucket, Key] = Body
+
+    def delete_objects(self, Bucket, Delete):
+        self.delete_calls += 1
+        for item in Delete["Objects"]:
+            self.objects.pop((Bucket, item["Key"]), None)
+
+    def get_paginator(self, operation):
+        assert operation == "list_objects_v2"
+        return self
+
+    def paginate(self, Bucket, Prefix):
+        keys = sorted(key for bucket, key in self.objects if bucket == Bucket and key.startswith(Prefix))
+        # Listed up front, as S3 pages are snapshots: deleting while paginating must not skip keys.
+        pages = [keys[i:i + self.PAGE_SIZE] for i in range(0, len(keys), self.PAGE_SIZE)]
+        for page in pages:
+            yield {"Contents": [{"Key": key, "Size": len(self.objects.get((Bucket, key), b"")),
+                                 "LastModified": datetime(2024, 5, 17, tzinfo=timezone.utc)} for key in page]}
+
+
+@pytest.fixture
+def local(tmp_path):
+    return LocalStorage(str(tmp_path / "storage"))
+
+
+@pytest.fixture
+def s3():
+    return S3Storage("bucket", prefix="/jobs/", client=FakeS3Client())
+
+
+@pytest.fixture(params=["local", "s3"])
+def storage(request):
+    return request.getfixturevalue(request.param)
+
+
+def test_write_read_and_overwrite(storage):
+    assert storage.read("job/input") is None
+
+    storage.write("job/input", b"first")
+    storage.write("job/input", b"second")
+
+    assert storage.read("job/input") == b"second"
+    assert storage.read("job/result") is None
+
+
+def test_delete_key_and_folder(storage):
+    for key in ("a/input", "a/input.png", "a/nested/mask", "ab/input", "b/input"):
+        storage.write(key, b"x")
+
+    # A plain key must not take the keys it is a prefix of with it.
+    assert storage.delete("a/input") == 1
+    assert storage.read("a/input.png") == b"x"
+    assert storage.delete("a/input") == 0
+    assert storage.delete("a/") == 2
+    assert storage.read("ab/input") == b"x" and storage.read("b/input") == b"x"
+    assert storage.delete("missing/") == 0
+
+
+def test_list_by_prefix(storage):
+    storage.write("a/input", b"12345")
+    storage.write("a/result", b"1")
+    storage.write("b/input", b"1")
+
+    items = {item.key: item for item in storage.list("a/")}
+
+    assert sorted(items) == ["a/input", "a/result"]
+    assert items["a/input"].size == 5 and items["a/input"].modified > 0
+    assert sorted(item.key for item in storage.list()) == ["a/input", "a/result", "b/input"]
+
+
+@pytest.mark.parametrize("key", ["", "/input", "a//input", "../input", "a/../input", "a/.", "a\\input"])
+def test_invalid_keys_are_rejected(storage, key):
+    with pytest.raises(ValueError, match="Invalid storage key"):
+        storage.read(key)
+    with pytest.raises(ValueError, match="Invalid storage key"):
+        storage.write(key, b"x")
+
+
+def test_local_storage_leaves_no_temporary_files(local):
+    local.write("a/input", b"x")
+    local.write("a/input", b"y")
+
+    assert os.listdir(os.path.join(local.root, "a")) == ["input"]
+    assert LocalStorage(os.path.join(local.root, "missing")).read("a/input") is None
+
+
+def test_local_storage_concurrent_writes_of_one_key(local):
+    contents = [bytes([i]) * 100000 for i in range(8)]
+
+    with ThreadPoolExecutor(len(contents)) as executor:
+        list(executor.map(lambda data: local.write("a/state", data), contents * 4))
+
+    # One writer wins as a whole; no temporary file is left behind.
+    assert local.read("a/state") in contents
+    assert os.listdir(os.path.join(local.root, "a")) == ["state"]
+
+
+def test_s3_keys_carry_the_prefix(s3):
+    s3.write("a/input", b"x")
+
+    assert ("bucket", "jobs/a/input") in s3.client.objects
+    assert [item.key for item in s3.list()] == ["a/input"]
+    assert S3Storage("bucket", client=s3.client).read("jobs/a/input") == b"x"
+
+
+def test_s3_folder_delete_spans_pages(s3):
+    for i in range(5):
+        s3.write(f"a/{i}", b"x")
+    s3.write("b/0", b"x")
+
+    assert s3.delete("a/") == 5
+    # Five keys at two per page.
+    assert s3.client.delete_calls == 3
+    assert [item.key for item in s3.list()] == ["b/0"]
+
+
+def test_s3_needs_a_bucket():
+    with pytest.raises(ValueError, match="needs a bucket"):
+        S3Storage("", client=FakeS3Client())
+
+
+def test_configured_storage(tmp_path, monkeypatch):
+    monkeypatch.setattr(settings, "jobs_dir", str(tmp_path / "jobs"))
+    monkeypatch.setattr(settings, "templates_dir", str(tmp_path / "templates"))
+
+    assert get_storage().root == str(tmp_path / "jobs")
+    assert get_template_storage().root == str(tmp_path / "templates")
+    monkeypatch.setattr(settings, "storage_backend", "ftp")
+    with pytest.raises(ValueError, match="Unknown storage backend 'ftp'"):
+        get_storage()
+
+
+def test_template_registry_on_s3():
+    pytest.importorskip("cv2")
+    from watermark_remover.core.templates import TemplateRegistry
+
+    client = FakeS3Client()
+    image = np.zeros((12, 20, 3), dtype=np.uint8)
+    image[3:9, 4:16] = 200
+    TemplateRegistry(S3Storage("bucket", prefix="templates", client=client)).add("logo", image)
+    # Another process, sharing only the bucket, sees the template.
+    registry = TemplateRegistry(S3Storage("bucket", prefix="templates", client=client))
+
+    assert registry.names() == ["logo"]
+    np.testing.assert_array_equal(registry.get("logo").image, image)
+    assert ("bucket", "templates/logo/meta.json") in client.objects
+    registry.delete("logo")
+    assert registry.names() == [] and not client.objects
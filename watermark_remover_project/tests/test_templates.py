@@ -0,0 +1,180 @@
+import io
+
+import numpy as np
+import pytest
+from PIL import Image
+
+cv2 = pytest.importorskip("cv2")
+
+from watermark_remover.core.templates import TemplateRegistry, match_templates, matches_to_mask, template_mask
+
+# Where the logo is stamped in the test photos.
+POSITION = (100, 60)
+OPACITY = 180
+
+
+@pytest.fixture
+def registry(tmp_path):
+    return TemplateRegistry(str(tmp_path / "templates"))
+
+
+def _logo():
+    """A white framed cross, 40x24, with partial opacity on the strokes only."""
+    alpha = np.zeros((24, 40), dtype=np.uint8)
+    cv2.rectangle(alpha, (1, 1), (38, 22), OPACITY, 3)
+    cv2.line(alpha, (4, 4), (35, 19), OPACITY, 3)
+    return np.full((24, 40, 3), 255, dtype=np.uint8), alpha
+
+
+def _photo(height=160, width=240):
+    rng = np.random.default_rng(1)
+    noise = rng.integers(0, 30, size=(height, width, 3), dtype=np.uint8)
+    return cv2.GaussianBlur(noise, (0, 0), 2) + np.uint8(70)
+
+
+def _stamp(photo, image, alpha, scale=1.0):
+    if scale != 1.0:
+        size = (round(alpha.shape[1] * scale), round(alpha.shape[0] * scale))
+        image = cv2.resize(image, size, interpolation=cv2.INTER_LINEAR)
+        alpha = cv2.resize(alpha, size, interpolation=cv2.INTER_LINEAR)
+    x, y = POSITION
+    height, width = alpha.shape
+    opacity = alpha[..., None].astype(np.float32) / 255
+    marked = photo.astype(np.float32)
+    marked[y:y + height, x:x + width] = opacity * image + (1 - opacity) * marked[y:y + height, x:x + width]
+    return np.rint(marked).astype(np.uint8)
+
+
+def test_add_and_get_round_trip(registry):
+    image, alpha = _logo()
+
+    registry.add("logo", image, alpha, scale_range=(1, 2))
+    template = registry.get("logo")
+
+    np.testing.assert_array_equal(template.image, image)
+    np.testing.assert_array_equal(template.alpha, alpha)
+    assert template.scale_range == (1.0, 2.0)
+    assert registry.names() == ["logo"]
+    assert registry.list()[0]["width"] == 40 and registry.list()[0]["height"] == 24
+
+
+def test_add_rejects_invalid_input(registry):
+    image, alpha = _logo()
+    registry.add("logo", image, alpha)
+
+    with pytest.raises(ValueError, match="already exists"):
+        registry.add("logo", image, alpha)
+    registry.add("logo", image, alpha, overwrite=True)
+    for name in ("", "../etc", ".hidden", "a/b", "x" * 65):
+        with pytest.raises(ValueError, match="Invalid template name"):
+            registry.add(name, image, alpha)
+    with pytest.raises(ValueError, match="fully transparent"):
+        registry.add("blank", image, np.zeros_like(alpha))
+    with pytest.raises(ValueError, match="does not match"):
+        registry.add("cropped", image, alpha[:10])
+    with pytest.raises(ValueError, match="scale range"):
+        registry.add("scaled", image, alpha, scale_range=(2, 1))
+
+
+def test_delete(registry):
+    image, alpha = _logo()
+    registry.add("logo", image, alpha)
+
+    registry.delete("logo")
+
+    assert registry.names() == []
+    with pytest.raises(FileNotFoundError):
+        registry.get("logo")
+    with pytest.raises(FileNotFoundError):
+        registry.delete("logo")
+
+
+@pytest.mark.parametrize("scale", [1.0, 1.21])
+def test_match_finds_logo_at_its_scale(registry, scale):
+    image, alpha = _logo()
+    template = registry.add("logo", image, alpha, scale_range=(1.0, 2.0))
+
+    matches = match_templates(_stamp(_photo(), image, alpha, scale), [template], threshold=0.5)
+
+    assert matches
+    best = matches[0]
+    assert best.name == "logo"
+    assert abs(best.x - POSITION[0]) <= 2 and abs(best.y - POSITION[1]) <= 2
+    assert best.scale == pytest.approx(scale, abs=0.06)
+    assert best.score > 0.7
+
+
+def test_match_threshold(registry):
+    image, alpha = _logo()
+    registry.add("logo", image, alpha, scale_range=(1.0, 1.0))
+    marked = _stamp(_photo(), image, alpha)
+
+    mask, matches = template_mask(_photo(), registry, threshold=0.5)
+    assert matches == []
+    assert not mask.any()
+
+    mask, matches = template_mask(marked, registry, threshold=0.5)
+    assert len(matches) == 1
+    # The mask covers the logo's strokes, grown slightly, and nothing far from it.
+    x, y = POSITION
+    assert (mask[y:y + 24, x:x + 40][alpha > 0] == 255).all()
+    assert not mask[:y - 4].any() and not mask[y + 28:].any()
+    assert matches_to_mask(matches, (240, 160), grow=0).sum() < mask.sum()
+
+
+def _png(array):
+    buffer = io.BytesIO()
+    Image.fromarray(array).save(buffer, format="PNG")
+    return buffer.getvalue()
+
+
+@pytest.fixture
+def client(registry, monkeypatch):
+    pytest.importorskip("fastapi")
+    pytest.importorskip("celery")
+    pytest.importorskip("httpx")
+    from fastapi.testclient import TestClient
+
+    from watermark_remover.api import main
+
+    monkeypatch.setattr(main, "template_registry", registry)
+    return TestClient(main.app)
+
+
+def test_template_endpoints(client):
+    image, alpha = _logo()
+    logo = _png(np.dstack([image, alpha]))
+
+    response = client.post("/v1/templates", data={"name": "logo"}, files={"file": ("logo.png", logo, "image/png")})
+    assert response.status_code == 201
+    assert response.json()["width"] == 40
+    response = client.post("/v1/templates", data={"name": "logo"}, files={"file": ("logo.png", logo, "image/png")})
+    assert response.status_code == 409
+
+    assert [template["name"] for template in client.get("/v1/templates").json()["templates"]] == ["logo"]
+    assert client.get("/v1/templates/logo").json()["scale_range"] is None
+    response = client.get("/v1/templates/logo/image")
+    assert response.headers["content-type"] == "image/png"
+    np.testing.assert_array_equal(np.asarray(Image.open(io.BytesIO(response.content)))[..., 3], alpha)
+
+    response = client.put("/v1/templates/logo", data={"scale_min": "0.8", "scale_max": "1.5"},
+                          files={"file": ("logo.png", logo, "image/png")})
+    assert response.status_code == 200
+    assert response.json()["scale_range"] == [0.8, 1.5]
+    response = client.put("/v1/templates/logo", data={"scale_min": "0.8"},
+                          files={"file": ("logo.png", logo, "image/png")})
+    assert response.status_code == 400
+
+    assert client.delete("/v1/templates/logo").json() == {"deleted": "logo"}
+    assert client.get("/v1/templates/logo").status_code == 404
+    assert client.delete("/v1/templates/logo").status_code == 404
+    assert client.put("/v1/templates/logo", files={"file": ("logo.png", logo, "image/png")}).status_code == 404
+
+
+def test_template_endpoints_reject_invalid_names(client):
+    image, alpha = _logo()
+    logo = _png(np.dstack([image, alpha]))
+
+    response = client.post("/v1/templates", data={"name": "-logo"}, files={"file": ("logo.png", logo, "image/png")})
+    assert response.status_code == 400
+    assert client.get("/v1/templates/.hidden").status_code == 400
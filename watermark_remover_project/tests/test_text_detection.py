@@ -0,0 +1,73 @@
+import numpy as np
+import pytest
+
+cv2 = pytest.importorskip("cv2")
+
+from watermark_remover.core.text_detection import Glyph, detect_text, group_lines, stroke_width_variation
+
+ORANGE = (255, 140, 0)
+
+
+def _date_stamped():
+    """A dark 240x400 photo with an orange date stamp in the bottom-right corner."""
+    image = np.full((240, 400, 3), 60, dtype=np.uint8)
+    cv2.putText(image, "2024 05 17", (230, 220), cv2.FONT_HERSHEY_SIMPLEX, 0.8, ORANGE, 2, cv2.LINE_8)
+    text = (image != 60).any(axis=2)
+    return image, text
+
+
+def test_finds_date_stamp_with_glyph_tight_mask():
+    image, text = _date_stamped()
+
+    result = detect_text(image, corners=["bottom_right"], colors=["orange"], grow=0)
+
+    assert result.lines
+    assert result.confidence > 0.5
+    inside = result.mask > 0
+    assert inside[text].mean() > 0.8
+    # The mask follows the strokes rather than filling the line boxes.
+    assert inside.sum() < 2 * text.sum()
+    assert not inside[:150].any()
+
+
+def test_corner_and_colour_filters():
+    image, _ = _date_stamped()
+
+    assert detect_text(image, corners=["bottom_right"]).mask.any()
+    for result in (detect_text(image, corners=["top_left"]), detect_text(image, colors=["#0000ff"])):
+        assert not result.mask.any()
+        assert result.lines == []
+        assert result.confidence == 0.0
+
+
+def test_rejects_unknown_corner_and_colour():
+    image, _ = _date_stamped()
+
+    with pytest.raises(ValueError, match="Unknown corner 'middle'"):
+        detect_text(image, corners=["middle"])
+    with pytest.raises(ValueError, match="Unknown colour"):
+        detect_text(image, colors=["mauve-ish"])
+
+
+def test_stroke_width_variation():
+    bar = np.zeros((20, 40), dtype=np.uint8)
+    bar[8:12, 2:38] = 255
+
+    width, variation = stroke_width_variation(bar)
+
+    assert 2 <= width <= 6
+    assert variation < 0.3
+    assert stroke_width_variation(np.zeros((10, 10), dtype=np.uint8)) == (0.0, float("inf"))
+
+
+def test_group_lines_drops_isolated_glyphs():
+    def glyph(x, y):
+        return Glyph(box=(x, y, 8, 12), points=np.array([[x, y]]), variation=0.1)
+
+    row = [glyph(10, 20), glyph(22, 20), glyph(34, 21)]
+    lines = group_lines(row + [glyph(10, 80)], (100, 100), link=6)
+
+    assert len(lines) == 1
+    members, box = lines[0]
+    assert len(members) == 3
+    assert box == (10, 20, 32, 13)
@@ -0,0 +1,86 @@
+import numpy as np
+import pytest
+
+pytest.importorskip("cv2")
+
+from watermark_remover.core.backends import InpaintingBackend
+from watermark_remover.core.tiling import TiledInpainter, mask_regions, split_box, with_tiling
+
+
+class CountingBackend(InpaintingBackend):
+    """Fills the n-th tile it sees with 50 * n, so every tile is identifiable in the output."""
+
+    def __init__(self):
+        self.shapes = []
+
+    def predict(self, image, mask):
+        self.shapes.append(image.shape[:2])
+        result = image.copy()
+        result[mask > 0] = 50 * len(self.shapes)
+        return result
+
+
+@pytest.fixture
+def image_and_mask():
+    rng = np.random.default_rng(0)
+    image = rng.integers(0, 256, size=(40, 200, 3), dtype=np.uint8)
+    mask = np.zeros((40, 200), dtype=np.uint8)
+    mask[10:30, 10:190] = 255
+    return image, mask
+
+
+def test_known_pixels_unchanged(image_and_mask):
+    image, mask = image_and_mask
+    backend = CountingBackend()
+
+    result = TiledInpainter(backend, context_margin=4, tile_size=64, feather=8).predict(image, mask)
+
+    np.testing.assert_array_equal(result[mask == 0], image[mask == 0])
+    # The 28x188 box around the mask is split into four 28x64 tiles.
+    assert backend.shapes == [(28, 64)] * 4
+    assert (result[mask > 0] >= 50).all()
+
+
+def test_tile_seams_are_feathered(image_and_mask):
+    image, mask = image_and_mask
+
+    result = TiledInpainter(CountingBackend(), context_margin=4, tile_size=64, feather=8).predict(image, mask)
+
+    row = result[20, 10:190, 0].astype(int)
+    # Tiles start at x = 6, 54, 102 and 130; each later tile ramps in over 16 columns.
+    assert row[0] == 50 and row[-1] == 200
+    assert 50 < result[20, 60, 0] < 100
+    # Without feathering neighbouring tiles would differ by 50 at the seams.
+    assert np.abs(np.diff(row)).max() <= 4
+    assert (np.diff(row) >= 0).all()
+
+
+def test_mask_regions_merge_overlapping_boxes():
+    mask = np.zeros((100, 100), dtype=np.uint8)
+    mask[10:20, 10:20] = 255
+    mask[10:20, 30:40] = 255
+    mask[80:90, 80:90] = 255
+
+    # The first two components are 10 px apart: their margins overlap.
+    assert mask_regions(mask, 6) == [(4, 4, 26, 46), (74, 74, 96, 96)]
+    assert len(mask_regions(mask, 2)) == 3
+
+
+def test_split_box_covers_box_in_raster_order():
+    tiles = split_box((0, 0, 100, 150), tile_size=64, overlap=16)
+
+    assert tiles == sorted(tiles)
+    assert all(bottom - top <= 64 and right - left <= 64 for top, left, bottom, right in tiles)
+    covered = np.zeros((100, 150), dtype=bool)
+    for top, left, bottom, right in tiles:
+        covered[top:bottom, left:right] = True
+    assert covered.all()
+
+
+def test_with_tiling():
+    backend = CountingBackend()
+
+    assert with_tiling(backend, 0) is backend
+    assert isinstance(with_tiling(backend, 256), TiledInpainter)
+    with pytest.raises(ValueError, match="4 \\* feather"):
+        TiledInpainter(backend, tile_size=64, feather=16)
@@ -0,0 +1,205 @@
+import io
+import json
+import socket
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+import numpy as np
+import pytest
+from PIL import Image
+
+pytest.importorskip("cv2")
+pytest.importorskip("fastapi")
+pytest.importorskip("celery")
+pytest.importorskip("httpx")
+from fastapi.testclient import TestClient
+
+from watermark_remover.api.main import app
+from watermark_remover.config import settings
+from watermark_remover.tasks.celery_app import celery_app
+from watermark_remover.tasks.webhooks import (SIGNATURE_HEADER, TIMESTAMP_HEADER, DELIVERY_HEADER, post_webhook,
+                                              validate_callback_url, verify_signature)
+
+SECRET = "test-secret"
+
+
+class Receiver:
+    """Local HTTP stand-in for a webhook endpoint: answers with the queued status codes, then 200."""
+
+    def __init__(self):
+        self.requests = []
+        self.statuses = []
+        receiver = self
+
+        class Handler(BaseHTTPRequestHandler):
+            def do_POST(self):
+                body = self.rfile.read(int(self.headers["Content-Length"]))
+                receiver.requests.append((dict(self.headers), body))
+                self.send_response(receiver.statuses.pop(0) if receiver.statuses else 200)
+                self.end_headers()
+
+            def log_message(self, *args):
+                pass
+
+        self.server = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+        self.url = f"http://127.0.0.1:{self.server.server_address[1]}/hook"
+        threading.Thread(target=self.server.serve_forever, daemon=True).start()
+
+    def close(self):
+        self.server.shutdown()
+        self.server.server_close()
+
+
+@pytest.fixture
+def receiver():
+    receiver = Receiver()
+    yield receiver
+    receiver.close()
+
+
+@pytest.fixture
+def dns(monkeypatch):
+    """Resolves the hostnames added to the returned dict without the network; others as usual."""
+    records = {}
+    resolve = socket.getaddrinfo
+
+    def getaddrinfo(host, *args, **kwargs):
+        if host in records:
+            return [(socket.AF_INET, socket.SOCK_STREAM, socket.IPPROTO_TCP, "", (records[host], 0))]
+        return resolve(host, *args, **kwargs)
+
+    monkeypatch.setattr(socket, "getaddrinfo", getaddrinfo)
+    return records
+
+
+@pytest.fixture
+def client(tmp_path, monkeypatch):
+    """API client whose jobs and webhooks run eagerly, without waiting between retries."""
+    monkeypatch.setattr(settings, "jobs_dir", str(tmp_path / "jobs"))
+    monkeypatch.setattr(settings, "webhook_secret", SECRET)
+    monkeypatch.setattr(settings, "webhook_backoff", 0)
+    monkeypatch.setattr(settings, "webhook_max_retries", 2)
+    # The local receiver listens on loopback, which callbacks may only reach when allowed.
+    monkeypatch.setattr(settings, "webhook_allowed_hosts", "127.0.0.1")
+    previous = celery_app.conf.task_always_eager
+    celery_app.conf.task_always_eager = True
+    yield TestClient(app)
+    celery_app.conf.task_always_eager = previous
+
+
+def _png(array):
+    buffer = io.BytesIO()
+    Image.fromarray(array).save(buffer, format="PNG")
+    return buffer.getvalue()
+
+
+def _submit(client, callback_url, mask_shape=(24, 32)):
+    image = np.full((24, 32, 3), 120, dtype=np.uint8)
+    mask = np.zeros(mask_shape, dtype=np.uint8)
+    mask[2:6, 2:6] = 255
+    files = {"file": ("photo.png", _png(image), "image/png"), "mask": ("mask.png", _png(mask), "image/png")}
+    return client.post("/v1/jobs", files=files, data={"backend": "opencv_telea", "callback_url": callback_url})
+
+
+def test_signed_webhook_on_success(client, receiver):
+    response = _submit(client, receiver.url)
+    assert response.status_code == 202
+    job_id = response.json()["id"]
+
+    assert len(receiver.requests) == 1
+    headers, body = receiver.requests[0]
+    assert verify_signature(body, headers[TIMESTAMP_HEADER], headers[SIGNATURE_HEADER], SECRET)
+    assert not verify_signature(body, headers[TIMESTAMP_HEADER], headers[SIGNATURE_HEADER], "other-secret")
+    payload = json.loads(body)
+    assert payload["event"] == "job.succeeded"
+    assert payload["id"] == job_id
+    assert payload["result_url"] == f"http://testserver/v1/jobs/{job_id}/result"
+
+    deliveries = client.get(f"/v1/jobs/{job_id}/deliveries").json()
+    assert [(entry["attempt"], entry["status_code"], entry["delivered"]) for entry in deliveries] == [(1, 200, True)]
+
+
+def test_webhook_retried_until_delivered(client, receiver):
+    receiver.statuses = [500]
+    job_id = _submit(client, receiver.url).json()["id"]
+
+    deliveries = client.get(f"/v1/jobs/{job_id}/deliveries").json()
+    assert [(entry["status_code"], entry["delivered"]) for entry in deliveries] == [(500, False), (200, True)]
+    # Every attempt carries the same delivery id, so receivers can deduplicate.
+    assert len({headers[DELIVERY_HEADER] for headers, _ in receiver.requests}) == 1
+
+
+def test_failed_job_webhook_gives_up_after_retries(client, receiver):
+    receiver.statuses = [503] * 10
+    job_id = _submit(client, receiver.url, mask_shape=(10, 10)).json()["id"]
+
+    assert json.loads(receiver.requests[0][1])["event"] == "job.failed"
+    assert json.loads(receiver.requests[0][1])["result_url"] is None
+    deliveries = client.get(f"/v1/jobs/{job_id}/deliveries").json()
+    assert [entry["attempt"] for entry in deliveries] == [1, 2, 3]
+    assert not any(entry["delivered"] for entry in deliveries)
+    # Undeliverable webhooks do not change the job.
+    assert client.get(f"/v1/jobs/{job_id}").json()["status"] == "failed"
+
+
+@pytest.mark.parametrize("callback_url", ["ftp://example.com/hook", "/relative/hook", "not a url"])
+def test_invalid_callback_url_rejected(client, callback_url):
+    assert _submit(client, callback_url).status_code == 400
+
+
+def test_callback_requires_secret(client, receiver, monkeypatch):
+    monkeypatch.setattr(settings, "webhook_secret", None)
+    response = _submit(client, receiver.url)
+    assert response.status_code == 400
+    assert "not configured" in response.json()["detail"]
+    assert not receiver.requests
+
+
+@pytest.mark.parametrize("callback_url", [
+    "http://localhost/hook",
+    "http://10.0.0.5/hook",
+    "http://192.168.1.20:8080/hook",
+    "http://169.254.169.254/latest/meta-data/",
+    "http://[::1]/hook",
+    "http://[::ffff:10.0.0.5]/hook",
+    "http://0.0.0.0/hook",
+    "http://240.0.0.1/hook",
+    "http://100.64.0.1/hook",
+    "http://224.0.0.1/hook",
+])
+def test_non_public_callback_hosts_rejected(client, callback_url):
+    response = _submit(client, callback_url)
+
+    assert response.status_code == 400
+    assert "non-public address" in response.json()["detail"]
+
+
+def test_callback_host_is_resolved(client, dns):
+    dns["hooks.example.com"] = "93.184.216.34"
+    dns["hooks.internal.example"] = "10.1.2.3"
+
+    assert validate_callback_url("https://hooks.example.com/watermark") == "https://hooks.example.com/watermark"
+    with pytest.raises(ValueError, match="'hooks.internal.example' resolves to a non-public address"):
+        validate_callback_url("https://hooks.internal.example/watermark")
+    with pytest.raises(ValueError, match="could not be resolved"):
+        validate_callback_url("https://nowhere.invalid/watermark")
+
+
+def test_allowed_hosts_bypass_address_check(client, dns, monkeypatch):
+    dns["hooks.internal.example"] = "10.1.2.3"
+    monkeypatch.setattr(settings, "webhook_allowed_hosts", "other.example, HOOKS.internal.example")
+
+    assert validate_callback_url("http://hooks.internal.example:9000/hook")
+    with pytest.raises(ValueError, match="non-public address"):
+        validate_callback_url("http://127.0.0.1/hook")
+
+
+def test_delivery_rechecks_callback_host(client, dns):
+    # A host that resolved publicly at submission but points inward at delivery time.
+    dns["hooks.example.com"] = "127.0.0.1"
+
+    entry = post_webhook("http://hooks.example.com/hook", {"event": "job.succeeded"}, SECRET, "delivery")
+
+    assert entry["status_code"] is None
+    assert not entry["delivered"]
+    assert "non-public address" in entry["error"]
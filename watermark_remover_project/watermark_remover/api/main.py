@@ -1,66 +1,149 @@
 import fastapi
-from fastapi import FastAPI, File, UploadFile, HTTPException
-from starlette.responses import FileResponse
+from typing import List
+from urllib.parse import quote
+from fastapi import Depends, FastAPI, File, Form, Request, UploadFile, HTTPException
+from starlette.background import BackgroundTask
+from starlette.concurrency import run_in_threadpool
+from starlette.responses import Response
+import numpy as np
 import uvicorn
-import os
-import shutil
-import uuid
 
 # Adjust the import path based on the project structure
-# Assuming main.py is in watermark_remover/api/ and lama_wrapper.py is in watermark_remover/core/
-from watermark_remover.core.lama_wrapper import LaMaWrapper
+# Assuming main.py is in watermark_remover/api/ and the engines are in watermark_remover/core/
+from watermark_remover.config import settings
+from watermark_remover.core.backends import available_backends
+from watermark_remover.core.detection import detect_watermark
+from watermark_remover.core.pipeline import RemovalOptions, remove_watermark_bytes
+from watermark_remover.core.templates import get_template_registry
+from watermark_remover.tasks.batches import batch_progress, submit_batch, unpack_uploads
+from watermark_remover.tasks.jobs import RESULT, Job, get_job_store
+from watermark_remover.tasks.processing import remove_watermark_task
+from watermark_remover.tasks.retention import cleanup_stats, delete_batch, delete_record, expires_at, is_expired
+from watermark_remover.tasks.webhooks import validate_callback_url
+from watermark_remover.utils.image_io import encode_image, open_image, split_channels
+from watermark_remover.utils.mask_formats import EXPORT_FORMATS, MEDIA_TYPES, export_mask
 
 # Initialize FastAPI app
 app = FastAPI(title="Watermark Remover API", version="0.1.0")
 
-# Instantiate LaMaWrapper
-# For a PoC, a global instance is fine. For production, consider lifespan management.
-lama_wrapper = LaMaWrapper() # Assuming default model path or placeholder doesn't need one
+template_registry = get_template_registry()
 
-# Define temporary directories for API file handling
-# These paths are relative to the project root (where this script might be run from)
-# or need to be absolute. For simplicity, let's assume running from project root
-# or adjust paths accordingly.
-# For robustness, construct paths relative to this file's location.
-API_TEMP_BASE_DIR = os.path.join(os.path.dirname(__file__), "../../../data/api_temp")
-API_INPUT_DIR = os.path.join(API_TEMP_BASE_DIR, "input")
-API_OUTPUT_DIR = os.path.join(API_TEMP_BASE_DIR, "output")
 
-# Ensure these directories exist
-os.makedirs(API_INPUT_DIR, exist_ok=True)
-os.makedirs(API_OUTPUT_DIR, exist_ok=True)
+def removal_options_form(
+    mask_format: str = Form("auto"),
+    region: str = Form(None),
+    color_key: str = Form(None),
+    color_tolerance: float = Form(None),
+    backend: str = Form(None),
+    inpaint_radius: int = Form(None),
+    quality: str = Form(None),
+    inpaint_alpha: bool = Form(False),
+    keep_metadata: bool = Form(True),
+    jpeg_quality: int = Form(None),
+    min_confidence: float = Form(None),
+    templates: str = Form(None),
+    detect_text_overlays: bool = Form(False),
+    text_corners: str = Form(None),
+    text_colors: str = Form(None),
+    mask_dilate: int = Form(None),
+    mask_close: int = Form(None),
+    mask_min_area: int = Form(None),
+    mask_fill_holes: bool = Form(None),
+    mask_feather: int = Form(None),
+) -> RemovalOptions:
+    """
+    Processing options shared by the synchronous endpoint and jobs (see RemovalOptions).
+    Invalid options are rejected with 400 before anything is processed.
+    """
+    options = RemovalOptions(
+        backend=backend, inpaint_radius=inpaint_radius, quality=quality, inpaint_alpha=inpaint_alpha,
+        keep_metadata=keep_metadata, jpeg_quality=jpeg_quality, min_confidence=min_confidence,
+        mask_format=mask_format, region=region, color_key=color_key, color_tolerance=color_tolerance,
+        templates=templates, detect_text_overlays=detect_text_overlays, text_corners=text_corners,
+        text_colors=text_colors, mask_dilate=mask_dilate, mask_close=mask_close, mask_min_area=mask_min_area,
+        mask_fill_holes=mask_fill_holes, mask_feather=mask_feather,
+    )
+    try:
+        return options.validate()
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=str(e))
+
 
-@app.post("/v1/remove_watermark_single/", response_class=FileResponse)
-async def remove_watermark_single_endpoint(file: UploadFile = File(...)):
+@app.get("/v1/backends")
+async def list_backends_endpoint():
     """
-    Receives an image file, removes the watermark (placeholder: converts to grayscale),
-    and returns the processed image.
+    Lists the inpainting backends that can be passed as `backend`.
     """
-    temp_input_filename = f"temp_input_{uuid.uuid4()}_{file.filename}"
-    temp_output_filename = f"temp_output_{uuid.uuid4()}_{file.filename}"
-    
-    input_file_path = os.path.join(API_INPUT_DIR, temp_input_filename)
-    output_file_path = os.path.join(API_OUTPUT_DIR, temp_output_filename)
+    return {"default": settings.backend, "backends": available_backends()}
 
-    try:
-        # Save uploaded file
-        with open(input_file_path, "wb") as buffer:
-            shutil.copyfileobj(file.file, buffer)
 
-        # Process with LaMaWrapper
-        processed_image_path = lama_wrapper.remove_watermark(input_file_path, output_file_path)
+def _attachment(filename: str) -> str:
+    """
+    Content-Disposition header for a download, as Starlette's FileResponse builds it: names that
+    are not plain ASCII, or contain quotes or control characters, are sent percent-encoded
+    (RFC 6266 `filename*`), since header values must be latin-1 and cannot hold them raw.
+    """
+    if not filename:
+        return "attachment"
+    quoted = quote(filename)
+    if quoted != filename:
+        return f"attachment; filename*=utf-8''{quoted}"
+    return f'attachment; filename="{filename}"'
 
-        if not os.path.exists(processed_image_path):
-            raise HTTPException(status_code=500, detail="Error processing image: Output file not found.")
 
-        # Return processed image
-        # The FileResponse will automatically handle streaming the file.
-        # The media_type can be inferred from the filename or explicitly set.
-        return FileResponse(processed_image_path, media_type=file.content_type, filename=file.filename)
+@app.post("/v1/remove_watermark_single/")
+async def remove_watermark_single_endpoint(
+    file: UploadFile = File(...),
+    mask: UploadFile = File(None),
+    options: RemovalOptions = Depends(removal_options_form),
+):
+    """
+    Receives an image file and an optional binary mask image (same size, white marks
+    the watermark), removes the watermark and returns the processed image.
+    `mask_format` is "image", "coco" (COCO JSON, RLE or polygons), "voc" (palette PNG) or
+    "svg"; "auto" (default) recognises them from the upload.
+    `region` describes the mask as JSON shapes instead (rect, polygon or ellipse, in pixels
+    or with "units": "relative", anchored to a corner); with a mask upload both are combined.
+    `color_key` (comma-separated colours, e.g. "white" or "#d01020") selects pixels within
+    `color_tolerance` (CIE Lab distance) of those colours, only inside `region` if given.
+    `backend` selects the inpainting engine; it defaults to the configured one.
+    `inpaint_radius` sets the neighbourhood radius of the OpenCV engines, which LaMa
+    also uses when no weights are configured.
+    `quality` is "fast", "balanced" or "high"; the latter two refine a coarse fill at
+    increasing resolutions for sharper results on large regions.
+    The output keeps the upload's colour mode, alpha and ICC profile; `inpaint_alpha`,
+    `keep_metadata` (EXIF/XMP) and `jpeg_quality` control how it is written.
+    Without a mask the watermark is detected automatically; the image is only changed if
+    the detection confidence (returned in the X-Watermark-Confidence header) reaches
+    `min_confidence`. `templates` (comma-separated names, or "*" for all) builds the mask
+    from logos of the template library found in the image instead.
+    `detect_text_overlays` builds a glyph-tight mask from text such as date stamps,
+    optionally restricted to `text_corners` (e.g. "bottom_right") and `text_colors`
+    (e.g. "orange" or "#ff8c00"), both comma-separated.
+    Whatever its source, the mask is refined before inpainting: `mask_close` and
+    `mask_dilate` (radii in pixels), `mask_min_area` (smallest kept component),
+    `mask_fill_holes` and `mask_feather` (soft edge width) default to the configured values.
+    Everything is processed in memory; nothing is written to disk. For large images or
+    many requests, submit a job to /v1/jobs instead.
+    """
+    try:
+        image_bytes = await file.read()
+        mask_bytes = await mask.read() if mask else None
+        # Inference is CPU-bound; keep it off the event loop.
+        result = await run_in_threadpool(remove_watermark_bytes, image_bytes, file.filename, options, mask_bytes,
+                                         mask.filename if mask else None, template_registry)
+
+        return Response(
+            content=result.content,
+            media_type=result.media_type,
+            headers={"Content-Disposition": _attachment(file.filename), **result.headers},
+        )
 
     except FileNotFoundError as e:
-        # Specific error from LaMaWrapper if input file to it was not found (should be caught by earlier checks too)
         raise HTTPException(status_code=404, detail=f"File not found during processing: {e}")
+    except ValueError as e:
+        # Undecodable upload or invalid mask, e.g. its size does not match the image
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
     except HTTPException as e:
         # Re-raise HTTPExceptions if they are already raised
         raise e
@@ -68,22 +151,360 @@ async def remove_watermark_single_endpoint(file: UploadFile = File(...)):
         # Catch-all for other errors
         # Log the error for debugging: print(f"Unexpected error: {e}")
         raise HTTPException(status_code=500, detail=f"An unexpected error occurred: {str(e)}")
-    finally:
-        # Cleanup: Delete temporary input and output files
-        if os.path.exists(input_file_path):
-            os.remove(input_file_path)
-        # The output_file_path might be the same as processed_image_path
-        if os.path.exists(output_file_path) and output_file_path != processed_image_path: # just in case wrapper changes output path
-             os.remove(output_file_path)
-        # If processed_image_path is different and exists, it should also be cleaned up if not returned directly by FileResponse
-        # However, FileResponse handles the file, so usually we don't delete it here unless it's a copy
-        # For this PoC, if processed_image_path IS output_file_path, it's handled by FileResponse.
-        # If LaMaWrapper could return a *different* path than the one we provided for output,
-        # then that temporary file also needs cleanup. Assuming it uses the provided output_file_path.
+
+def _job_response(job: Job) -> dict:
+    response = job.to_dict()
+    response.pop("options", None)
+    response.update(status_url=f"/v1/jobs/{job.id}", result_url=f"/v1/jobs/{job.id}/result",
+                    expires_at=expires_at(job))
+    return response
+
+
+def _callback(request: Request, callback_url: str):
+    """Validated (callback URL, public base URL) of a submission; (None, None) without a callback."""
+    if not callback_url:
+        return None, None
+    try:
+        validate_callback_url(callback_url)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+    return callback_url, settings.public_url or str(request.base_url)
+
+
+@app.post("/v1/jobs", status_code=202)
+async def submit_job_endpoint(
+    request: Request,
+    file: UploadFile = File(...),
+    mask: UploadFile = File(None),
+    callback_url: str = Form(None),
+    options: RemovalOptions = Depends(removal_options_form),
+):
+    """
+    Queues an image for background processing and returns its job id right away.
+    Takes the same fields as /v1/remove_watermark_single/. Poll GET /v1/jobs/{id} for
+    the status ("queued", "running", "succeeded" or "failed") and progress, then fetch
+    the processed image from GET /v1/jobs/{id}/result.
+
+    With `callback_url`, a signed JSON payload with the status and result URL is also
+    POSTed there when the job finishes (see watermark_remover.tasks.webhooks); the
+    attempts are listed by GET /v1/jobs/{id}/deliveries.
+    """
+    callback_url, base_url = _callback(request, callback_url)
+    image_bytes = await file.read()
+    try:
+        # Reject undecodable uploads now rather than in a failed job.
+        open_image(image_bytes)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+
+    store = get_job_store()
+    job = store.create(image_bytes, file.filename, options.to_dict(), mask_bytes=await mask.read() if mask else None,
+                       mask_filename=mask.filename if mask else None, callback_url=callback_url, base_url=base_url)
+    try:
+        # Runs the task in place in eager mode, so keep it off the event loop.
+        await run_in_threadpool(remove_watermark_task.delay, job.id)
+    except Exception as e:
+        store.update(job.id, status="failed", stage="failed", error=f"Could not queue job: {e}")
+        raise HTTPException(status_code=503, detail=f"Could not queue job: {e}")
+    return _job_response(store.get(job.id))
+
+
+@app.get("/v1/jobs/{job_id}")
+async def get_job_endpoint(job_id: str):
+    """
+    Returns a job's status, progress, processing stage and, for failed jobs, the error.
+    """
+    try:
+        return _job_response(get_job_store().get(job_id))
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+
+
+@app.get("/v1/jobs/{job_id}/result")
+async def get_job_result_endpoint(job_id: str):
+    """
+    Returns the processed image of a succeeded job, with the same headers as
+    /v1/remove_watermark_single/. Unfinished and failed jobs give 409, expired ones 410.
+    The job is deleted once the result has been sent, unless
+    WATERMARK_REMOVER_DELETE_AFTER_DOWNLOAD=0 keeps it until it expires.
+    """
+    store = get_job_store()
+    try:
+        job = store.get(job_id)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    if job.status != "succeeded":
+        detail = f"Job {job_id} is {job.status}" + (f": {job.error}" if job.error else "")
+        raise HTTPException(status_code=409, detail=detail)
+    if is_expired(job):
+        raise HTTPException(status_code=410, detail=f"The result of job {job_id} has expired")
+    return Response(
+        content=store.read(job_id, RESULT),
+        media_type=job.media_type,
+        headers={"Content-Disposition": _attachment(job.filename), **job.headers},
+        background=BackgroundTask(delete_record, store, job_id) if settings.delete_after_download else None,
+    )
+
+
+@app.get("/v1/jobs/{job_id}/deliveries")
+async def get_job_deliveries_endpoint(job_id: str):
+    """
+    Returns the webhook delivery log of a job: one entry per attempt with the HTTP
+    status or error, oldest first.
+    """
+    store = get_job_store()
+    try:
+        store.get(job_id)
+        return store.deliveries(job_id)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+
+
+def _batch_response(store, batch) -> dict:
+    response = batch_progress(store, batch)
+    response.update(status_url=f"/v1/batches/{batch.id}", result_url=f"/v1/batches/{batch.id}/result",
+                    expires_at=expires_at(batch))
+    return response
+
+
+@app.post("/v1/batches", status_code=202)
+async def submit_batch_endpoint(
+    request: Request,
+    files: List[UploadFile] = File(...),
+    callback_url: str = Form(None),
+    options: RemovalOptions = Depends(removal_options_form),
+):
+    """
+    Queues many images at once: several `files`, ZIP archives of images, or both. Each image
+    becomes a job processed with the same options as /v1/remove_watermark_single/ (masks
+    can come from `region`, `color_key`, `templates`, text or automatic detection).
+    Poll GET /v1/batches/{id} for per-file status and overall progress, then download
+    GET /v1/batches/{id}/result: a ZIP of the processed images under their upload names
+    plus `manifest.json` with each file's status and error. With `callback_url`, a signed
+    webhook is sent once the archive is built, as for jobs.
+    """
+    callback_url, base_url = _callback(request, callback_url)
+    uploads = [(upload.filename, await upload.read()) for upload in files]
+    try:
+        images, skipped = unpack_uploads(uploads)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+
+    store = get_job_store()
+    try:
+        # Runs every task in place in eager mode, so keep it off the event loop.
+        batch = await run_in_threadpool(submit_batch, store, images, options.to_dict(), skipped,
+                                        callback_url, base_url)
+    except Exception as e:
+        raise HTTPException(status_code=503, detail=f"Could not queue batch: {e}")
+    return _batch_response(store, batch)
+
+
+@app.get("/v1/batches/{batch_id}")
+async def get_batch_endpoint(batch_id: str):
+    """
+    Returns a batch's status ("running" until its archive is built), overall progress and
+    every file's job status and error.
+    """
+    store = get_job_store()
+    try:
+        return _batch_response(store, store.get_batch(batch_id))
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+
+
+@app.get("/v1/batches/{batch_id}/result")
+async def get_batch_result_endpoint(batch_id: str):
+    """
+    Returns the ZIP of a finished batch: processed images and `manifest.json`.
+    Batches still running, or whose archive could not be built, give 409; expired
+    ones give 410. The batch and its jobs are deleted once the archive has been sent,
+    unless WATERMARK_REMOVER_DELETE_AFTER_DOWNLOAD=0 keeps them until they expire.
+    """
+    store = get_job_store()
+    try:
+        batch = store.get_batch(batch_id)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    if batch.status != "succeeded":
+        detail = f"Batch {batch_id} is {batch.status}" + (f": {batch.error}" if batch.error else "")
+        raise HTTPException(status_code=409, detail=detail)
+    if is_expired(batch):
+        raise HTTPException(status_code=410, detail=f"The result of batch {batch_id} has expired")
+    return Response(
+        content=store.read(batch_id, RESULT),
+        media_type="application/zip",
+        headers={"Content-Disposition": f'attachment; filename="watermark-removed-{batch_id}.zip"'},
+        background=BackgroundTask(delete_batch, store, batch) if settings.delete_after_download else None,
+    )
+
+
+@app.get("/v1/batches/{batch_id}/deliveries")
+async def get_batch_deliveries_endpoint(batch_id: str):
+    """
+    Returns the webhook delivery log of a batch, as for jobs.
+    """
+    store = get_job_store()
+    try:
+        store.get_batch(batch_id)
+        return store.deliveries(batch_id)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+
+
+@app.get("/v1/cleanup")
+async def get_cleanup_stats_endpoint():
+    """
+    Returns the metrics of the last periodic cleanup (jobs, batches and files deleted,
+    bytes reclaimed and kept) and totals over all runs.
+    """
+    return cleanup_stats(get_job_store())
+
+
+@app.post("/v1/detect_watermark/")
+async def detect_watermark_endpoint(
+    file: UploadFile = File(...),
+    min_confidence: float = Form(None),
+    mask_format: str = Form("png"),
+):
+    """
+    Proposes a watermark mask for an image without processing it. Returns the mask as a
+    PNG (white marks the watermark) with the detection confidence in the
+    X-Watermark-Confidence header and whether it reaches `min_confidence` in
+    X-Watermark-Detected.
+    `mask_format` exports it for labelling tools instead: "coco" (COCO JSON with RLE),
+    "coco_polygons", "voc" (palette PNG) or "svg". Edited masks can be uploaded back to
+    /v1/remove_watermark_single/ as they are.
+    """
+    min_confidence = settings.detection_min_confidence if min_confidence is None else min_confidence
+    if mask_format not in EXPORT_FORMATS:
+        raise HTTPException(status_code=400, detail=f"Unknown mask_format '{mask_format}'. Expected one of: {', '.join(EXPORT_FORMATS)}")
+    try:
+        source = open_image(await file.read())
+        detection = await run_in_threadpool(detect_watermark, split_channels(source)[0], min_confidence)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+
+    return Response(
+        content=export_mask(detection.mask, mask_format, file_name=file.filename, score=detection.confidence),
+        media_type=MEDIA_TYPES[mask_format],
+        headers={
+            "X-Watermark-Confidence": f"{detection.confidence:.3f}",
+            "X-Watermark-Detected": str(detection.detected).lower(),
+        },
+    )
+
+def _template_upload(image_bytes: bytes, alpha_bytes: bytes = None):
+    """RGB and alpha arrays from an uploaded logo (and optional opacity image)."""
+    image, alpha = split_channels(open_image(image_bytes))
+    if alpha_bytes is not None:
+        alpha = np.asarray(open_image(alpha_bytes).convert("L"))
+    return image, alpha
+
+
+def _scale_range(scale_min: float, scale_max: float):
+    if scale_min is None and scale_max is None:
+        return None
+    if scale_min is None or scale_max is None:
+        raise HTTPException(status_code=400, detail="Give both scale_min and scale_max, or neither")
+    return scale_min, scale_max
+
+
+@app.get("/v1/templates")
+async def list_templates_endpoint():
+    """
+    Lists the watermark templates in the library.
+    """
+    return {"templates": template_registry.list()}
+
+
+@app.post("/v1/templates", status_code=201)
+async def create_template_endpoint(
+    name: str = Form(...),
+    file: UploadFile = File(...),
+    alpha: UploadFile = File(None),
+    scale_min: float = Form(None),
+    scale_max: float = Form(None),
+):
+    """
+    Registers a logo. Its transparency is used as alpha unless a grayscale `alpha` image
+    (white = opaque) is uploaded. `scale_min`/`scale_max` restrict the scales searched.
+    """
+    scale_range = _scale_range(scale_min, scale_max)
+    try:
+        if template_registry.exists(name):
+            raise HTTPException(status_code=409, detail=f"Template '{name}' already exists")
+        image, alpha_array = _template_upload(await file.read(), await alpha.read() if alpha else None)
+        template = template_registry.add(name, image, alpha_array, scale_range=scale_range)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+    return template.metadata()
+
+
+@app.get("/v1/templates/{name}")
+async def get_template_endpoint(name: str):
+    """
+    Returns a template's metadata.
+    """
+    try:
+        return template_registry.get(name).metadata()
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+
+
+@app.get("/v1/templates/{name}/image")
+async def get_template_image_endpoint(name: str):
+    """
+    Returns a template as an RGBA PNG.
+    """
+    try:
+        template = template_registry.get(name)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+    return Response(content=encode_image(template.to_rgba(), "PNG"), media_type="image/png")
+
+
+@app.put("/v1/templates/{name}")
+async def update_template_endpoint(
+    name: str,
+    file: UploadFile = File(...),
+    alpha: UploadFile = File(None),
+    scale_min: float = Form(None),
+    scale_max: float = Form(None),
+):
+    """
+    Replaces an existing template's logo, alpha and scale range.
+    """
+    scale_range = _scale_range(scale_min, scale_max)
+    try:
+        if not template_registry.exists(name):
+            raise HTTPException(status_code=404, detail=f"Template not found: {name}")
+        image, alpha_array = _template_upload(await file.read(), await alpha.read() if alpha else None)
+        template = template_registry.add(name, image, alpha_array, scale_range=scale_range, overwrite=True)
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+    return template.metadata()
+
+
+@app.delete("/v1/templates/{name}")
+async def delete_template_endpoint(name: str):
+    """
+    Deletes a template.
+    """
+    try:
+        template_registry.delete(name)
+    except FileNotFoundError as e:
+        raise HTTPException(status_code=404, detail=str(e))
+    except ValueError as e:
+        raise HTTPException(status_code=400, detail=f"Invalid input: {e}")
+    return {"deleted": name}
 
 if __name__ == "__main__":
     # This allows running the app with `python main.py`
     # The string "main:app" refers to the file `main.py` and the variable `app`.
     # `reload=True` is useful for development.
     uvicorn.run("main:app", host="0.0.0.0", port=8000, reload=True)
-```
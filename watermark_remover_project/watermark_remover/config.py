@@ -0,0 +1,185 @@
+"""
+Runtime settings, read from environment variables.
+
+All variables are prefixed with WATERMARK_REMOVER_, e.g.
+WATERMARK_REMOVER_BACKEND=opencv_telea selects the OpenCV Telea engine.
+"""
+
+import os
+from dataclasses import dataclass
+
+ENV_PREFIX = "WATERMARK_REMOVER_"
+
+
+def _env(name: str, default=None):
+    return os.environ.get(ENV_PREFIX + name, default)
+
+
+@dataclass
+class Settings:
+    # Name of the inpainting backend (see watermark_remover.core.backends.available_backends()).
+    backend: str = "lama"
+    # Local LaMa TorchScript file or checkpoint, for backends that need one.
+    model_path: str = None
+    # Torch device for the LaMa backend.
+    device: str = "cpu"
+    # Load LaMa checkpoints the weights-only unpickler rejects with the full unpickler, which can
+    # execute code from the file. Only enable for trusted model files.
+    lama_allow_pickle: bool = False
+    # Neighbourhood radius for the OpenCV engines, including LaMa's fallback without weights.
+    inpaint_radius: int = 3
+    # Quality preset: "fast" (single pass), "balanced" or "high" (multi-scale refinement).
+    quality: str = "fast"
+    # Crop-around-mask tiling: maximum crop side passed to the engine (0 disables tiling),
+    # known context kept around each mask component, and blend width between tiles.
+    tile_size: int = 1024
+    tile_margin: int = 128
+    tile_feather: int = 32
+    # ONNX Runtime intra-op threads for the lama_onnx backend; 0 means one per core.
+    intra_op_num_threads: int = 0
+    # Batched inference: most images per forward pass, and an optional cap on the estimated
+    # memory of one pass in MB (0 means no cap).
+    batch_size: int = 4
+    memory_budget_mb: int = 0
+    # Known-watermark template (.npz from scripts/estimate_watermark.py) for the reverse_blend backend,
+    # and the backend it uses where the watermark is too opaque or the image clipped.
+    template_path: str = None
+    reverse_blend_fallback: str = "lama"
+    # Folder of the logo template library (watermark_remover.core.templates; with the s3 storage
+    # backend, templates are stored under s3_templates_prefix in the bucket instead), and the minimum
+    # correlation for a template match (also used by reverse_blend to locate its template).
+    templates_dir: str = "watermark_templates"
+    template_match_threshold: float = 0.5
+    # Automatic detection, used when no mask is given: confidence needed to inpaint the proposal.
+    detection_min_confidence: float = 0.35
+    # Mask refinement before the engine (watermark_remover.utils.mask_refinement): dilation and
+    # closing radii, smallest kept component in pixels, hole filling, and feather width.
+    mask_dilate: int = 0
+    mask_close: int = 0
+    mask_min_area: int = 0
+    mask_fill_holes: bool = False
+    mask_feather: int = 0
+    # Colour-keyed masks (watermark_remover.core.color_mask): largest CIE Lab distance to a key colour.
+    color_key_tolerance: float = 12.0
+    # Asynchronous jobs (watermark_remover.tasks): Celery broker and result backend (needed by
+    # batches, which run as chords), job folder shared by the API and the workers, and eager mode
+    # (tasks run in the API process, for tests).
+    celery_broker_url: str = "redis://localhost:6379/0"
+    celery_result_backend: str = "redis://localhost:6379/1"
+    jobs_dir: str = "data/jobs"
+    celery_eager: bool = False
+    # Where job artifacts and templates are stored (watermark_remover.storage): "local" (under jobs_dir
+    # and templates_dir) or "s3". For s3: bucket, key prefixes of jobs and templates, endpoint of
+    # S3-compatible services such as MinIO, region and credentials (boto3's usual credential chain when unset).
+    storage_backend: str = "local"
+    s3_bucket: str = None
+    s3_prefix: str = "jobs"
+    s3_templates_prefix: str = "templates"
+    s3_endpoint_url: str = None
+    s3_region: str = None
+    s3_access_key: str = None
+    s3_secret_key: str = None
+    # Retention (watermark_remover.tasks.retention), in seconds: how long finished jobs and batches
+    # are kept, how long unfinished ones may stay queued or running, and how often the Celery beat
+    # cleanup runs (0 disables each). Results are deleted in the background once they have been
+    # downloaded; without delete_after_download they are kept until result_ttl.
+    result_ttl: int = 86400
+    job_max_age: int = 604800
+    cleanup_interval: int = 3600
+    delete_after_download: bool = True
+    # Batch uploads: most images per batch, and the largest total size of the images in MB.
+    batch_max_files: int = 500
+    batch_max_mb: int = 1024
+    # Webhooks (watermark_remover.tasks.webhooks): HMAC signing secret (callbacks are refused without
+    # one), request timeout in seconds, retries after the first attempt, and the first retry delay in
+    # seconds (doubled on each retry). Callback hosts must resolve to public addresses unless listed
+    # (comma-separated) in the allowed hosts. The public URL of the API, used for result links in
+    # payloads, defaults to the URL the job was submitted to.
+    webhook_secret: str = None
+    webhook_timeout: float = 10.0
+    webhook_max_retries: int = 5
+    webhook_backoff: float = 30.0
+    webhook_allowed_hosts: str = None
+    public_url: str = None
+
+    @classmethod
+    def from_env(cls) -> "Settings":
+        return cls(
+            backend=_env("BACKEND", cls.backend),
+            model_path=_env("MODEL_PATH", cls.model_path),
+            device=_env("DEVICE", cls.device),
+            lama_allow_pickle=_env("LAMA_ALLOW_PICKLE", "0").lower() in ("1", "true", "yes"),
+            inpaint_radius=int(_env("INPAINT_RADIUS", cls.inpaint_radius)),
+            quality=_env("QUALITY", cls.quality),
+            tile_size=int(_env("TILE_SIZE", cls.tile_size)),
+            tile_margin=int(_env("TILE_MARGIN", cls.tile_margin)),
+            tile_feather=int(_env("TILE_FEATHER", cls.tile_feather)),
+            intra_op_num_threads=int(_env("INTRA_OP_THREADS", cls.intra_op_num_threads)),
+            batch_size=int(_env("BATCH_SIZE", cls.batch_size)),
+            memory_budget_mb=int(_env("MEMORY_BUDGET_MB", cls.memory_budget_mb)),
+            template_path=_env("TEMPLATE_PATH", cls.template_path),
+            reverse_blend_fallback=_env("REVERSE_BLEND_FALLBACK", cls.reverse_blend_fallback),
+            templates_dir=_env("TEMPLATES_DIR", cls.templates_dir),
+            template_match_threshold=float(_env("TEMPLATE_MATCH_THRESHOLD", cls.template_match_threshold)),
+            detection_min_confidence=float(_env("DETECTION_MIN_CONFIDENCE", cls.detection_min_confidence)),
+            mask_dilate=int(_env("MASK_DILATE", cls.mask_dilate)),
+            mask_close=int(_env("MASK_CLOSE", cls.mask_close)),
+            mask_min_area=int(_env("MASK_MIN_AREA", cls.mask_min_area)),
+            mask_fill_holes=_env("MASK_FILL_HOLES", "0").lower() in ("1", "true", "yes"),
+            mask_feather=int(_env("MASK_FEATHER", cls.mask_feather)),
+            color_key_tolerance=float(_env("COLOR_KEY_TOLERANCE", cls.color_key_tolerance)),
+            celery_broker_url=_env("CELERY_BROKER_URL", cls.celery_broker_url),
+            celery_result_backend=_env("CELERY_RESULT_BACKEND", cls.celery_result_backend),
+            jobs_dir=_env("JOBS_DIR", cls.jobs_dir),
+            celery_eager=_env("CELERY_EAGER", "0").lower() in ("1", "true", "yes"),
+            storage_backend=_env("STORAGE_BACKEND", cls.storage_backend),
+            s3_bucket=_env("S3_BUCKET", cls.s3_bucket),
+            s3_prefix=_env("S3_PREFIX", cls.s3_prefix),
+            s3_templates_prefix=_env("S3_TEMPLATES_PREFIX", cls.s3_templates_prefix),
+            s3_endpoint_url=_env("S3_ENDPOINT_URL", cls.s3_endpoint_url),
+            s3_region=_env("S3_REGION", cls.s3_region),
+            s3_access_key=_env("S3_ACCESS_KEY", cls.s3_access_key),
+            s3_secret_key=_env("S3_SECRET_KEY", cls.s3_secret_key),
+            result_ttl=int(_env("RESULT_TTL", cls.result_ttl)),
+            job_max_age=int(_env("JOB_MAX_AGE", cls.job_max_age)),
+            cleanup_interval=int(_env("CLEANUP_INTERVAL", cls.cleanup_interval)),
+            delete_after_download=_env("DELETE_AFTER_DOWNLOAD", "1").lower() in ("1", "true", "yes"),
+            batch_max_files=int(_env("BATCH_MAX_FILES", cls.batch_max_files)),
+            batch_max_mb=int(_env("BATCH_MAX_MB", cls.batch_max_mb)),
+            webhook_secret=_env("WEBHOOK_SECRET", cls.webhook_secret),
+            webhook_timeout=float(_env("WEBHOOK_TIMEOUT", cls.webhook_timeout)),
+            webhook_max_retries=int(_env("WEBHOOK_MAX_RETRIES", cls.webhook_max_retries)),
+            webhook_backoff=float(_env("WEBHOOK_BACKOFF", cls.webhook_backoff)),
+            webhook_allowed_hosts=_env("WEBHOOK_ALLOWED_HOSTS", cls.webhook_allowed_hosts),
+            public_url=_env("PUBLIC_URL", cls.public_url),
+        )
+
+    def backend_options(self) -> dict:
+        """Options shared by all backends; each backend only receives those it accepts."""
+        return {
+            "model_path": self.model_path,
+            "device": self.device,
+            "allow_pickle": self.lama_allow_pickle,
+            "radius": self.inpaint_radius,
+            "fallback_radius": self.inpaint_radius,
+            "intra_op_num_threads": self.intra_op_num_threads,
+            "template_path": self.template_path,
+            "fallback_backend": self.reverse_blend_fallback,
+            "match_threshold": self.template_match_threshold,
+        }
+
+    def tiling_options(self) -> dict:
+        """Keyword arguments for watermark_remover.core.tiling.with_tiling."""
+        return {"tile_size": self.tile_size, "context_margin": self.tile_margin, "feather": self.tile_feather}
+
+    def batch_options(self) -> dict:
+        """Keyword arguments for InpaintingBackend.inpaint_batch."""
+        return {"max_batch_size": self.batch_size, "memory_budget_mb": self.memory_budget_mb or None}
+
+    def mask_refinement_options(self) -> dict:
+        """Keyword arguments for watermark_remover.utils.mask_refinement.MaskRefinement."""
+        return {"dilate": self.mask_dilate, "close": self.mask_close, "min_area": self.mask_min_area,
+                "fill_holes": self.mask_fill_holes, "feather": self.mask_feather}
+
+
+settings = Settings.from_env()
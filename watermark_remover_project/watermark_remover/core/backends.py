@@ -0,0 +1,298 @@
+"""
+Inpainting backend interface and registry.
+
+Every engine (LaMa, OpenCV Telea/Navier-Stokes, PatchMatch, ...) implements
+`InpaintingBackend` and shares the same image/mask contract:
+
+- image: RGB uint8 array of shape (H, W, 3)
+- mask:  uint8 array of shape (H, W); non-zero pixels are inpainted
+- result: RGB uint8 array of shape (H, W, 3); pixels outside the mask are unchanged
+
+Backends are registered by name with an import path so that selecting one
+backend never imports the heavy dependencies of another (e.g. torch).
+"""
+
+import abc
+import importlib
+import inspect
+import os
+from typing import Callable, Dict, List, Optional, Sequence, Tuple, Union
+
+from PIL import Image
+import numpy as np
+
+from watermark_remover.core.detection import DEFAULT_MIN_CONFIDENCE, detect_watermark
+from watermark_remover.utils.image_io import ImageInput, merge_channels, open_image, save_image, split_channels
+from watermark_remover.utils.mask_refinement import MaskRefinement, blend
+from watermark_remover.utils.masks import MaskInput, load_mask
+
+
+class InpaintingBackend(abc.ABC):
+    # Registry name, filled in by `create_backend`.
+    name: str = None
+
+    @abc.abstractmethod
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        """
+        Inpaints the masked pixels of a single image.
+
+        Args:
+            image (np.ndarray): RGB image, uint8 array of shape (H, W, 3).
+            mask (np.ndarray): Array of shape (H, W); non-zero pixels are inpainted.
+
+        Returns:
+            np.ndarray: Inpainted RGB image, uint8 array of shape (H, W, 3).
+        """
+
+    def predict_batch(self, images: Sequence[np.ndarray], masks: Sequence[np.ndarray], max_batch_size: int = 4,
+                      memory_budget_mb: int = None) -> List[np.ndarray]:
+        """
+        Inpaints several images. Backends that can vectorise over a batch override this;
+        the default runs `predict` on each image.
+
+        Args:
+            images (Sequence[np.ndarray]): RGB uint8 images of shape (H, W, 3); sizes may differ.
+            masks (Sequence[np.ndarray]): Matching (H, W) masks.
+            max_batch_size (int, optional): Upper bound on images per forward pass. Defaults to 4.
+            memory_budget_mb (int, optional): Upper bound on estimated memory per forward pass.
+
+        Returns:
+            List[np.ndarray]: Inpainted images, in input order.
+        """
+        return [self.predict(image, mask) for image, mask in zip(images, masks)]
+
+    def inpaint(self, image: ImageInput, mask: MaskInput = None, inpaint_alpha: bool = False,
+                min_confidence: float = DEFAULT_MIN_CONFIDENCE,
+                mask_refinement: MaskRefinement = None) -> Image.Image:
+        """
+        Removes a watermark from an in-memory image.
+
+        The result keeps the input's colour mode (RGBA, grayscale, palette, CMYK,
+        16-bit), alpha channel and ICC profile/metadata in `info`; only masked
+        pixels change.
+
+        Args:
+            image (PIL.Image.Image | np.ndarray | bytes): The image, as a PIL image, an array
+                or the encoded bytes of an image file.
+            mask (str | bytes | np.ndarray | PIL.Image.Image, optional): Where the watermark is,
+                white/non-zero marking watermark pixels. Must match the image size.
+                Defaults to None: the watermark is detected automatically.
+            inpaint_alpha (bool, optional): Also inpaint the alpha channel of transparent
+                images instead of keeping it untouched. Defaults to False.
+            min_confidence (float, optional): Without a mask, the detection confidence needed
+                to inpaint; below it the image is returned unchanged.
+            mask_refinement (MaskRefinement, optional): Dilation, closing, speckle removal,
+                hole filling and feathering applied to the mask before the engine runs.
+
+        Returns:
+            PIL.Image.Image: The processed image.
+
+        Raises:
+            FileNotFoundError: If a mask path does not exist.
+            ValueError: If the image cannot be decoded, or the mask is invalid or its size
+                does not match the image.
+        """
+        image = open_image(image)
+        mask_array, _ = self.resolve_mask(image, mask, min_confidence)
+        mask_array, weight = (mask_refinement or MaskRefinement()).apply(mask_array)
+        rgb, alpha = split_channels(image)
+
+        print(f"Processing image (backend: {self.name or type(self).__name__}, mode: {image.mode}, size: {image.size})")
+        result = blend(rgb, self.predict(rgb, mask_array), weight)
+        if alpha is not None and inpaint_alpha:
+            alpha_rgb = np.repeat(alpha[..., None], 3, axis=2)
+            alpha = blend(alpha_rgb, self.predict(alpha_rgb, mask_array), weight)[..., 0]
+        return merge_channels(image, result, mask_array, alpha)
+
+    def inpaint_batch(self, images: Sequence[ImageInput], masks: Sequence[MaskInput] = None,
+                      inpaint_alpha: bool = False, max_batch_size: int = 4,
+                      memory_budget_mb: int = None,
+                      min_confidence: float = DEFAULT_MIN_CONFIDENCE,
+                      mask_refinement: MaskRefinement = None) -> List[Image.Image]:
+        """
+        Batched counterpart of `inpaint`: similar-sized images are run together.
+
+        Args:
+            images (Sequence): Images as accepted by `inpaint`.
+            masks (Sequence, optional): One mask (or None to detect it) per image.
+                Defaults to detecting every mask.
+            inpaint_alpha (bool, optional): See `inpaint`.
+            max_batch_size (int, optional): Upper bound on images per forward pass. Defaults to 4.
+            memory_budget_mb (int, optional): Upper bound on estimated memory per forward pass.
+            min_confidence (float, optional): See `inpaint`.
+            mask_refinement (MaskRefinement, optional): See `inpaint`.
+
+        Returns:
+            List[PIL.Image.Image]: Processed images, in input order.
+
+        Raises:
+            ValueError: If the number of masks does not match the number of images, or as `inpaint`.
+        """
+        masks = [None] * len(images) if masks is None else list(masks)
+        if len(masks) != len(images):
+            raise ValueError(f"Got {len(masks)} masks for {len(images)} images")
+
+        opened = [open_image(image) for image in images]
+        refinement = mask_refinement or MaskRefinement()
+        refined = [refinement.apply(self.resolve_mask(image, mask, min_confidence)[0])
+                   for image, mask in zip(opened, masks)]
+        mask_arrays = [mask for mask, _ in refined]
+        weights = [weight for _, weight in refined]
+        planes = [split_channels(image) for image in opened]
+
+        print(f"Processing {len(opened)} images (backend: {self.name or type(self).__name__}, max batch size: {max_batch_size})")
+        results = self.predict_batch([rgb for rgb, _ in planes], mask_arrays, max_batch_size=max_batch_size,
+                                     memory_budget_mb=memory_budget_mb)
+        results = [blend(rgb, result, weight) for (rgb, _), result, weight in zip(planes, results, weights)]
+        alphas = [alpha for _, alpha in planes]
+        if inpaint_alpha:
+            with_alpha = [i for i, alpha in enumerate(alphas) if alpha is not None]
+            inpainted = self.predict_batch([np.repeat(alphas[i][..., None], 3, axis=2) for i in with_alpha],
+                                           [mask_arrays[i] for i in with_alpha], max_batch_size=max_batch_size,
+                                           memory_budget_mb=memory_budget_mb)
+            for i, alpha in zip(with_alpha, inpainted):
+                alphas[i] = blend(np.repeat(alphas[i][..., None], 3, axis=2), alpha, weights[i])[..., 0]
+
+        return [merge_channels(image, result, mask, alpha)
+                for image, result, mask, alpha in zip(opened, results, mask_arrays, alphas)]
+
+    def resolve_mask(self, image: ImageInput, mask: MaskInput = None,
+                     min_confidence: float = DEFAULT_MIN_CONFIDENCE) -> Tuple[np.ndarray, Optional[float]]:
+        """
+        Loads the given mask, or detects one when mask is None.
+
+        Args:
+            image (PIL.Image.Image | np.ndarray | bytes): The image, as accepted by `inpaint`.
+            mask (optional): See `inpaint`.
+            min_confidence (float, optional): See `inpaint`.
+
+        Returns:
+            tuple: (uint8 (H, W) mask, detection confidence or None if a mask was given).
+                The mask is empty when the detection is not confident enough.
+        """
+        image = open_image(image)
+        if mask is not None:
+            return load_mask(mask, image.size), None
+
+        detection = detect_watermark(split_channels(image)[0], min_confidence=min_confidence)
+        if not detection.detected:
+            print(f"No watermark detected (confidence {detection.confidence:.2f} < {min_confidence:.2f}); "
+                  f"leaving the image unchanged.")
+            return np.zeros((image.height, image.width), dtype=np.uint8), detection.confidence
+        print(f"Detected watermark mask (confidence {detection.confidence:.2f}, "
+              f"{np.count_nonzero(detection.mask)} pixels).")
+        return detection.mask, detection.confidence
+
+    def remove_watermark(self, image_path: str, output_path: str, mask: MaskInput = None,
+                         inpaint_alpha: bool = False, keep_metadata: bool = True,
+                         jpeg_quality: int = None, min_confidence: float = DEFAULT_MIN_CONFIDENCE,
+                         mask_refinement: MaskRefinement = None) -> str:
+        """
+        Removes a watermark from an image file. Thin wrapper around `inpaint`.
+
+        Args:
+            image_path (str): Path to the input image with a watermark.
+            output_path (str): Path to save the processed image.
+            mask (str | bytes | np.ndarray | PIL.Image.Image, optional): See `inpaint`.
+            inpaint_alpha (bool, optional): See `inpaint`.
+            keep_metadata (bool, optional): Carry EXIF/XMP over to the output. Defaults to True.
+            jpeg_quality (int, optional): JPEG/WebP quality of the output. Defaults to the
+                input's own quantisation for JPEG inputs, 95 otherwise.
+            min_confidence (float, optional): See `inpaint`.
+            mask_refinement (MaskRefinement, optional): See `inpaint`.
+
+        Returns:
+            str: Path to the processed image.
+
+        Raises:
+            FileNotFoundError: If the input image_path or a mask path does not exist.
+            ValueError: If the mask is invalid or its size does not match the image.
+        """
+        if not os.path.exists(image_path):
+            raise FileNotFoundError(f"Input image not found: {image_path}")
+
+        try:
+            with Image.open(image_path) as image:
+                image.load()
+                print(f"Processing image: {image_path}")
+                processed_image = self.inpaint(image, mask, inpaint_alpha=inpaint_alpha,
+                                               min_confidence=min_confidence, mask_refinement=mask_refinement)
+                save_image(processed_image, output_path, image, keep_metadata=keep_metadata,
+                           jpeg_quality=jpeg_quality)
+            print(f"Processed image saved to: {output_path}")
+
+            return output_path
+        except Exception as e:
+            print(f"Error processing image {image_path}: {e}")
+            raise
+
+
+BackendTarget = Union[str, Callable[..., InpaintingBackend]]
+
+# name -> (class or "module:attribute" import path, default constructor options)
+_REGISTRY: Dict[str, Tuple[BackendTarget, dict]] = {}
+
+
+def register_backend(name: str, target: BackendTarget, **defaults) -> None:
+    """
+    Registers a backend under `name`.
+
+    Args:
+        name (str): Name used in config, on the CLI and in the API.
+        target (str | callable): Backend class/factory, or a "module:attribute"
+            import path resolved the first time the backend is created.
+        **defaults: Constructor options applied before caller-supplied options,
+            e.g. `method="ns"` to register a preset of a generic class.
+    """
+    _REGISTRY[name] = (target, defaults)
+
+
+def available_backends() -> List[str]:
+    return sorted(_REGISTRY)
+
+
+def _resolve(target: BackendTarget) -> Callable[..., InpaintingBackend]:
+    if isinstance(target, str):
+        module_name, attribute = target.split(":")
+        return getattr(importlib.import_module(module_name), attribute)
+    return target
+
+
+def create_backend(name: str, ignore_unknown_options: bool = False, **options) -> InpaintingBackend:
+    """
+    Instantiates a registered backend.
+
+    Args:
+        name (str): Registered backend name.
+        ignore_unknown_options (bool): Drop options the backend's constructor does not
+            accept instead of failing. Used when passing shared settings (e.g. model_path)
+            to whichever backend was selected.
+        **options: Constructor options.
+
+    Raises:
+        ValueError: If no backend is registered under `name`.
+    """
+    if name not in _REGISTRY:
+        raise ValueError(f"Unknown inpainting backend '{name}'. Available: {', '.join(available_backends())}")
+
+    target, defaults = _REGISTRY[name]
+    factory = _resolve(target)
+    kwargs = {**defaults, **options}
+    if ignore_unknown_options:
+        accepted = inspect.signature(factory).parameters
+        # Factories taking **options pass them on themselves (e.g. to a fallback backend).
+        if not any(parameter.kind == parameter.VAR_KEYWORD for parameter in accepted.values()):
+            kwargs = {key: value for key, value in kwargs.items() if key in accepted}
+
+    backend = factory(**kwargs)
+    backend.name = name
+    return backend
+
+
+register_backend("lama", "watermark_remover.core.lama_wrapper:LaMaWrapper")
+register_backend("lama_onnx", "watermark_remover.core.onnx_backend:LaMaOnnxBackend")
+register_backend("opencv_telea", "watermark_remover.core.opencv_backend:OpenCVBackend", method="telea")
+register_backend("opencv_ns", "watermark_remover.core.opencv_backend:OpenCVBackend", method="ns")
+register_backend("patchmatch", "watermark_remover.core.patchmatch:PatchMatchBackend")
+register_backend("reverse_blend", "watermark_remover.core.reverse_blend:create_reverse_blend_backend")
+register_backend("periodic_blend", "watermark_remover.core.reverse_blend:create_periodic_blend_backend")
@@ -0,0 +1,70 @@
+"""
+Colour-keyed masks for flat-coloured watermarks (pure white text, a brand red).
+
+Pixels within a tolerance of one of the given colours are selected, measured
+as the CIE Lab distance (roughly perceptual: a distance of ~2.3 is just
+noticeable), optionally only inside a region. Anti-aliased edges blend the
+watermark colour with the background and fall outside the tolerance, so the
+selection is grown by a pixel or two to cover them.
+"""
+
+from typing import Sequence, Union
+
+import cv2
+import numpy as np
+from PIL import Image
+
+from watermark_remover.utils.colors import ColorInput, lab_distance, parse_color, to_lab
+from watermark_remover.utils.regions import region_mask
+
+DEFAULT_TOLERANCE = 12.0
+
+
+def color_mask(image: Union[Image.Image, np.ndarray], colors: Sequence[ColorInput],
+               tolerance: float = DEFAULT_TOLERANCE, region=None, grow: int = 1) -> np.ndarray:
+    """
+    Selects the pixels of an image close to any of the given colours.
+
+    Args:
+        image (PIL.Image.Image | np.ndarray): The image; arrays are RGB uint8 (H, W, 3).
+        colors (Sequence): Preset names (e.g. "white"), "#rrggbb" strings or RGB tuples.
+        tolerance (float, optional): Largest CIE Lab distance to a colour. Defaults to DEFAULT_TOLERANCE.
+        region (optional): Only select inside this region: a specification accepted by
+            `watermark_remover.utils.regions.region_mask`, or a (H, W) mask array. Defaults to the whole image.
+        grow (int, optional): Pixels added around the selection to cover anti-aliased edges. Defaults to 1.
+
+    Returns:
+        np.ndarray: uint8 (H, W) mask, 255 on selected pixels.
+
+    Raises:
+        ValueError: If no colour is given, a colour or the region is invalid, or the tolerance is negative.
+    """
+    if not colors:
+        raise ValueError("Give at least one colour for a colour-keyed mask")
+    if tolerance < 0:
+        raise ValueError(f"Colour tolerance must not be negative, got {tolerance}")
+    targets = [parse_color(color) for color in colors]
+
+    rgb = np.asarray(image.convert("RGB")) if isinstance(image, Image.Image) else image
+    height, width = rgb.shape[:2]
+    if region is None:
+        inside = np.ones((height, width), dtype=bool)
+    elif isinstance(region, np.ndarray):
+        if region.shape[:2] != (height, width):
+            raise ValueError(f"Region mask size {region.shape[1]}x{region.shape[0]} does not match image size {width}x{height}")
+        inside = region > 0
+    else:
+        inside = region_mask(region, (width, height)) > 0
+
+    lab = to_lab(rgb)
+    selected = np.zeros((height, width), dtype=bool)
+    for target in targets:
+        selected |= lab_distance(rgb, target, lab=lab) <= tolerance
+
+    mask = (selected & inside).astype(np.uint8) * 255
+    if grow > 0 and mask.any():
+        mask = cv2.dilate(mask, cv2.getStructuringElement(cv2.MORPH_ELLIPSE, (2 * grow + 1, 2 * grow + 1)))
+        # Growing must not leak out of the requested region.
+        mask[~inside] = 0
+    print(f"Colour key: {len(targets)} colour(s), tolerance {tolerance:g}, {int(np.count_nonzero(mask))} mask pixels")
+    return mask
@@ -0,0 +1,198 @@
+"""
+Automatic watermark mask detection from a single image.
+
+Without a mask there is no ground truth, so detection combines three cheap
+cues that watermarks tend to share and natural image content rarely does all
+at once:
+
+- contrast: small structures that stand out from their surroundings
+  (morphological top-hat / black-hat),
+- strokes: text-like regions of near-constant stroke width (MSER regions
+  filtered by stroke-width consistency, kept in clusters of several glyphs),
+- overlay: semi-transparent overlays, which shift lightness while pulling
+  colours towards grey compared with the local background.
+
+Overlays tiled across the whole image are handled separately by the spectral
+detector in `watermark_remover.core.periodic`.
+
+The cues are merged into a score map, thresholded, cleaned up into connected
+components, and the resulting mask gets a confidence from how clearly the
+masked pixels separate from the rest of the image.
+"""
+
+from dataclasses import dataclass, field
+from typing import Dict, Union
+
+import cv2
+import numpy as np
+from PIL import Image
+
+from watermark_remover.core.periodic import detect_periodic_pattern
+from watermark_remover.core.text_detection import find_glyphs
+
+# Below this confidence `inpaint` leaves the image untouched rather than guessing.
+DEFAULT_MIN_CONFIDENCE = 0.35
+
+# Cue weights in the combined score map.
+CUE_WEIGHTS = {"contrast": 0.4, "strokes": 0.35, "overlay": 0.25}
+
+# Components outside this range of the image area are treated as noise or content.
+MIN_COMPONENT_AREA = 0.0002
+MAX_MASK_COVERAGE = 0.3
+
+
+@dataclass
+class DetectionResult:
+    # uint8 (H, W) mask, 255 for proposed watermark pixels.
+    mask: np.ndarray
+    # Confidence in [0, 1] that the mask covers a watermark.
+    confidence: float
+    # Per-cue agreement in [0, 1], for inspection.
+    cues: Dict[str, float] = field(default_factory=dict)
+    min_confidence: float = DEFAULT_MIN_CONFIDENCE
+
+    @property
+    def detected(self) -> bool:
+        """True if the proposal is confident enough to be inpainted."""
+        return self.confidence >= self.min_confidence and bool(self.mask.any())
+
+
+def _odd(value: float, minimum: int = 3) -> int:
+    value = max(minimum, int(round(value)))
+    return value if value % 2 else value + 1
+
+
+def _normalise(cue: np.ndarray) -> np.ndarray:
+    """Scales a non-negative cue to [0, 1] by its 99th percentile, robust to a few outliers."""
+    high = float(np.percentile(cue, 99))
+    if high <= 1e-6:
+        return np.zeros_like(cue, dtype=np.float32)
+    return np.clip(cue / high, 0, 1).astype(np.float32)
+
+
+def contrast_cue(gray: np.ndarray) -> np.ndarray:
+    """Local contrast of structures smaller than ~1/40 of the image, from top-hat and black-hat."""
+    size = _odd(min(gray.shape) / 40)
+    kernel = cv2.getStructuringElement(cv2.MORPH_ELLIPSE, (size, size))
+    tophat = cv2.morphologyEx(gray, cv2.MORPH_TOPHAT, kernel)
+    blackhat = cv2.morphologyEx(gray, cv2.MORPH_BLACKHAT, kernel)
+    return _normalise(np.maximum(tophat, blackhat).astype(np.float32))
+
+
+def stroke_cue(gray: np.ndarray) -> np.ndarray:
+    """Pixels of text-like MSER regions that come in clusters of at least two glyphs."""
+    height, width = gray.shape
+    area = height * width
+    glyphs = np.zeros_like(gray, dtype=np.uint8)
+    for glyph in find_glyphs(gray, min_area=max(8, int(area * 0.00002)), max_area=max(64, int(area * 0.01))):
+        glyphs[glyph.points[:, 1], glyph.points[:, 0]] = 255
+
+    if not glyphs.any():
+        return glyphs.astype(np.float32)
+
+    # Group glyphs into words/lines and drop isolated ones.
+    link = _odd(min(height, width) / 60)
+    clusters = cv2.dilate(glyphs, cv2.getStructuringElement(cv2.MORPH_RECT, (link * 2 + 1, link)))
+    count, labels = cv2.connectedComponents(clusters)
+    _, glyph_labels = cv2.connectedComponents(glyphs)
+    on = glyphs > 0
+    pairs = np.unique(np.stack([labels[on], glyph_labels[on]], axis=1), axis=0)
+    keep = np.bincount(pairs[:, 0], minlength=count) >= 2
+    return (keep[labels] & on).astype(np.float32)
+
+
+def overlay_cue(rgb: np.ndarray) -> np.ndarray:
+    """Semi-transparent overlays: lightness shifted and chroma reduced against the local background."""
+    lab = cv2.cvtColor(rgb, cv2.COLOR_RGB2LAB).astype(np.float32)
+    lightness = lab[..., 0]
+    chroma = np.hypot(lab[..., 1] - 128, lab[..., 2] - 128)
+
+    size = _odd(min(rgb.shape[:2]) / 16, minimum=5)
+    background_lightness = cv2.medianBlur(lightness.astype(np.uint8), size).astype(np.float32)
+    background_chroma = cv2.medianBlur(np.clip(chroma, 0, 255).astype(np.uint8), size).astype(np.float32)
+
+    shift = np.clip(np.abs(lightness - background_lightness) / 32, 0, 1)
+    # Grey backgrounds carry no chroma to lose; the cue only fires on colourful ones.
+    desaturation = np.clip((background_chroma - chroma) / np.maximum(background_chroma, 8), 0, 1)
+    return (shift * desaturation).astype(np.float32)
+
+
+def _clean_mask(score: np.ndarray) -> np.ndarray:
+    """Thresholds the score map and keeps plausibly sized connected components."""
+    score_u8 = np.clip(score * 255, 0, 255).astype(np.uint8)
+    threshold, _ = cv2.threshold(score_u8, 0, 255, cv2.THRESH_BINARY + cv2.THRESH_OTSU)
+    # Otsu always splits something; on a clean image the split is meaningless and low.
+    binary = (score_u8 > max(threshold, 64)).astype(np.uint8) * 255
+
+    close = _odd(min(score.shape) / 100)
+    binary = cv2.morphologyEx(binary, cv2.MORPH_CLOSE, cv2.getStructuringElement(cv2.MORPH_ELLIPSE, (close, close)))
+    binary = cv2.morphologyEx(binary, cv2.MORPH_OPEN, np.ones((3, 3), np.uint8))
+
+    count, labels, stats, _ = cv2.connectedComponentsWithStats(binary)
+    min_area = MIN_COMPONENT_AREA * score.size
+    keep = np.zeros(count, dtype=bool)
+    for label in range(1, count):
+        keep[label] = min_area <= stats[label, cv2.CC_STAT_AREA] <= MAX_MASK_COVERAGE * score.size
+    return keep[labels]
+
+
+def _separation(values: np.ndarray, inside: np.ndarray) -> float:
+    """How much higher a map is inside the mask than outside, scaled to [0, 1]."""
+    if not inside.any() or inside.all():
+        return 0.0
+    return float(np.clip((values[inside].mean() - values[~inside].mean()) * 2, 0, 1))
+
+
+def detect_watermark(image: Union[Image.Image, np.ndarray], min_confidence: float = DEFAULT_MIN_CONFIDENCE,
+                     max_side: int = 1024, periodic: bool = True) -> DetectionResult:
+    """
+    Proposes a watermark mask for an image without any user input.
+
+    Args:
+        image (PIL.Image.Image | np.ndarray): The image; arrays are RGB uint8 (H, W, 3).
+        min_confidence (float, optional): Confidence at which `DetectionResult.detected` is True.
+            Defaults to DEFAULT_MIN_CONFIDENCE.
+        max_side (int, optional): Analysis runs on a copy downscaled to this longest side.
+            Defaults to 1024.
+        periodic (bool, optional): Also look for overlays tiled across the image
+            (`watermark_remover.core.periodic`) and prefer them when more confident. Defaults to True.
+
+    Returns:
+        DetectionResult: Mask at the input size, confidence and per-cue agreement.
+    """
+    rgb = np.asarray(image.convert("RGB")) if isinstance(image, Image.Image) else image
+    height, width = rgb.shape[:2]
+
+    scale = min(1.0, max_side / max(height, width))
+    work = rgb if scale == 1 else cv2.resize(rgb, (max(1, round(width * scale)), max(1, round(height * scale))),
+                                             interpolation=cv2.INTER_AREA)
+    gray = cv2.cvtColor(work, cv2.COLOR_RGB2GRAY)
+
+    cues = {"contrast": contrast_cue(gray), "strokes": stroke_cue(gray), "overlay": overlay_cue(work)}
+    score = sum(CUE_WEIGHTS[name] * cue for name, cue in cues.items())
+    score = cv2.GaussianBlur(score, (0, 0), 1.5)
+    score = score / max(float(score.max()), 1e-6)
+
+    inside = _clean_mask(score)
+    agreement = {name: _separation(cue, inside) for name, cue in cues.items()}
+    coverage = float(inside.mean())
+    coverage_penalty = float(np.clip(1 - (coverage - 0.15) / 0.15, 0, 1))
+    confidence = _separation(score, inside) * (0.5 + 0.5 * float(np.mean(list(agreement.values())))) * coverage_penalty
+
+    # Grow the mask slightly to cover anti-aliased watermark edges, then return it at full size.
+    mask = cv2.dilate(inside.astype(np.uint8) * 255, np.ones((3, 3), np.uint8))
+    if scale != 1:
+        mask = cv2.resize(mask, (width, height), interpolation=cv2.INTER_NEAREST)
+
+    result = DetectionResult(mask=mask, confidence=round(confidence, 3), cues=agreement, min_confidence=min_confidence)
+
+    # Overlays tiled across the whole image defeat the local cues (they are everywhere and
+    # exceed MAX_MASK_COVERAGE) but show up clearly in the spectrum.
+    if periodic:
+        # Only the mask is needed here; the full-resolution alpha/colour fold is left to periodic_blend.
+        pattern = detect_periodic_pattern(rgb, blend_estimate=False)
+        agreement["periodic"] = pattern.confidence
+        if pattern.confidence > result.confidence:
+            result = DetectionResult(mask=pattern.mask, confidence=pattern.confidence, cues=agreement,
+                                     min_confidence=min_confidence)
+    return result
@@ -0,0 +1,285 @@
+"""
+Joint watermark estimation from several images carrying the same watermark.
+
+Follows the median-of-gradients idea of Dekel et al., "On the Effectiveness
+of Visible Watermarks" (CVPR 2017): image content varies across a photo set
+while the watermark does not, so the per-pixel median of the image gradients
+converges to the watermark's gradients. From these:
+
+1. the watermark's bounding box is found where the median gradient is strong,
+2. each image is re-aligned by matching the gradient template, and the median
+   is recomputed on the aligned crops,
+3. the watermark matte (alpha * W) is reconstructed from the median gradients
+   with a Poisson solve and gives the watermark shape,
+4. alpha comes from how much the watermark damps the variation across images:
+   with J = alpha * W + (1 - alpha) * I, std(J) = (1 - alpha) * std(I), and
+   the watermark colour W follows from the median of J.
+
+The result is a `WatermarkTemplate` that can be saved and reused, plus a mask
+per input image for the inpainting engine.
+"""
+
+from dataclasses import dataclass
+from typing import List, Sequence, Tuple
+
+import cv2
+import numpy as np
+
+# Where images of different sizes are aligned before the first estimate.
+ANCHORS = ("top_left", "top_right", "bottom_left", "bottom_right", "center")
+
+MIN_IMAGES = 3
+
+
+@dataclass
+class WatermarkTemplate:
+    # float32 (h, w) opacity in [0, 1].
+    alpha: np.ndarray
+    # float32 (h, w, 3) watermark colour in [0, 255], RGB.
+    watermark: np.ndarray
+    # uint8 (h, w) mask, 255 on the watermark.
+    mask: np.ndarray
+    # float32 (h, w) median gradient magnitude, used to locate the watermark in new images.
+    gradient: np.ndarray
+
+    @property
+    def size(self) -> Tuple[int, int]:
+        """(width, height) of the template."""
+        return self.mask.shape[1], self.mask.shape[0]
+
+    def save(self, path: str) -> str:
+        """Saves the template as a compressed `.npz` file."""
+        np.savez_compressed(path, alpha=self.alpha, watermark=self.watermark, mask=self.mask,
+                            gradient=self.gradient)
+        return path
+
+    @classmethod
+    def load(cls, path: str) -> "WatermarkTemplate":
+        """
+        Loads a template written by `save`.
+
+        Raises:
+            FileNotFoundError: If path does not exist.
+        """
+        with np.load(path) as data:
+            return cls(alpha=data["alpha"], watermark=data["watermark"], mask=data["mask"],
+                       gradient=data["gradient"])
+
+
+@dataclass
+class EstimationResult:
+    template: WatermarkTemplate
+    # (x, y) of the template's top-left corner in each image.
+    positions: List[Tuple[int, int]]
+    # Normalised cross-correlation of the gradient template at each position, in [-1, 1].
+    scores: List[float]
+    # uint8 (H, W) mask per image, at the image's size.
+    masks: List[np.ndarray]
+
+
+def _gradients(image: np.ndarray) -> Tuple[np.ndarray, np.ndarray]:
+    """Forward differences along x and y, per channel; the last column/row is zero."""
+    gx = np.zeros_like(image)
+    gy = np.zeros_like(image)
+    gx[:, :-1] = image[:, 1:] - image[:, :-1]
+    gy[:-1] = image[1:] - image[:-1]
+    return gx, gy
+
+
+def _magnitude(gx: np.ndarray, gy: np.ndarray) -> np.ndarray:
+    return np.sqrt((gx ** 2 + gy ** 2).sum(axis=2)).astype(np.float32)
+
+
+def _dst(array: np.ndarray, axis: int) -> np.ndarray:
+    """Type-I discrete sine transform along `axis`, via an odd extension and the FFT."""
+    array = np.moveaxis(array, axis, -1)
+    n = array.shape[-1]
+    zeros = np.zeros(array.shape[:-1] + (1,), dtype=array.dtype)
+    extended = np.concatenate([zeros, array, zeros, -array[..., ::-1]], axis=-1)
+    transformed = -np.fft.fft(extended, axis=-1).imag[..., 1:n + 1] / 2
+    return np.moveaxis(transformed, -1, axis)
+
+
+def poisson_reconstruct(gx: np.ndarray, gy: np.ndarray) -> np.ndarray:
+    """
+    Recovers an image from its forward-difference gradients, with zero values on the border.
+
+    Args:
+        gx (np.ndarray): (H, W) gradient along x.
+        gy (np.ndarray): (H, W) gradient along y.
+
+    Returns:
+        np.ndarray: float64 (H, W) image whose Laplacian matches the divergence of (gx, gy).
+    """
+    divergence = np.zeros_like(gx, dtype=np.float64)
+    divergence[:, 1:] += gx[:, 1:] - gx[:, :-1]
+    divergence[:, 0] += gx[:, 0]
+    divergence[1:] += gy[1:] - gy[:-1]
+    divergence[0] += gy[0]
+
+    interior = divergence[1:-1, 1:-1]
+    height, width = interior.shape
+    if height < 1 or width < 1:
+        return np.zeros_like(divergence)
+    ky = 2 * np.cos(np.pi * np.arange(1, height + 1) / (height + 1)) - 2
+    kx = 2 * np.cos(np.pi * np.arange(1, width + 1) / (width + 1)) - 2
+    transformed = _dst(_dst(interior, 0), 1) / (ky[:, None] + kx[None, :])
+    # The inverse of DST-I is DST-I scaled by 2 / (n + 1) per axis.
+    solution = _dst(_dst(transformed, 0), 1) * (4 / ((height + 1) * (width + 1)))
+
+    result = np.zeros_like(divergence)
+    result[1:-1, 1:-1] = solution
+    return result
+
+
+def _anchor_offset(size: Tuple[int, int], crop: Tuple[int, int], anchor: str) -> Tuple[int, int]:
+    """(x, y) of a crop of size crop=(w, h) anchored inside an image of size=(w, h)."""
+    (width, height), (crop_width, crop_height) = size, crop
+    x = {"left": 0, "right": width - crop_width}.get(anchor.split("_")[-1], (width - crop_width) // 2)
+    y = {"top": 0, "bottom": height - crop_height}.get(anchor.split("_")[0], (height - crop_height) // 2)
+    return x, y
+
+
+def _median_gradients(crops: Sequence[np.ndarray]) -> Tuple[np.ndarray, np.ndarray]:
+    gradients = [_gradients(crop) for crop in crops]
+    gx = np.median(np.stack([g[0] for g in gradients]), axis=0)
+    gy = np.median(np.stack([g[1] for g in gradients]), axis=0)
+    return gx, gy
+
+
+def _watermark_box(magnitude: np.ndarray, margin: int) -> Tuple[int, int, int, int]:
+    """(x, y, w, h) around the strong median gradients, grown by margin."""
+    normalised = np.clip(magnitude / max(float(np.percentile(magnitude, 99.9)), 1e-6) * 255, 0, 255).astype(np.uint8)
+    threshold, _ = cv2.threshold(normalised, 0, 255, cv2.THRESH_BINARY + cv2.THRESH_OTSU)
+    strong = (normalised > max(threshold, 48)).astype(np.uint8)
+    strong = cv2.morphologyEx(strong, cv2.MORPH_OPEN, np.ones((2, 2), np.uint8))
+    if not strong.any():
+        raise ValueError("No consistent watermark found: the median gradient is flat. "
+                         "Are all images carrying the same watermark at the same place?")
+
+    # Keep the components holding most of the gradient energy, dropping stray specks.
+    count, labels, stats, _ = cv2.connectedComponentsWithStats(
+        cv2.dilate(strong, np.ones((2 * margin + 1, 2 * margin + 1), np.uint8)))
+    energy = np.bincount(labels.ravel(), weights=magnitude.ravel() * strong.ravel(), minlength=count)
+    energy[0] = 0
+    keep = energy >= 0.1 * energy.max()
+    x0 = int(stats[keep, cv2.CC_STAT_LEFT].min())
+    y0 = int(stats[keep, cv2.CC_STAT_TOP].min())
+    x1 = int((stats[keep, cv2.CC_STAT_LEFT] + stats[keep, cv2.CC_STAT_WIDTH]).max())
+    y1 = int((stats[keep, cv2.CC_STAT_TOP] + stats[keep, cv2.CC_STAT_HEIGHT]).max())
+    return x0, y0, x1 - x0, y1 - y0
+
+
+def locate_template(image: np.ndarray, template: WatermarkTemplate) -> Tuple[Tuple[int, int], float]:
+    """
+    Finds a watermark template in an image by matching gradient magnitudes.
+
+    Args:
+        image (np.ndarray): RGB uint8 (H, W, 3) image, at least as large as the template.
+        template (WatermarkTemplate): The watermark to look for.
+
+    Returns:
+        tuple: ((x, y) of the template's top-left corner, normalised correlation score).
+    """
+    magnitude = _magnitude(*_gradients(image.astype(np.float32)))
+    scores = cv2.matchTemplate(magnitude, template.gradient, cv2.TM_CCOEFF_NORMED)
+    _, score, _, location = cv2.minMaxLoc(scores)
+    return (int(location[0]), int(location[1])), float(score)
+
+
+def place_array(array: np.ndarray, image_size: Tuple[int, int], position: Tuple[int, int]) -> np.ndarray:
+    """
+    Returns a zero array of image_size=(w, h) holding `array` (a template-sized map such as the
+    alpha or mask) with its top-left corner at position=(x, y), cropped at the image border.
+    """
+    width, height = image_size
+    placed = np.zeros((height, width) + array.shape[2:], dtype=array.dtype)
+    x, y = position
+    template_height, template_width = array.shape[:2]
+    x0, y0 = max(x, 0), max(y, 0)
+    x1, y1 = min(x + template_width, width), min(y + template_height, height)
+    if x1 > x0 and y1 > y0:
+        placed[y0:y1, x0:x1] = array[y0 - y:y1 - y, x0 - x:x1 - x]
+    return placed
+
+
+def place_mask(template: WatermarkTemplate, image_size: Tuple[int, int], position: Tuple[int, int]) -> np.ndarray:
+    """Returns the template mask at `position` in a blank (H, W) mask of image_size=(w, h)."""
+    return place_array(template.mask, image_size, position)
+
+
+def estimate_watermark(images: Sequence[np.ndarray], anchor: str = "top_left", margin: int = 8,
+                       min_alpha: float = 0.02, iterations: int = 2) -> EstimationResult:
+    """
+    Estimates a watermark shared by a set of images.
+
+    Args:
+        images (Sequence[np.ndarray]): RGB uint8 (H, W, 3) images; sizes may differ.
+        anchor (str, optional): How images of different sizes line up for the first estimate:
+            one of ANCHORS. Later iterations re-align every image by template matching.
+            Defaults to "top_left".
+        margin (int, optional): Pixels kept around the watermark in the template. Defaults to 8.
+        min_alpha (float, optional): Opacity below which a pixel is outside the mask. Defaults to 0.02.
+        iterations (int, optional): Alignment / re-estimation rounds. Defaults to 2.
+
+    Returns:
+        EstimationResult: Template, per-image positions, match scores and masks.
+
+    Raises:
+        ValueError: If there are fewer than MIN_IMAGES images, the anchor is unknown, or no
+            consistent watermark is found.
+    """
+    if len(images) < MIN_IMAGES:
+        raise ValueError(f"Need at least {MIN_IMAGES} images to estimate a shared watermark, got {len(images)}")
+    if anchor not in ANCHORS:
+        raise ValueError(f"Unknown anchor '{anchor}'. Expected one of: {', '.join(ANCHORS)}")
+
+    arrays = [image.astype(np.float32) for image in images]
+    sizes = [(array.shape[1], array.shape[0]) for array in arrays]
+    crop_size = (min(width for width, _ in sizes), min(height for _, height in sizes))
+    offsets = [_anchor_offset(size, crop_size, anchor) for size in sizes]
+
+    print(f"Estimating watermark from {len(arrays)} images (common area {crop_size[0]}x{crop_size[1]}, anchor {anchor})")
+    gx, gy = _median_gradients([array[y:y + crop_size[1], x:x + crop_size[0]]
+                                for array, (x, y) in zip(arrays, offsets)])
+    box_x, box_y, box_width, box_height = _watermark_box(_magnitude(gx, gy), margin)
+    positions = [(x + box_x, y + box_y) for x, y in offsets]
+    scores = [1.0] * len(arrays)
+
+    for _ in range(max(1, iterations)):
+        crops = [array[y:y + box_height, x:x + box_width] for array, (x, y) in zip(arrays, positions)]
+        gx, gy = _median_gradients(crops)
+        template = _build_template(np.stack(crops), gx, gy, min_alpha)
+        located = [locate_template(image, template) for image in images]
+        positions = [position for position, _ in located]
+        scores = [score for _, score in located]
+
+    masks = [place_mask(template, size, position) for size, position in zip(sizes, positions)]
+    print(f"Watermark template {box_width}x{box_height}; match scores {min(scores):.2f}-{max(scores):.2f}")
+    return EstimationResult(template=template, positions=positions, scores=scores, masks=masks)
+
+
+def _build_template(crops: np.ndarray, gx: np.ndarray, gy: np.ndarray, min_alpha: float) -> WatermarkTemplate:
+    """Matte, opacity and colour from aligned crops (N, h, w, 3) and their median gradients."""
+    matte = np.stack([poisson_reconstruct(gx[..., c], gy[..., c]) for c in range(3)], axis=-1)
+    matte_strength = np.abs(matte).max(axis=2)
+    shape = matte_strength > max(float(matte_strength.max()) * 0.1, 1.0)
+    shape = cv2.dilate(shape.astype(np.uint8), np.ones((3, 3), np.uint8)) > 0
+
+    # Variation across images outside the watermark is what the background alone varies by.
+    spread = crops.std(axis=0).mean(axis=2)
+    background = ~cv2.dilate(shape.astype(np.uint8), np.ones((5, 5), np.uint8)).astype(bool)
+    reference = float(np.median(spread[background])) if background.any() else float(np.median(spread))
+    alpha = np.clip(1 - spread / max(reference, 1e-6), 0, 1)
+    alpha = cv2.GaussianBlur(alpha.astype(np.float32), (3, 3), 0) * shape
+
+    median = np.median(crops, axis=0)
+    background_colour = (np.median(crops[:, background], axis=(0, 1)) if background.any()
+                         else np.median(crops, axis=(0, 1, 2)))
+    safe_alpha = np.maximum(alpha, 1e-3)[..., None]
+    watermark = np.clip((median - (1 - safe_alpha) * background_colour) / safe_alpha, 0, 255)
+    watermark[alpha < min_alpha] = 0
+
+    mask = ((alpha >= min_alpha) | shape).astype(np.uint8) * 255
+    return WatermarkTemplate(alpha=alpha.astype(np.float32), watermark=watermark.astype(np.float32), mask=mask,
+                             gradient=_magnitude(gx, gy))
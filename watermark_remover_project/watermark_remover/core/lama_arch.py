@@ -0,0 +1,254 @@
+"""
+PyTorch re-implementation of the LaMa generator (FFC ResNet).
+
+This mirrors `saicinpainting.training.modules.ffc` from the official LaMa
+repository closely enough that `big-lama` generator weights load with
+`strict=True`. Only the pieces used by the released big-lama configuration
+are implemented (no gating, no spatial transforms, no output FFC).
+"""
+
+import torch
+import torch.nn as nn
+
+
+class FourierUnit(nn.Module):
+    def __init__(self, in_channels: int, out_channels: int, groups: int = 1, fft_norm: str = "ortho"):
+        super().__init__()
+        self.groups = groups
+        self.fft_norm = fft_norm
+        # Real and imaginary parts are stacked along the channel axis, hence * 2.
+        self.conv_layer = nn.Conv2d(in_channels * 2, out_channels * 2, kernel_size=1,
+                                    stride=1, padding=0, groups=groups, bias=False)
+        self.bn = nn.BatchNorm2d(out_channels * 2)
+        self.relu = nn.ReLU(inplace=True)
+
+    def forward(self, x):
+        batch = x.shape[0]
+        fft_dim = (-2, -1)
+        ffted = torch.fft.rfftn(x.float(), dim=fft_dim, norm=self.fft_norm)
+        ffted = torch.stack((ffted.real, ffted.imag), dim=-1)
+        ffted = ffted.permute(0, 1, 4, 2, 3).contiguous()  # (batch, c, 2, h, w/2+1)
+        ffted = ffted.view((batch, -1,) + ffted.size()[3:])
+
+        ffted = self.relu(self.bn(self.conv_layer(ffted)))
+
+        ffted = ffted.view((batch, -1, 2,) + ffted.size()[2:]).permute(0, 1, 3, 4, 2).contiguous()
+        ffted = torch.complex(ffted[..., 0], ffted[..., 1])
+        return torch.fft.irfftn(ffted, s=x.shape[-2:], dim=fft_dim, norm=self.fft_norm)
+
+
+class SpectralTransform(nn.Module):
+    def __init__(self, in_channels: int, out_channels: int, stride: int = 1, groups: int = 1,
+                 enable_lfu: bool = True):
+        super().__init__()
+        self.enable_lfu = enable_lfu
+        self.downsample = nn.AvgPool2d(kernel_size=(2, 2), stride=2) if stride == 2 else nn.Identity()
+        self.conv1 = nn.Sequential(
+            nn.Conv2d(in_channels, out_channels // 2, kernel_size=1, groups=groups, bias=False),
+            nn.BatchNorm2d(out_channels // 2),
+            nn.ReLU(inplace=True),
+        )
+        self.fu = FourierUnit(out_channels // 2, out_channels // 2, groups)
+        if self.enable_lfu:
+            self.lfu = FourierUnit(out_channels // 2, out_channels // 2, groups)
+        self.conv2 = nn.Conv2d(out_channels // 2, out_channels, kernel_size=1, groups=groups, bias=False)
+
+    def forward(self, x):
+        x = self.downsample(x)
+        x = self.conv1(x)
+        output = self.fu(x)
+
+        if self.enable_lfu:
+            n, c, h, w = x.shape
+            split_no = 2
+            split_s = h // split_no
+            xs = torch.cat(torch.split(x[:, :c // 4], split_s, dim=-2), dim=1).contiguous()
+            xs = torch.cat(torch.split(xs, split_s, dim=-1), dim=1).contiguous()
+            xs = self.lfu(xs)
+            xs = xs.repeat(1, 1, split_no, split_no).contiguous()
+        else:
+            xs = 0
+
+        return self.conv2(x + output + xs)
+
+
+class FFC(nn.Module):
+    def __init__(self, in_channels: int, out_channels: int, kernel_size: int, ratio_gin: float,
+                 ratio_gout: float, stride: int = 1, padding: int = 0, dilation: int = 1,
+                 groups: int = 1, bias: bool = False, enable_lfu: bool = True,
+                 padding_type: str = "reflect"):
+        super().__init__()
+        in_cg = int(in_channels * ratio_gin)
+        in_cl = in_channels - in_cg
+        out_cg = int(out_channels * ratio_gout)
+        out_cl = out_channels - out_cg
+
+        self.ratio_gin = ratio_gin
+        self.ratio_gout = ratio_gout
+        self.global_in_num = in_cg
+
+        # nn.Identity swallows the constructor arguments, which keeps the
+        # parameter names identical to the upstream implementation.
+        module = nn.Identity if in_cl == 0 or out_cl == 0 else nn.Conv2d
+        self.convl2l = module(in_cl, out_cl, kernel_size, stride, padding, dilation, groups, bias,
+                              padding_mode=padding_type)
+        module = nn.Identity if in_cl == 0 or out_cg == 0 else nn.Conv2d
+        self.convl2g = module(in_cl, out_cg, kernel_size, stride, padding, dilation, groups, bias,
+                              padding_mode=padding_type)
+        module = nn.Identity if in_cg == 0 or out_cl == 0 else nn.Conv2d
+        self.convg2l = module(in_cg, out_cl, kernel_size, stride, padding, dilation, groups, bias,
+                              padding_mode=padding_type)
+        module = nn.Identity if in_cg == 0 or out_cg == 0 else SpectralTransform
+        self.convg2g = module(in_cg, out_cg, stride, 1 if groups == 1 else groups // 2, enable_lfu)
+
+    def forward(self, x):
+        x_l, x_g = x if type(x) is tuple else (x, 0)
+        out_xl, out_xg = 0, 0
+
+        if self.ratio_gout != 1:
+            out_xl = self.convl2l(x_l) + self.convg2l(x_g)
+        if self.ratio_gout != 0:
+            out_xg = self.convl2g(x_l) + self.convg2g(x_g)
+
+        return out_xl, out_xg
+
+
+class FFC_BN_ACT(nn.Module):
+    def __init__(self, in_channels: int, out_channels: int, kernel_size: int, ratio_gin: float = 0,
+                 ratio_gout: float = 0, stride: int = 1, padding: int = 0, dilation: int = 1,
+                 groups: int = 1, bias: bool = False, norm_layer=nn.BatchNorm2d,
+                 activation_layer=nn.Identity, padding_type: str = "reflect", enable_lfu: bool = True):
+        super().__init__()
+        self.ffc = FFC(in_channels, out_channels, kernel_size, ratio_gin, ratio_gout, stride, padding,
+                       dilation, groups, bias, enable_lfu, padding_type=padding_type)
+        lnorm = nn.Identity if ratio_gout == 1 else norm_layer
+        gnorm = nn.Identity if ratio_gout == 0 else norm_layer
+        global_channels = int(out_channels * ratio_gout)
+        self.bn_l = lnorm(out_channels - global_channels)
+        self.bn_g = gnorm(global_channels)
+
+        lact = nn.Identity if ratio_gout == 1 else activation_layer
+        gact = nn.Identity if ratio_gout == 0 else activation_layer
+        self.act_l = lact(inplace=True)
+        self.act_g = gact(inplace=True)
+
+    def forward(self, x):
+        x_l, x_g = self.ffc(x)
+        x_l = self.act_l(self.bn_l(x_l))
+        x_g = self.act_g(self.bn_g(x_g))
+        return x_l, x_g
+
+
+class FFCResnetBlock(nn.Module):
+    def __init__(self, dim: int, padding_type: str, norm_layer, activation_layer=nn.ReLU,
+                 dilation: int = 1, **conv_kwargs):
+        super().__init__()
+        self.conv1 = FFC_BN_ACT(dim, dim, kernel_size=3, padding=dilation, dilation=dilation,
+                                norm_layer=norm_layer, activation_layer=activation_layer,
+                                padding_type=padding_type, **conv_kwargs)
+        self.conv2 = FFC_BN_ACT(dim, dim, kernel_size=3, padding=dilation, dilation=dilation,
+                                norm_layer=norm_layer, activation_layer=activation_layer,
+                                padding_type=padding_type, **conv_kwargs)
+
+    def forward(self, x):
+        x_l, x_g = x if type(x) is tuple else (x, 0)
+        id_l, id_g = x_l, x_g
+
+        x_l, x_g = self.conv1((x_l, x_g))
+        x_l, x_g = self.conv2((x_l, x_g))
+
+        return id_l + x_l, id_g + x_g
+
+
+class ConcatTupleLayer(nn.Module):
+    def forward(self, x):
+        x_l, x_g = x
+        if not torch.is_tensor(x_g):
+            return x_l
+        return torch.cat(x, dim=1)
+
+
+class FFCResNetGenerator(nn.Module):
+    """
+    The LaMa generator. Defaults match the released big-lama configuration
+    except for `n_blocks`, which callers pass explicitly (big-lama uses 18).
+    """
+
+    def __init__(self, input_nc: int = 4, output_nc: int = 3, ngf: int = 64, n_downsampling: int = 3,
+                 n_blocks: int = 18, max_features: int = 1024, resnet_ratio: float = 0.75):
+        super().__init__()
+        norm_layer = nn.BatchNorm2d
+        activation_layer = nn.ReLU
+        init_conv_kwargs = dict(ratio_gin=0, ratio_gout=0, enable_lfu=False)
+        downsample_conv_kwargs = dict(ratio_gin=0, ratio_gout=0, enable_lfu=False)
+        resnet_conv_kwargs = dict(ratio_gin=resnet_ratio, ratio_gout=resnet_ratio, enable_lfu=False)
+
+        model = [nn.ReflectionPad2d(3),
+                 FFC_BN_ACT(input_nc, ngf, kernel_size=7, padding=0, norm_layer=norm_layer,
+                            activation_layer=activation_layer, **init_conv_kwargs)]
+
+        # Downsampling; the last stage splits its output into local/global branches.
+        for i in range(n_downsampling):
+            mult = 2 ** i
+            cur_conv_kwargs = dict(downsample_conv_kwargs)
+            if i == n_downsampling - 1:
+                cur_conv_kwargs["ratio_gout"] = resnet_conv_kwargs["ratio_gin"]
+            model += [FFC_BN_ACT(min(max_features, ngf * mult), min(max_features, ngf * mult * 2),
+                                 kernel_size=3, stride=2, padding=1, norm_layer=norm_layer,
+                                 activation_layer=activation_layer, **cur_conv_kwargs)]
+
+        feats_num_bottleneck = min(max_features, ngf * 2 ** n_downsampling)
+        for _ in range(n_blocks):
+            model += [FFCResnetBlock(feats_num_bottleneck, padding_type="reflect",
+                                     activation_layer=activation_layer, norm_layer=norm_layer,
+                                     **resnet_conv_kwargs)]
+
+        model += [ConcatTupleLayer()]
+
+        for i in range(n_downsampling):
+            mult = 2 ** (n_downsampling - i)
+            model += [nn.ConvTranspose2d(min(max_features, ngf * mult), min(max_features, int(ngf * mult / 2)),
+                                         kernel_size=3, stride=2, padding=1, output_padding=1),
+                      nn.BatchNorm2d(min(max_features, int(ngf * mult / 2))),
+                      nn.ReLU(True)]
+
+        model += [nn.ReflectionPad2d(3),
+                  nn.Conv2d(ngf, output_nc, kernel_size=7, padding=0),
+                  nn.Sigmoid()]
+        self.model = nn.Sequential(*model)
+
+    def forward(self, x):
+        return self.model(x)
+
+
+class LaMaInpaintModel(nn.Module):
+    """
+    Wraps the generator with LaMa's input masking and output compositing so it
+    exposes the same `(image, mask) -> image` signature as the big-lama
+    TorchScript export: image is (N, 3, H, W) in [0, 1], mask is (N, 1, H, W)
+    with 1 marking pixels to fill.
+    """
+
+    def __init__(self, generator: FFCResNetGenerator):
+        super().__init__()
+        self.generator = generator
+
+    def forward(self, image, mask):
+        masked_image = image * (1 - mask)
+        predicted = self.generator(torch.cat([masked_image, mask], dim=1))
+        return mask * predicted + (1 - mask) * image
+
+
+def generator_config_from_state_dict(state_dict: dict) -> dict:
+    """
+    Infers `ngf`, `n_downsampling` and `n_blocks` from generator weights so that
+    checkpoints of any size (big-lama or a small test model) can be loaded.
+    """
+    ngf = state_dict["model.1.ffc.convl2l.weight"].shape[0]
+    block_indices = sorted({int(key.split(".")[1]) for key in state_dict
+                            if key.startswith("model.") and ".conv1.ffc." in key})
+    if not block_indices:
+        raise ValueError("State dict does not look like a LaMa FFC ResNet generator.")
+    # Layout is [pad, init conv, *downsampling, *resnet blocks, ...].
+    n_downsampling = block_indices[0] - 2
+    return dict(ngf=ngf, n_downsampling=n_downsampling, n_blocks=len(block_indices))
@@ -0,0 +1,111 @@
+"""
+Pre- and post-processing shared by the PyTorch and ONNX Runtime LaMa backends.
+Kept free of torch imports so the ONNX backend works without PyTorch installed.
+"""
+
+from collections import defaultdict
+from typing import Callable, List, Sequence
+
+import numpy as np
+
+# LaMa downsamples three times and the FFC blocks need even spatial sizes,
+# so inputs are padded to a multiple of 8 before inference.
+PAD_MODULO = 8
+
+
+# Images in a batch are padded to a shared size rounded up to this multiple,
+# so similar sizes land in the same bucket.
+BUCKET_MODULO = 64
+# Rough peak memory of a big-lama forward pass per input pixel (float32 activations).
+MEMORY_BYTES_PER_PIXEL = 600
+
+
+def _round_up(value: int, modulo: int) -> int:
+    return -(-value // modulo) * modulo
+
+
+def pad_to_size(array: np.ndarray, height: int, width: int) -> np.ndarray:
+    """Symmetrically pads the first two (spatial) axes of `array` to (height, width)."""
+    padding = [(0, height - array.shape[0]), (0, width - array.shape[1])] + [(0, 0)] * (array.ndim - 2)
+    return np.pad(array, padding, mode="symmetric")
+
+
+def pad_to_modulo(array: np.ndarray, modulo: int = PAD_MODULO) -> np.ndarray:
+    """
+    Pads the first two (spatial) axes of `array` up to a multiple of `modulo`
+    using symmetric padding, as LaMa's own prediction script does.
+    """
+    height, width = array.shape[:2]
+    return pad_to_size(array, _round_up(height, modulo), _round_up(width, modulo))
+
+
+def to_model_inputs(image: np.ndarray, mask: np.ndarray, size: tuple = None):
+    """
+    Converts an RGB uint8 image and a mask to padded float32 NCHW arrays:
+    image in [0, 1] with shape (1, 3, H', W'), mask in {0, 1} with shape (1, 1, H', W').
+    H' and W' are the next multiples of 8, or `size` = (H', W') if given.
+    """
+    mask = (mask > 0).astype(np.float32)
+    if size is None:
+        image, mask = pad_to_modulo(image), pad_to_modulo(mask)
+    else:
+        image, mask = pad_to_size(image, *size), pad_to_size(mask, *size)
+    image_input = image.astype(np.float32).transpose(2, 0, 1)[None] / 255.0
+    return np.ascontiguousarray(image_input), np.ascontiguousarray(mask[None, None])
+
+
+def from_model_output(output: np.ndarray, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+    """
+    Converts a (1, 3, H', W') model output in [0, 1] back to an RGB uint8 image of the
+    original size. Known pixels are copied back so the model never alters them.
+    """
+    height, width = image.shape[:2]
+    output = np.clip(output[0].transpose(1, 2, 0), 0, 1)
+    output = np.rint(output * 255).astype(np.uint8)[:height, :width]
+    return np.where(mask[..., None] > 0, output, image)
+
+
+def predict_bucketed(images: Sequence[np.ndarray], masks: Sequence[np.ndarray],
+                     run: Callable[[np.ndarray, np.ndarray], np.ndarray], max_batch_size: int = 4,
+                     memory_budget_mb: int = None) -> List[np.ndarray]:
+    """
+    Runs a LaMa model over many images with as few forward passes as possible.
+
+    Images are grouped into buckets by their size rounded up to BUCKET_MODULO,
+    padded to the bucket size and run in batches of at most `max_batch_size`
+    whose estimated activation memory stays within `memory_budget_mb`.
+
+    Args:
+        images (Sequence[np.ndarray]): RGB uint8 images of shape (H, W, 3); sizes may differ.
+        masks (Sequence[np.ndarray]): Matching (H, W) masks.
+        run (callable): Maps an (N, 3, H, W) image batch and (N, 1, H, W) mask batch to the
+            (N, 3, H, W) model output in [0, 1].
+        max_batch_size (int, optional): Upper bound on images per forward pass. Defaults to 4.
+        memory_budget_mb (int, optional): Upper bound on estimated memory per forward pass;
+            a single image larger than the budget still runs alone. Defaults to no limit.
+
+    Returns:
+        List[np.ndarray]: Inpainted images, in input order.
+    """
+    buckets = defaultdict(list)
+    for index, image in enumerate(images):
+        height, width = image.shape[:2]
+        buckets[(_round_up(height, BUCKET_MODULO), _round_up(width, BUCKET_MODULO))].append(index)
+
+    results = [None] * len(images)
+    for (height, width), indices in buckets.items():
+        batch_size = max(1, max_batch_size)
+        if memory_budget_mb:
+            per_image = height * width * MEMORY_BYTES_PER_PIXEL
+            batch_size = max(1, min(batch_size, memory_budget_mb * 1024 * 1024 // per_image))
+
+        for start in range(0, len(indices), batch_size):
+            chunk = indices[start:start + batch_size]
+            inputs = [to_model_inputs(images[i], masks[i], size=(height, width)) for i in chunk]
+            image_batch = np.concatenate([image_input for image_input, _ in inputs])
+            mask_batch = np.concatenate([mask_input for _, mask_input in inputs])
+            output = run(image_batch, mask_batch)
+            for offset, i in enumerate(chunk):
+                results[i] = from_model_output(output[offset:offset + 1], images[i], masks[i])
+
+    return results
@@ -1,81 +1,153 @@
 from PIL import Image
+import numpy as np
 import os
 
-class LaMaWrapper:
-    def __init__(self, model_path: str = None):
+import torch
+
+from watermark_remover.core.backends import InpaintingBackend
+from watermark_remover.core.lama_common import from_model_output, predict_bucketed, to_model_inputs
+from watermark_remover.core.opencv_backend import OpenCVBackend
+from watermark_remover.core.lama_arch import (
+    FFCResNetGenerator,
+    LaMaInpaintModel,
+    generator_config_from_state_dict,
+)
+
+
+def load_lama_model(model_path: str, device: str = "cpu", allow_pickle: bool = False) -> torch.nn.Module:
+    """
+    Loads a LaMa model from a local file.
+
+    Accepts either a TorchScript export taking `(image, mask)` (e.g. the
+    `big-lama.pt` used by lama-cleaner) or a state dict / Lightning checkpoint
+    of the FFC ResNet generator (e.g. big-lama `best.ckpt`).
+
+    Args:
+        model_path (str): Path to the TorchScript file or checkpoint.
+        device (str): Torch device to map the weights to.
+        allow_pickle (bool, optional): Load checkpoints that the weights-only unpickler rejects
+            (e.g. Lightning checkpoints with pickled hyper-parameters) with the full unpickler,
+            which can run arbitrary code from the file. Only for trusted files. Defaults to False.
+
+    Returns:
+        torch.nn.Module: Model in eval mode with an `(image, mask) -> image` forward.
+
+    Raises:
+        FileNotFoundError: If model_path does not exist.
+        ValueError: If the checkpoint needs the full unpickler and allow_pickle is False.
+    """
+    if not os.path.exists(model_path):
+        raise FileNotFoundError(f"LaMa model not found: {model_path}")
+
+    try:
+        return torch.jit.load(model_path, map_location=device).eval()
+    except RuntimeError:
+        # Not a TorchScript archive; fall through to checkpoint loading.
+        pass
+
+    try:
+        checkpoint = torch.load(model_path, map_location=device, weights_only=True)
+    except Exception as e:
+        # Lightning checkpoints pickle their hyper-parameters, which the weights-only
+        # unpickler rejects. The full unpickler executes code from the file.
+        if not allow_pickle:
+            raise ValueError(
+                f"{model_path} cannot be loaded as weights only ({e}). If the file is trusted, allow the full "
+                f"unpickler with WATERMARK_REMOVER_LAMA_ALLOW_PICKLE=1, or re-save the generator's state dict."
+            ) from e
+        print(f"Warning: loading {model_path} with the full unpickler, which can execute code from the file.")
+        checkpoint = torch.load(model_path, map_location=device, weights_only=False)
+
+    state_dict = checkpoint.get("state_dict", checkpoint)
+    # Training checkpoints also hold the discriminator and loss networks.
+    if any(key.startswith("generator.") for key in state_dict):
+        state_dict = {key[len("generator."):]: value for key, value in state_dict.items()
+                      if key.startswith("generator.")}
+
+    generator = FFCResNetGenerator(**generator_config_from_state_dict(state_dict))
+    generator.load_state_dict(state_dict, strict=True)
+    return LaMaInpaintModel(generator).to(device).eval()
+
+
+class LaMaWrapper(InpaintingBackend):
+    name = "lama"
+
+    def __init__(self, model_path: str = None, device: str = "cpu", fallback_method: str = "telea",
+                 fallback_radius: int = 3, allow_pickle: bool = False):
         """
-        Initializes the LaMaWrapper.
-        For now, this is a placeholder for loading the actual LaMa model.
+        Initializes the LaMaWrapper and loads the LaMa model.
 
         Args:
-            model_path (str, optional): Path to the pre-trained LaMa model.
-                                         Defaults to None, implying potential download
-                                         or default location.
+            model_path (str, optional): Path to a local big-lama TorchScript file or
+                                         generator checkpoint. Defaults to None, in which
+                                         case no model is loaded and the classical
+                                         OpenCV engine is used instead.
+            device (str, optional): Torch device used for inference. Defaults to "cpu".
+            fallback_method (str, optional): OpenCV method used without LaMa weights,
+                                             "telea" or "ns". Defaults to "telea".
+            fallback_radius (int, optional): Inpainting radius of the fallback. Defaults to 3.
+            allow_pickle (bool, optional): Allow checkpoints that need the full (code-executing)
+                                           unpickler; see `load_lama_model`. Defaults to False.
+
+        Raises:
+            FileNotFoundError: If model_path is given but does not exist.
         """
-        # TODO: Implement actual LaMa model loading here.
-        # This would involve:
-        # 1. Downloading pre-trained model weights if not available.
-        # 2. Initializing the PyTorch model architecture.
-        # 3. Loading the weights into the model.
-        print(f"LaMaWrapper initialized. (Placeholder - Model path: {model_path})")
-        self.model = None # Placeholder for the loaded model
-
-    def remove_watermark(self, image_path: str, output_path: str) -> str:
+        self.model_path = model_path
+        self.device = torch.device(device)
+        self.model = load_lama_model(model_path, device, allow_pickle) if model_path else None
+        self.fallback = None
+        if self.model is None:
+            self.fallback = OpenCVBackend(method=fallback_method, radius=fallback_radius)
+            print(f"Warning: no LaMa weights configured; falling back to OpenCV "
+                  f"'{fallback_method}' inpainting (radius {fallback_radius}).")
+        print(f"LaMaWrapper initialized. (Model path: {model_path}, device: {self.device})")
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
         """
-        Removes a watermark from an image.
-        Currently uses a placeholder (grayscale conversion) instead of LaMa.
+        Runs LaMa on a single image.
+        Without a loaded model the classical OpenCV fallback is used.
 
         Args:
-            image_path (str): Path to the input image with a watermark.
-            output_path (str): Path to save the processed image.
+            image (np.ndarray): RGB image, uint8 array of shape (H, W, 3).
+            mask (np.ndarray): Array of shape (H, W); non-zero pixels are inpainted.
 
         Returns:
-            str: Path to the processed image.
+            np.ndarray: Inpainted RGB image, uint8 array of shape (H, W, 3).
+        """
+        if self.fallback is not None:
+            return self.fallback.predict(image, mask)
 
-        Raises:
-            FileNotFoundError: If the input image_path does not exist.
+        image_input, mask_input = to_model_inputs(image, mask)
+        return from_model_output(self._run(image_input, mask_input), image, mask)
+
+    def predict_batch(self, images, masks, max_batch_size: int = 4, memory_budget_mb: int = None):
+        """
+        Runs LaMa over several images, grouping similar sizes into padded batches.
+        See `watermark_remover.core.lama_common.predict_bucketed`.
         """
-        if not os.path.exists(image_path):
-            raise FileNotFoundError(f"Input image not found: {image_path}")
+        if self.fallback is not None:
+            return super().predict_batch(images, masks, max_batch_size, memory_budget_mb)
+        return predict_bucketed(images, masks, self._run, max_batch_size=max_batch_size,
+                                memory_budget_mb=memory_budget_mb)
 
-        try:
-            image = Image.open(image_path)
-
-            # TODO: Replace this placeholder with actual LaMa model inference.
-            # The actual process would involve:
-            # 1. Preprocessing the image to the format LaMa expects.
-            # 2. Generating a mask for the watermark (this is a crucial step,
-            #    LaMa is an inpainting model and needs a mask).
-            #    For a PoC, a simple mask covering a predefined area could be used,
-            #    or a more sophisticated approach if time allows.
-            # 3. Running the image and mask through the LaMa model.
-            # 4. Postprocessing the output.
-            print(f"Processing image: {image_path} (Placeholder: converting to grayscale)")
-            processed_image = image.convert('L')
-
-            # Ensure output directory exists
-            os.makedirs(os.path.dirname(output_path), exist_ok=True)
-            
-            processed_image.save(output_path)
-            print(f"Processed image saved to: {output_path}")
-            
-            return output_path
-        except Exception as e:
-            print(f"Error processing image {image_path}: {e}")
-            raise
+    def _run(self, image_batch: np.ndarray, mask_batch: np.ndarray) -> np.ndarray:
+        with torch.inference_mode():
+            output = self.model(torch.from_numpy(image_batch).to(self.device),
+                                torch.from_numpy(mask_batch).to(self.device))
+        return output.float().cpu().numpy()
 
 if __name__ == '__main__':
-    # Example Usage (for testing the placeholder logic)
+    # Example Usage (for testing the wrapper end to end)
     # Create dummy input directories and a sample image if they don't exist
-    
+
     # Paths for the __main__ block, distinct from API paths
     # Relative to this file (watermark_remover_project/watermark_remover/core/lama_wrapper.py)
     script_dir = os.path.dirname(__file__)
     base_dir = os.path.abspath(os.path.join(script_dir, "../../../data/wrapper_test"))
-    
+
     input_dir = os.path.join(base_dir, "input")
     output_dir = os.path.join(base_dir, "output")
-    
+
     os.makedirs(input_dir, exist_ok=True)
     os.makedirs(output_dir, exist_ok=True)
     print(f"Wrapper test directories created/ensured: {input_dir}, {output_dir}")
@@ -93,14 +165,14 @@ if __name__ == '__main__':
 
 
     if os.path.exists(sample_image_path):
-        print("\n--- Example LaMaWrapper Usage (Placeholder) ---")
+        print("\n--- Example LaMaWrapper Usage ---")
         try:
-            # Initialize wrapper (no model path needed for placeholder)
-            lama_wrapper = LaMaWrapper()
-            
+            # Set LAMA_MODEL_PATH to try real inference; otherwise the OpenCV fallback is used.
+            lama_wrapper = LaMaWrapper(model_path=os.environ.get("LAMA_MODEL_PATH"))
+
             # Process the image
             result_path = lama_wrapper.remove_watermark(sample_image_path, sample_output_path)
-            print(f"Placeholder processing complete. Output at: {result_path}")
+            print(f"Processing complete. Output at: {result_path}")
 
             # Verify output (optional)
             if os.path.exists(result_path):
@@ -114,5 +186,3 @@ if __name__ == '__main__':
         print("--- End Example ---")
     else:
         print(f"Skipping example usage as dummy input image could not be created or found at {sample_image_path}")
-
-```
@@ -0,0 +1,116 @@
+"""
+LaMa inference with ONNX Runtime on CPU, for workers without PyTorch.
+
+The model is expected to take `image` (N, 3, H, W) in [0, 1] and `mask`
+(N, 1, H, W) in {0, 1} and return the inpainted image, which is what
+`export_onnx` produces. Exports with a fixed input size (e.g. 512x512) are
+also supported: inputs are resized to the model size and the result is
+resized back before compositing.
+"""
+
+import os
+
+import cv2
+import numpy as np
+import onnxruntime as ort
+
+from watermark_remover.core.backends import InpaintingBackend
+from watermark_remover.core.lama_common import from_model_output, predict_bucketed, to_model_inputs
+
+
+class LaMaOnnxBackend(InpaintingBackend):
+    name = "lama_onnx"
+
+    def __init__(self, model_path: str, intra_op_num_threads: int = 0):
+        """
+        Args:
+            model_path (str): Path to the exported LaMa `.onnx` file.
+            intra_op_num_threads (int, optional): Threads used inside a single operator.
+                                                  0 lets ONNX Runtime pick (one per core).
+
+        Raises:
+            FileNotFoundError: If model_path does not exist.
+        """
+        if not model_path or not os.path.exists(model_path):
+            raise FileNotFoundError(f"LaMa ONNX model not found: {model_path}")
+
+        options = ort.SessionOptions()
+        options.intra_op_num_threads = intra_op_num_threads
+        self.model_path = model_path
+        self.session = ort.InferenceSession(model_path, sess_options=options,
+                                            providers=["CPUExecutionProvider"])
+
+        image_input, mask_input = self.session.get_inputs()[:2]
+        self.image_input_name = image_input.name
+        self.mask_input_name = mask_input.name
+        height, width = image_input.shape[2:4]
+        # Dynamic axes show up as strings/None; only a static size needs resizing.
+        self.fixed_size = (width, height) if isinstance(height, int) and isinstance(width, int) else None
+        print(f"LaMaOnnxBackend initialized. (Model path: {model_path}, intra-op threads: {intra_op_num_threads or 'auto'})")
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        model_image, model_mask = image, mask
+        if self.fixed_size:
+            model_image = cv2.resize(image, self.fixed_size, interpolation=cv2.INTER_AREA)
+            model_mask = cv2.resize((mask > 0).astype(np.uint8), self.fixed_size, interpolation=cv2.INTER_NEAREST)
+
+        image_input, mask_input = to_model_inputs(model_image, model_mask)
+        output = self._run(image_input, mask_input)
+
+        if self.fixed_size:
+            height, width = image.shape[:2]
+            output = output[:, :, :model_image.shape[0], :model_image.shape[1]]
+            resized = cv2.resize(output[0].transpose(1, 2, 0), (width, height), interpolation=cv2.INTER_CUBIC)
+            output = resized.transpose(2, 0, 1)[None]
+
+        return from_model_output(output, image, mask)
+
+    def predict_batch(self, images, masks, max_batch_size: int = 4, memory_budget_mb: int = None):
+        """
+        Runs the model over several images, grouping similar sizes into padded batches.
+        Fixed-size exports have a fixed batch as well, so they process one image at a time.
+        """
+        if self.fixed_size:
+            return super().predict_batch(images, masks, max_batch_size, memory_budget_mb)
+        return predict_bucketed(images, masks, self._run, max_batch_size=max_batch_size,
+                                memory_budget_mb=memory_budget_mb)
+
+    def _run(self, image_batch: np.ndarray, mask_batch: np.ndarray) -> np.ndarray:
+        output = self.session.run(None, {self.image_input_name: image_batch,
+                                         self.mask_input_name: mask_batch})[0].astype(np.float32)
+        # Some third-party exports return 0-255 instead of 0-1.
+        if output.max() > 1.5:
+            output = output / 255.0
+        return output
+
+
+def export_onnx(model_path: str, onnx_path: str, opset_version: int = 17, allow_pickle: bool = False) -> str:
+    """
+    Exports a LaMa TorchScript file or checkpoint to ONNX with dynamic batch and spatial axes.
+    Requires PyTorch (>= 2.5 for the FFT ops), so it is only needed on the machine doing the export.
+
+    Args:
+        model_path (str): LaMa model accepted by `load_lama_model`.
+        onnx_path (str): Destination `.onnx` path.
+        opset_version (int, optional): ONNX opset. Defaults to 17, the first with DFT.
+        allow_pickle (bool, optional): See `load_lama_model`. Defaults to False.
+
+    Returns:
+        str: onnx_path.
+    """
+    import torch
+
+    from watermark_remover.core.lama_wrapper import load_lama_model
+
+    model = load_lama_model(model_path, "cpu", allow_pickle)
+    example = (torch.rand(1, 3, 64, 64), torch.zeros(1, 1, 64, 64))
+    dynamic_axes = {
+        "image": {0: "batch", 2: "height", 3: "width"},
+        "mask": {0: "batch", 2: "height", 3: "width"},
+        "output": {0: "batch", 2: "height", 3: "width"},
+    }
+    os.makedirs(os.path.dirname(os.path.abspath(onnx_path)), exist_ok=True)
+    # The dynamo exporter handles rfftn/irfftn and complex intermediates.
+    torch.onnx.export(model, example, onnx_path, input_names=["image", "mask"], output_names=["output"],
+                      dynamic_axes=dynamic_axes, opset_version=opset_version, dynamo=True)
+    return onnx_path
@@ -0,0 +1,37 @@
+import cv2
+import numpy as np
+
+from watermark_remover.core.backends import InpaintingBackend
+
+
+class OpenCVBackend(InpaintingBackend):
+    """
+    Classical inpainting with OpenCV's Telea (fast marching) or Navier-Stokes method.
+    """
+
+    METHODS = {
+        "telea": cv2.INPAINT_TELEA,
+        "ns": cv2.INPAINT_NS,
+    }
+
+    def __init__(self, method: str = "telea", radius: int = 3):
+        """
+        Args:
+            method (str, optional): "telea" or "ns". Defaults to "telea".
+            radius (int, optional): Neighbourhood radius in pixels considered around
+                                    each inpainted pixel. Defaults to 3.
+
+        Raises:
+            ValueError: If the method is unknown or the radius is not positive.
+        """
+        if method not in self.METHODS:
+            raise ValueError(f"Unknown OpenCV inpainting method '{method}'. Expected one of: {', '.join(self.METHODS)}")
+        if radius < 1:
+            raise ValueError(f"Inpainting radius must be at least 1, got {radius}")
+        self.method = method
+        self.radius = radius
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        mask = (mask > 0).astype(np.uint8) * 255
+        # cv2.inpaint treats channels independently, so RGB order is fine.
+        return cv2.inpaint(np.ascontiguousarray(image), mask, self.radius, self.METHODS[self.method])
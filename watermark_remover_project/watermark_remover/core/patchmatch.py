@@ -0,0 +1,203 @@
+"""
+Exemplar-based inpainting using PatchMatch (Barnes et al. 2009) inside the
+multi-scale EM scheme of Wexler et al. 2007.
+
+The hole is filled coarse-to-fine. At each scale, a nearest-neighbour field
+(NNF) maps every patch touching the hole to a fully known source patch, and
+hole pixels are re-estimated by voting over all patches that cover them.
+Everything is vectorised over the target pixels with numpy; only the loops
+over patch offsets and iterations run in Python.
+"""
+
+import cv2
+import numpy as np
+
+from watermark_remover.core.backends import InpaintingBackend
+
+
+class PatchMatchBackend(InpaintingBackend):
+    def __init__(self, patch_size: int = 7, em_iterations: int = 4, search_iterations: int = 4,
+                 min_size: int = 32, seed: int = 0):
+        """
+        Args:
+            patch_size (int, optional): Odd patch side length. Defaults to 7.
+            em_iterations (int, optional): Search/vote rounds per scale. Defaults to 4.
+            search_iterations (int, optional): PatchMatch propagation + random search
+                                               rounds per EM iteration. Defaults to 4.
+            min_size (int, optional): Smallest image side at the coarsest scale. Defaults to 32.
+            seed (int, optional): Seed for the random search, for reproducible output. Defaults to 0.
+
+        Raises:
+            ValueError: If patch_size is not an odd number >= 3.
+        """
+        if patch_size < 3 or patch_size % 2 == 0:
+            raise ValueError(f"patch_size must be an odd number >= 3, got {patch_size}")
+        self.patch_size = patch_size
+        self.em_iterations = em_iterations
+        self.search_iterations = search_iterations
+        self.min_size = min_size
+        self.seed = seed
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        hole = mask > 0
+        if not hole.any():
+            return image.copy()
+
+        rng = np.random.default_rng(self.seed)
+        images, holes = self._build_pyramid(image, hole)
+
+        # The coarsest level is seeded with a diffusion fill so the first
+        # NNF search has something sensible to match against.
+        current = cv2.inpaint(np.ascontiguousarray(images[-1]), holes[-1].astype(np.uint8) * 255,
+                              3, cv2.INPAINT_TELEA).astype(np.float32)
+        nnf = None
+
+        for level in range(len(images) - 1, -1, -1):
+            level_image = images[level].astype(np.float32)
+            level_hole = holes[level]
+            if level != len(images) - 1:
+                upsampled = cv2.resize(current, (level_image.shape[1], level_image.shape[0]),
+                                       interpolation=cv2.INTER_LINEAR)
+                current = level_image.copy()
+                current[level_hole] = upsampled[level_hole]
+            else:
+                current[~level_hole] = level_image[~level_hole]
+
+            current, nnf = self._fill_level(current, level_hole, nnf, rng)
+            if current is None:
+                # No fully known source patch exists; fall back to diffusion.
+                return cv2.inpaint(np.ascontiguousarray(image), hole.astype(np.uint8) * 255,
+                                   3, cv2.INPAINT_TELEA)
+
+        result = image.copy()
+        result[hole] = np.clip(np.rint(current[hole]), 0, 255).astype(np.uint8)
+        return result
+
+    def _build_pyramid(self, image: np.ndarray, hole: np.ndarray):
+        images, holes = [image], [hole]
+        while min(images[-1].shape[:2]) // 2 >= self.min_size:
+            height, width = images[-1].shape[:2]
+            size = (width // 2, height // 2)
+            images.append(cv2.resize(images[-1], size, interpolation=cv2.INTER_AREA))
+            # Any hole pixel in the 2x2 block keeps the coarse pixel in the hole.
+            coarse_hole = cv2.resize(holes[-1].astype(np.float32), size, interpolation=cv2.INTER_AREA) > 0
+            holes.append(coarse_hole)
+        return images, holes
+
+    def _fill_level(self, current: np.ndarray, hole: np.ndarray, previous_nnf, rng):
+        height, width = hole.shape
+        half = self.patch_size // 2
+        kernel = np.ones((self.patch_size, self.patch_size), np.uint8)
+
+        # Targets: every patch overlapping the hole. Sources: patches entirely outside it.
+        target = cv2.dilate(hole.astype(np.uint8), kernel) > 0
+        source_valid = ~target
+        source_valid[:half, :] = source_valid[height - half:, :] = False
+        source_valid[:, :half] = source_valid[:, width - half:] = False
+        source_coords = np.argwhere(source_valid)
+        if len(source_coords) == 0:
+            return None, None
+
+        ty, tx = np.nonzero(target)
+        nnf = self._initial_nnf(previous_nnf, ty, tx, height, width, source_valid, source_coords, rng)
+
+        for _ in range(self.em_iterations):
+            padded = np.pad(current, ((half, half), (half, half), (0, 0)), mode="edge")
+            distances = self._distance(padded, ty, tx, nnf[ty, tx, 0], nnf[ty, tx, 1])
+            for _ in range(self.search_iterations):
+                distances = self._propagate(padded, nnf, distances, ty, tx, source_valid)
+                distances = self._random_search(padded, nnf, distances, ty, tx, source_valid, rng)
+            current = self._vote(current, nnf, distances, hole, target)
+
+        return current, nnf
+
+    def _initial_nnf(self, previous_nnf, ty, tx, height, width, source_valid, source_coords, rng):
+        nnf = np.zeros((height, width, 2), dtype=np.int64)
+        if previous_nnf is not None:
+            # Upsample the coarser NNF: offsets double, sub-pixel position is kept.
+            coarse_y = np.minimum(ty // 2, previous_nnf.shape[0] - 1)
+            coarse_x = np.minimum(tx // 2, previous_nnf.shape[1] - 1)
+            cy = np.clip(previous_nnf[coarse_y, coarse_x, 0] * 2 + ty % 2, 0, height - 1)
+            cx = np.clip(previous_nnf[coarse_y, coarse_x, 1] * 2 + tx % 2, 0, width - 1)
+            nnf[ty, tx, 0], nnf[ty, tx, 1] = cy, cx
+            invalid = ~source_valid[cy, cx]
+        else:
+            invalid = np.ones(len(ty), dtype=bool)
+
+        picks = source_coords[rng.integers(0, len(source_coords), size=int(invalid.sum()))]
+        nnf[ty[invalid], tx[invalid]] = picks
+        return nnf
+
+    def _distance(self, padded: np.ndarray, ty, tx, sy, sx) -> np.ndarray:
+        """Sum of squared differences between target patches at (ty, tx) and source patches at (sy, sx)."""
+        half = self.patch_size // 2
+        total = np.zeros(len(ty), dtype=np.float32)
+        # Coordinates are in unpadded space; the pad offset cancels the -half of the window.
+        for dy in range(self.patch_size):
+            for dx in range(self.patch_size):
+                diff = padded[ty + dy, tx + dx] - padded[sy + dy, sx + dx]
+                total += np.einsum("ij,ij->i", diff, diff)
+        return total / (self.patch_size * self.patch_size)
+
+    def _try_candidates(self, padded, nnf, distances, ty, tx, cy, cx, source_valid):
+        height, width = source_valid.shape
+        cy = np.clip(cy, 0, height - 1)
+        cx = np.clip(cx, 0, width - 1)
+        valid = source_valid[cy, cx]
+        candidate = np.full(len(ty), np.inf, dtype=np.float32)
+        if valid.any():
+            candidate[valid] = self._distance(padded, ty[valid], tx[valid], cy[valid], cx[valid])
+        better = candidate < distances
+        nnf[ty[better], tx[better], 0] = cy[better]
+        nnf[ty[better], tx[better], 1] = cx[better]
+        return np.where(better, candidate, distances)
+
+    def _propagate(self, padded, nnf, distances, ty, tx, source_valid):
+        height, width = source_valid.shape
+        # Jump-flood style propagation: all four neighbours are tried in parallel,
+        # which converges like the sequential scan order after a few rounds.
+        for oy, ox in ((0, 1), (1, 0), (0, -1), (-1, 0)):
+            ny = np.clip(ty - oy, 0, height - 1)
+            nx = np.clip(tx - ox, 0, width - 1)
+            cy = nnf[ny, nx, 0] + (ty - ny)
+            cx = nnf[ny, nx, 1] + (tx - nx)
+            distances = self._try_candidates(padded, nnf, distances, ty, tx, cy, cx, source_valid)
+        return distances
+
+    def _random_search(self, padded, nnf, distances, ty, tx, source_valid, rng):
+        radius = max(source_valid.shape)
+        while radius >= 1:
+            cy = nnf[ty, tx, 0] + rng.integers(-radius, radius + 1, size=len(ty))
+            cx = nnf[ty, tx, 1] + rng.integers(-radius, radius + 1, size=len(ty))
+            distances = self._try_candidates(padded, nnf, distances, ty, tx, cy, cx, source_valid)
+            radius //= 2
+        return distances
+
+    def _vote(self, current, nnf, distances, hole, target) -> np.ndarray:
+        height, width = hole.shape
+        half = self.patch_size // 2
+        # Similar patches get more say; the scale adapts to the current match quality.
+        sigma = np.percentile(distances, 75) + 1e-6
+        weights = np.zeros((height, width), dtype=np.float32)
+        weights[target] = np.exp(-distances / (2 * sigma))
+
+        hy, hx = np.nonzero(hole)
+        accumulated = np.zeros((len(hy), current.shape[2]), dtype=np.float32)
+        total_weight = np.zeros(len(hy), dtype=np.float32)
+        for dy in range(-half, half + 1):
+            for dx in range(-half, half + 1):
+                # Patch centred at p = q - (dy, dx) covers hole pixel q at offset (dy, dx).
+                py = hy - dy
+                px = hx - dx
+                inside = (py >= 0) & (py < height) & (px >= 0) & (px < width)
+                py, px = py[inside], px[inside]
+                sy = np.clip(nnf[py, px, 0] + dy, 0, height - 1)
+                sx = np.clip(nnf[py, px, 1] + dx, 0, width - 1)
+                w = weights[py, px]
+                accumulated[inside] += current[sy, sx] * w[:, None]
+                total_weight[inside] += w
+
+        updated = current.copy()
+        filled = total_weight > 0
+        updated[hy[filled], hx[filled]] = accumulated[filled] / total_weight[filled, None]
+        return updated
@@ -0,0 +1,257 @@
+"""
+Detection of watermarks tiled across the whole image (e.g. diagonal text repeated in a grid).
+
+A pattern repeated on a lattice concentrates its energy in a few sharp peaks of
+the Fourier spectrum, while natural image content has a smooth spectrum. The
+detector:
+
+1. high-passes the image and finds spectral peaks that stand out from the
+   local spectral background (their prominence gives the confidence),
+2. reconstructs the repeated pattern by keeping only those peaks (inverse
+   FFT), and thresholds it into a mask,
+3. recovers the repetition lattice from the autocorrelation of the pattern,
+   folds every pixel into one lattice cell and, as in multi-image estimation,
+   reads the opacity from how much the overlay damps variation across the
+   repeats and the colour from the cell mean. This gives a full-image
+   alpha/colour estimate for reverse blending.
+"""
+
+from dataclasses import dataclass
+from typing import Optional, Tuple, Union
+
+import cv2
+import numpy as np
+from PIL import Image
+
+from watermark_remover.core.estimation import WatermarkTemplate
+
+# The analysis runs on a copy downscaled to at most this longest side.
+ANALYSIS_MAX_SIDE = 1024
+# Spectral peaks must exceed the local spectral background by this much (natural log units).
+MIN_PEAK_PROMINENCE = 1.5
+MAX_PEAKS = 12
+
+
+@dataclass
+class PeriodicPattern:
+    # Confidence in [0, 1] that the image carries a periodic overlay.
+    confidence: float
+    # uint8 (H, W) full-image mask of the overlay.
+    mask: np.ndarray
+    # Lattice vectors (dx, dy) in pixels of the full image; the second is None for stripes.
+    period: Optional[Tuple[Tuple[float, float], Optional[Tuple[float, float]]]] = None
+    # float32 (H, W) opacity and (H, W, 3) colour for reverse blending, when the lattice was found.
+    alpha: Optional[np.ndarray] = None
+    watermark: Optional[np.ndarray] = None
+
+    @property
+    def has_blend_estimate(self) -> bool:
+        return self.alpha is not None
+
+    def to_watermark_template(self) -> WatermarkTemplate:
+        """The full-image estimate as a template for `ReverseBlendBackend` at position (0, 0)."""
+        if self.alpha is None:
+            raise ValueError("No reverse-blend estimate: the repetition lattice was not found")
+        gradient = cv2.magnitude(cv2.Sobel(self.alpha, cv2.CV_32F, 1, 0), cv2.Sobel(self.alpha, cv2.CV_32F, 0, 1))
+        return WatermarkTemplate(alpha=self.alpha, watermark=self.watermark, mask=self.mask, gradient=gradient)
+
+
+def _spectral_peaks(high_pass: np.ndarray, min_period: int, max_period: int) -> Tuple[np.ndarray, np.ndarray]:
+    """(ky, kx) of prominent peaks in the upper half-plane of the shifted spectrum, and their prominence."""
+    height, width = high_pass.shape
+    window = cv2.createHanningWindow((width, height), cv2.CV_32F)
+    spectrum = np.fft.fftshift(np.log1p(np.abs(np.fft.fft2(high_pass * window)))).astype(np.float32)
+    prominence = spectrum - cv2.blur(spectrum, (15, 15))
+
+    ky, kx = np.mgrid[0:height, 0:width]
+    ky, kx = ky - height // 2, kx - width // 2
+    # Frequency in cycles per pixel; its inverse is the period.
+    frequency = np.hypot(ky / height, kx / width)
+    band = (frequency >= 1 / max_period) & (frequency <= 1 / min_period)
+    # The spectrum of a real image is symmetric: keep one half-plane.
+    band &= (ky > 0) | ((ky == 0) & (kx > 0))
+
+    peaks = band & (prominence >= cv2.dilate(prominence, np.ones((5, 5), np.uint8))) & (prominence > MIN_PEAK_PROMINENCE)
+    order = np.argsort(prominence[peaks])[::-1][:MAX_PEAKS]
+    coordinates = np.argwhere(peaks)[order]
+    return coordinates - [height // 2, width // 2], prominence[peaks][order]
+
+
+def _reconstruct(high_pass: np.ndarray, peaks: np.ndarray) -> np.ndarray:
+    """Inverse FFT of the spectrum restricted to small discs around the peaks (and their mirrors)."""
+    height, width = high_pass.shape
+    keep = np.zeros((height, width), dtype=np.uint8)
+    for ky, kx in peaks:
+        for sign in (1, -1):
+            cv2.circle(keep, (int(sign * kx) % width, int(sign * ky) % height), 1, 1, -1)
+    return np.real(np.fft.ifft2(np.fft.fft2(high_pass) * keep)).astype(np.float32)
+
+
+def _refine_vector(autocorrelation: np.ndarray, vector: np.ndarray) -> np.ndarray:
+    """
+    Sub-pixel lattice vector: locates successively farther multiples of it in the map and
+    divides, so the error shrinks with the number of repeats spanned.
+    """
+    height, width = autocorrelation.shape
+    center = np.array([height // 2, width // 2])
+    vector = vector.astype(np.float64)
+    limit = min(height, width) * 0.45
+    multiple = 2
+    while multiple * np.hypot(*vector) <= limit:
+        guess = np.rint(center + vector * multiple).astype(int)
+        y0, y1 = max(guess[0] - 2, 1), min(guess[0] + 3, height - 1)
+        x0, x1 = max(guess[1] - 2, 1), min(guess[1] + 3, width - 1)
+        if y1 <= y0 or x1 <= x0:
+            break
+        local = autocorrelation[y0:y1, x0:x1]
+        y, x = np.unravel_index(np.argmax(local), local.shape)
+        y, x = y + y0, x + x0
+        # Parabolic interpolation of the peak along each axis.
+        offset = np.zeros(2)
+        for axis, (before, peak, after) in enumerate((
+                (autocorrelation[y - 1, x], autocorrelation[y, x], autocorrelation[y + 1, x]),
+                (autocorrelation[y, x - 1], autocorrelation[y, x], autocorrelation[y, x + 1]))):
+            curvature = before - 2 * peak + after
+            if curvature < 0:
+                offset[axis] = 0.5 * (before - after) / curvature
+        vector = (np.array([y, x]) + offset - center) / multiple
+        multiple *= 2
+    return vector
+
+
+def _lattice(pattern: np.ndarray, min_period: int) -> Tuple[Optional[np.ndarray], Optional[np.ndarray]]:
+    """Lattice vectors (dy, dx) of a periodic image from the peaks of its autocorrelation."""
+    height, width = pattern.shape
+    power = np.abs(np.fft.fft2(pattern)) ** 2
+    autocorrelation = np.fft.fftshift(np.real(np.fft.ifft2(power))).astype(np.float32)
+    autocorrelation /= max(float(autocorrelation.max()), 1e-12)
+
+    ky, kx = np.mgrid[0:height, 0:width]
+    offsets = np.stack([ky - height // 2, kx - width // 2], axis=-1)
+    distance = np.hypot(offsets[..., 0], offsets[..., 1])
+    local_max = autocorrelation >= cv2.dilate(autocorrelation, np.ones((5, 5), np.uint8))
+    candidates = local_max & (distance >= min_period) & (distance <= min(height, width) / 2) & (autocorrelation > 0.3)
+    if not candidates.any():
+        return None, None
+    vectors = offsets[candidates]
+    values = autocorrelation[candidates]
+    lengths = distance[candidates]
+
+    # The shortest strong vector is a basis vector; the second is the shortest strong one not parallel to it.
+    strong = values >= 0.5 * values.max()
+    first = vectors[strong][np.argmin(lengths[strong])]
+    cross = np.abs(vectors[:, 0] * first[1] - vectors[:, 1] * first[0]) / (lengths * np.hypot(*first))
+    independent = cross > 0.3
+    second = vectors[independent][np.argmin(lengths[independent])] if independent.any() else None
+
+    first = _refine_vector(autocorrelation, first)
+    if second is not None:
+        second = _refine_vector(autocorrelation, second)
+    return first, second
+
+
+def _fold(image: np.ndarray, first: np.ndarray, second: Optional[np.ndarray], mask: np.ndarray,
+          min_alpha: float) -> Tuple[np.ndarray, np.ndarray]:
+    """Opacity and colour maps from statistics over the repeats of each lattice cell position."""
+    height, width = image.shape[:2]
+    if second is None:
+        # Stripes: the "cell" spans the whole image across the stripes.
+        second = np.array([first[1], -first[0]]) / max(np.hypot(*first), 1e-6) * max(height, width) * 2
+    basis = np.array([[first[1], second[1]], [first[0], second[0]]])  # columns: (dx, dy)
+    inverse = np.linalg.inv(basis)
+
+    y, x = np.mgrid[0:height, 0:width].astype(np.float64)
+    u = inverse[0, 0] * x + inverse[0, 1] * y
+    v = inverse[1, 0] * x + inverse[1, 1] * y
+    bins_u = max(8, int(round(np.hypot(*first))))
+    bins_v = max(8, min(int(round(np.hypot(*second))), 4096))
+    cell = (np.floor((u % 1) * bins_u).astype(np.int64) % bins_u) * bins_v + \
+           (np.floor((v % 1) * bins_v).astype(np.int64) % bins_v)
+    cell = cell.ravel()
+    count = np.maximum(np.bincount(cell, minlength=bins_u * bins_v), 1)
+
+    pixels = image.reshape(-1, 3).astype(np.float64)
+    mean = np.stack([np.bincount(cell, weights=pixels[:, c], minlength=count.size) for c in range(3)], axis=1) / count[:, None]
+    square = np.stack([np.bincount(cell, weights=pixels[:, c] ** 2, minlength=count.size) for c in range(3)], axis=1) / count[:, None]
+    spread = np.sqrt(np.maximum(square - mean ** 2, 0)).mean(axis=1)
+
+    in_mask = np.bincount(cell, weights=(mask.ravel() > 0).astype(np.float64), minlength=count.size) / count > 0.5
+    reference = float(np.median(spread[~in_mask])) if (~in_mask).any() else float(np.median(spread))
+    background = mean[~in_mask].mean(axis=0) if (~in_mask).any() else mean.mean(axis=0)
+
+    alpha = np.clip(1 - spread / max(reference, 1e-6), 0, 1)
+    alpha[alpha < min_alpha] = 0
+    safe_alpha = np.maximum(alpha, 1e-3)[:, None]
+    watermark = np.clip((mean - (1 - safe_alpha) * background) / safe_alpha, 0, 255)
+    watermark[alpha == 0] = 0
+
+    return (alpha[cell].reshape(height, width).astype(np.float32),
+            watermark[cell].reshape(height, width, 3).astype(np.float32))
+
+
+def detect_periodic_pattern(image: Union[Image.Image, np.ndarray], min_period: int = 16,
+                            min_alpha: float = 0.03, blend_estimate: bool = True) -> PeriodicPattern:
+    """
+    Looks for a watermark tiled across the image.
+
+    Args:
+        image (PIL.Image.Image | np.ndarray): The image; arrays are RGB uint8 (H, W, 3).
+        min_period (int, optional): Shortest repeat distance in analysis pixels; finer periodicity
+            (textures, JPEG blocks) is ignored. Defaults to 16.
+        min_alpha (float, optional): Opacity below which a pixel is outside the estimated overlay.
+            Defaults to 0.03.
+        blend_estimate (bool, optional): Fold the full-resolution image into the lattice for the
+            alpha/colour estimate. This needs several float64 copies of the image; detection only
+            needs the mask and period and can skip it. Defaults to True.
+
+    Returns:
+        PeriodicPattern: Confidence, full-image mask and, when the lattice was found,
+        period vectors and (with blend_estimate) an alpha/colour estimate. The mask is empty
+        if nothing periodic was found.
+    """
+    rgb = np.asarray(image.convert("RGB")) if isinstance(image, Image.Image) else image
+    height, width = rgb.shape[:2]
+    factor = min(1.0, ANALYSIS_MAX_SIDE / max(height, width))
+    work = rgb if factor == 1 else cv2.resize(rgb, (round(width * factor), round(height * factor)),
+                                              interpolation=cv2.INTER_AREA)
+    gray = cv2.cvtColor(work, cv2.COLOR_RGB2GRAY).astype(np.float32)
+    max_period = min(gray.shape) // 2
+    empty = PeriodicPattern(confidence=0.0, mask=np.zeros((height, width), dtype=np.uint8))
+    if max_period <= min_period:
+        return empty
+
+    # Remove shading and structures larger than one repeat.
+    high_pass = gray - cv2.GaussianBlur(gray, (0, 0), max_period / 4)
+    peaks, prominence = _spectral_peaks(high_pass, min_period, max_period)
+    if len(peaks) == 0:
+        return empty
+    confidence = float(np.clip((np.median(prominence[:6]) - 1) / 3, 0, 1)) * min(1.0, len(peaks) / 2)
+
+    pattern = _reconstruct(high_pass, peaks)
+    # The overlay covers the smaller part of each cell: its polarity is the skew of the pattern.
+    centered = pattern - pattern.mean()
+    polarised = centered if float((centered ** 3).mean()) >= 0 else -centered
+    scaled = np.clip(polarised / max(float(np.percentile(polarised, 99.5)), 1e-6) * 255, 0, 255).astype(np.uint8)
+    threshold, _ = cv2.threshold(scaled, 0, 255, cv2.THRESH_BINARY + cv2.THRESH_OTSU)
+    mask = ((scaled > threshold) * 255).astype(np.uint8)
+    mask = cv2.dilate(mask, np.ones((3, 3), np.uint8))
+    if factor != 1:
+        mask = cv2.resize(mask, (width, height), interpolation=cv2.INTER_NEAREST)
+
+    first, second = _lattice(pattern, min_period)
+    if first is None:
+        print(f"Periodic overlay: {len(peaks)} spectral peaks, confidence {confidence:.2f}, no lattice")
+        return PeriodicPattern(confidence=round(confidence, 3), mask=mask)
+
+    # Lattice vectors were measured on the analysis copy; fold at full resolution.
+    first_full = first / factor
+    second_full = second / factor if second is not None else None
+    period = ((float(first_full[1]), float(first_full[0])),
+              (float(second_full[1]), float(second_full[0])) if second_full is not None else None)
+    print(f"Periodic overlay: {len(peaks)} spectral peaks, confidence {confidence:.2f}, period {period}")
+    if not blend_estimate:
+        return PeriodicPattern(confidence=round(confidence, 3), mask=mask, period=period)
+
+    alpha, watermark = _fold(rgb, first_full, second_full, mask, min_alpha)
+    mask = np.maximum(mask, cv2.dilate(((alpha > 0) * 255).astype(np.uint8), np.ones((3, 3), np.uint8)))
+    return PeriodicPattern(confidence=round(confidence, 3), mask=mask, period=period, alpha=alpha, watermark=watermark)
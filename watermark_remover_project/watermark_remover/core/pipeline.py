@@ -0,0 +1,218 @@
+"""
+End-to-end watermark removal for one encoded image, shared by the API and the Celery tasks.
+
+`RemovalOptions` holds the per-request settings (the form fields of the API)
+and `remove_watermark_bytes` runs the whole request: building the mask from
+an upload, regions, colour keys, templates, text or automatic detection,
+refining it, inpainting with the selected engine and encoding the result in
+the upload's format.
+"""
+
+import json
+from dataclasses import asdict, dataclass, field, fields
+from functools import lru_cache
+from typing import Callable, Dict, Optional
+
+import numpy as np
+from PIL import Image
+
+from watermark_remover.config import settings
+from watermark_remover.core.backends import InpaintingBackend, available_backends, create_backend
+from watermark_remover.core.color_mask import color_mask
+from watermark_remover.core.refinement import QUALITY_PRESETS, with_quality
+from watermark_remover.core.templates import TemplateRegistry, get_template_registry, template_mask
+from watermark_remover.core.text_detection import detect_text
+from watermark_remover.core.tiling import with_tiling
+from watermark_remover.utils.image_io import encode_image, format_for_extension, open_image, split_channels
+from watermark_remover.utils.mask_formats import IMPORT_FORMATS, import_mask
+from watermark_remover.utils.mask_refinement import MaskRefinement
+from watermark_remover.utils.masks import load_mask
+from watermark_remover.utils.regions import add_regions, parse_regions
+
+# Called with the completed fraction in [0, 1] and a short stage name.
+ProgressCallback = Callable[[float, str], None]
+
+
+@lru_cache(maxsize=None)
+def get_backend(name: str, inpaint_radius: int = None) -> InpaintingBackend:
+    """
+    Returns the backend registered as `name`, creating it on first use.
+    Instances are cached so model weights are only loaded once per process.
+    `inpaint_radius` overrides the configured radius of the OpenCV engines (and LaMa's fallback).
+    """
+    options = settings.backend_options()
+    if inpaint_radius is not None:
+        options.update(radius=inpaint_radius, fallback_radius=inpaint_radius)
+    return create_backend(name, ignore_unknown_options=True, **options)
+
+
+def split_list(value: str):
+    """Comma-separated value as a list, or None when empty."""
+    items = [item.strip() for item in (value or "").split(",") if item.strip()]
+    return items or None
+
+
+@dataclass
+class RemovalOptions:
+    """Per-request options; None falls back to the configured default."""
+    backend: str = None
+    inpaint_radius: int = None
+    quality: str = None
+    inpaint_alpha: bool = False
+    keep_metadata: bool = True
+    jpeg_quality: int = None
+    min_confidence: float = None
+    # Format of an uploaded mask (see watermark_remover.utils.mask_formats.IMPORT_FORMATS).
+    mask_format: str = "auto"
+    # JSON region shapes (watermark_remover.utils.regions).
+    region: str = None
+    # Comma-separated key colours and their tolerance (watermark_remover.core.color_mask).
+    color_key: str = None
+    color_tolerance: float = None
+    # Comma-separated template names, or "*" for all.
+    templates: str = None
+    detect_text_overlays: bool = False
+    text_corners: str = None
+    text_colors: str = None
+    mask_dilate: int = None
+    mask_close: int = None
+    mask_min_area: int = None
+    mask_fill_holes: bool = None
+    mask_feather: int = None
+
+    @classmethod
+    def from_dict(cls, values: dict) -> "RemovalOptions":
+        """
+        Raises:
+            ValueError: If a key is not an option.
+        """
+        known = {option.name for option in fields(cls)}
+        unknown = sorted(set(values) - known)
+        if unknown:
+            raise ValueError(f"Unknown option(s): {', '.join(unknown)}")
+        return cls(**values)
+
+    def to_dict(self) -> dict:
+        return asdict(self)
+
+    @property
+    def backend_name(self) -> str:
+        return self.backend or settings.backend
+
+    def mask_refinement(self) -> MaskRefinement:
+        options = settings.mask_refinement_options()
+        overrides = {"dilate": self.mask_dilate, "close": self.mask_close, "min_area": self.mask_min_area,
+                     "fill_holes": self.mask_fill_holes, "feather": self.mask_feather}
+        options.update({key: value for key, value in overrides.items() if value is not None})
+        return MaskRefinement(**options)
+
+    def validate(self) -> "RemovalOptions":
+        """
+        Checks the options that can be checked without the image.
+
+        Raises:
+            ValueError: If the backend, quality, mask format, radius, region or refinement is invalid.
+        """
+        if self.backend_name not in available_backends():
+            raise ValueError(f"Unknown backend '{self.backend_name}'. Available: {', '.join(available_backends())}")
+        if self.inpaint_radius is not None and self.inpaint_radius < 1:
+            raise ValueError("inpaint_radius must be at least 1")
+        quality = self.quality or settings.quality
+        if quality not in QUALITY_PRESETS:
+            raise ValueError(f"Unknown quality '{quality}'. Expected one of: {', '.join(QUALITY_PRESETS)}")
+        if self.mask_format not in IMPORT_FORMATS:
+            raise ValueError(f"Unknown mask_format '{self.mask_format}'. Expected one of: {', '.join(IMPORT_FORMATS)}")
+        if self.region:
+            self.regions()
+        self.mask_refinement().validate()
+        return self
+
+    def regions(self):
+        # Parsed from JSON text so the value is never taken as a server-side file path.
+        return parse_regions(json.loads(self.region)) if self.region else None
+
+
+@dataclass
+class RemovalResult:
+    # The encoded output image.
+    content: bytes
+    media_type: str
+    # Response headers describing how the mask was found (X-Watermark-Confidence, X-Template-Matches).
+    headers: Dict[str, str] = field(default_factory=dict)
+
+
+def remove_watermark_bytes(image_bytes: bytes, filename: str, options: RemovalOptions = None,
+                           mask_bytes: bytes = None, mask_filename: str = None,
+                           template_registry: TemplateRegistry = None,
+                           progress: Optional[ProgressCallback] = None) -> RemovalResult:
+    """
+    Removes the watermark from an encoded image.
+
+    Args:
+        image_bytes (bytes): The encoded image.
+        filename (str): Its file name; the extension selects the output format.
+        options (RemovalOptions, optional): Per-request options. Defaults to the configured ones.
+        mask_bytes (bytes, optional): An encoded mask in any of the supported formats.
+        mask_filename (str, optional): The mask's file name, used to recognise its format.
+        template_registry (TemplateRegistry, optional): Library for `options.templates`.
+            Defaults to the configured templates folder.
+        progress (callable, optional): Called with (fraction, stage) as processing advances.
+
+    Returns:
+        RemovalResult: Encoded output, media type and mask headers.
+
+    Raises:
+        FileNotFoundError: If a requested template does not exist.
+        ValueError: If an option or input is invalid, e.g. an undecodable upload or a mask of the wrong size.
+    """
+    options = (options or RemovalOptions()).validate()
+    report = progress or (lambda fraction, stage: None)
+    min_confidence = settings.detection_min_confidence if options.min_confidence is None else options.min_confidence
+
+    source = open_image(image_bytes)
+    report(0.1, "decoded")
+    mask_input = None
+    if mask_bytes:
+        mask_input = import_mask(mask_bytes, source.size, options.mask_format, file_name=mask_filename,
+                                 image_name=filename)
+    regions = options.regions()
+    if options.color_key:
+        tolerance = settings.color_key_tolerance if options.color_tolerance is None else options.color_tolerance
+        keyed = color_mask(split_channels(source)[0], split_list(options.color_key), tolerance, regions)
+        mask_input = keyed if mask_input is None else np.maximum(load_mask(mask_input, source.size), keyed)
+    elif regions:
+        mask_input = add_regions(mask_input, regions, source.size)
+
+    # Large images are processed as crops around the mask to bound memory use
+    engine = with_tiling(with_quality(get_backend(options.backend_name, options.inpaint_radius),
+                                      options.quality or settings.quality), **settings.tiling_options())
+
+    headers = {}
+    if mask_input is None and options.templates:
+        names = None if options.templates.strip() == "*" else split_list(options.templates)
+        mask_input, matches = template_mask(split_channels(source)[0],
+                                            template_registry or get_template_registry(),
+                                            names, settings.template_match_threshold)
+        headers["X-Template-Matches"] = ",".join(match.name for match in matches)
+    elif mask_input is None and options.detect_text_overlays:
+        detection = detect_text(split_channels(source)[0], split_list(options.text_corners),
+                                colors=split_list(options.text_colors))
+        mask_input = detection.mask
+        headers["X-Watermark-Confidence"] = f"{detection.confidence:.3f}"
+    elif mask_input is None:
+        # Detect here rather than in inpaint() to report the confidence.
+        mask_input, confidence = engine.resolve_mask(source, None, min_confidence)
+        headers["X-Watermark-Confidence"] = f"{confidence:.3f}"
+    report(0.3, "mask")
+
+    processed_image = engine.inpaint(source, mask_input, inpaint_alpha=options.inpaint_alpha,
+                                     mask_refinement=options.mask_refinement())
+    report(0.9, "inpainted")
+
+    # Reply in the upload's format, keeping its profile, metadata and JPEG quality.
+    output_format = format_for_extension(filename, default=None) or source.format or "PNG"
+    content = encode_image(processed_image, output_format, source, keep_metadata=options.keep_metadata,
+                           jpeg_quality=options.jpeg_quality)
+    report(1.0, "encoded")
+    return RemovalResult(content=content, media_type=Image.MIME.get(output_format, "application/octet-stream"),
+                         headers=headers)
@@ -0,0 +1,227 @@
+"""
+Exact removal of known semi-transparent watermarks by reversing the alpha blend.
+
+A watermark W stamped with opacity alpha turns the original pixel I into
+J = alpha * W + (1 - alpha) * I. Unlike inpainting, which discards the
+masked pixels, this is invertible wherever alpha < 1:
+
+    I = (J - alpha * W) / (1 - alpha)
+
+Pixels where the watermark is (nearly) opaque, the observation is clipped to
+0/255, or the inversion falls out of range carry no usable information and
+are handed to a fallback inpainting engine (LaMa by default).
+
+`PeriodicBlendBackend` does the same for overlays tiled across the whole
+image, with the alpha/colour estimate taken from the image itself
+(see `watermark_remover.core.periodic`).
+"""
+
+import copy
+from typing import Tuple, Union
+
+import numpy as np
+
+from watermark_remover.core.backends import InpaintingBackend, create_backend
+from watermark_remover.core.detection import DEFAULT_MIN_CONFIDENCE
+from watermark_remover.core.estimation import WatermarkTemplate, locate_template, place_array, place_mask
+from watermark_remover.core.periodic import detect_periodic_pattern
+from watermark_remover.utils.image_io import open_image, split_channels
+
+
+class ReverseBlendBackend(InpaintingBackend):
+    name = "reverse_blend"
+    # The template is located in whole images, so tiling and refinement wrap the fallback instead.
+    whole_image_only = True
+
+    def __init__(self, template: Union[WatermarkTemplate, str], fallback: InpaintingBackend = None,
+                 max_alpha: float = 0.95, clip_level: int = 2, tolerance: float = 12.0,
+                 position: Tuple[int, int] = None, match_threshold: float = 0.5):
+        """
+        Args:
+            template (WatermarkTemplate | str): The watermark's colour and alpha map, or the path
+                of a template saved by `WatermarkTemplate.save`.
+            fallback (InpaintingBackend, optional): Engine for pixels that cannot be recovered.
+                Without one they are left as observed.
+            max_alpha (float, optional): Opacity from which pixels are inpainted instead of
+                recovered; dividing by 1 - alpha amplifies noise and JPEG artefacts. Defaults to 0.95.
+            clip_level (int, optional): Observed values within this distance of 0 or 255 count
+                as clipped under the watermark. Defaults to 2.
+            tolerance (float, optional): How far outside [0, 255] a recovered value may fall
+                before it is treated as a mismatch with the template. Defaults to 12.
+            position (tuple, optional): (x, y) of the template in every image. Defaults to
+                locating it in each image by gradient matching.
+            match_threshold (float, optional): Correlation of the gradient match needed to use the
+                template's mask when no mask is given. Defaults to 0.5.
+
+        Raises:
+            FileNotFoundError: If template is a path that does not exist.
+        """
+        self.template = WatermarkTemplate.load(template) if isinstance(template, str) else template
+        self.fallback = fallback
+        self.max_alpha = max_alpha
+        self.clip_level = clip_level
+        self.tolerance = tolerance
+        self.position = position
+        self.match_threshold = match_threshold
+
+    def with_fallback(self, fallback: InpaintingBackend) -> "ReverseBlendBackend":
+        """Returns a copy using `fallback`; the template is shared."""
+        backend = copy.copy(self)
+        backend.fallback = fallback
+        return backend
+
+    def _fits(self, image: np.ndarray) -> bool:
+        """Whether the template can be located in `image`: matching needs an image at least its size."""
+        height, width = self.template.gradient.shape[:2]
+        return self.position is not None or (image.shape[0] >= height and image.shape[1] >= width)
+
+    def locate(self, image: np.ndarray) -> Tuple[Tuple[int, int], float]:
+        """
+        (x, y) of the template in `image` and the match score (1.0 for a fixed position,
+        -1.0 if the image is smaller than the template).
+        """
+        if self.position is not None:
+            return self.position, 1.0
+        if not self._fits(image):
+            return (0, 0), -1.0
+        return locate_template(image, self.template)
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        hole = mask > 0
+        if not hole.any():
+            return image.copy()
+        if not self._fits(image):
+            print("Image smaller than the watermark template; leaving it unchanged.")
+            return image.copy()
+
+        size = (image.shape[1], image.shape[0])
+        position, _ = self.locate(image)
+        alpha = place_array(self.template.alpha, size, position)
+        watermark = place_array(self.template.watermark, size, position)
+
+        observed = image.astype(np.float32)
+        recovered = (observed - alpha[..., None] * watermark) / np.maximum(1 - alpha, 1e-3)[..., None]
+
+        stamped = alpha > 0
+        clipped = ((image <= self.clip_level) | (image >= 255 - self.clip_level)).any(axis=2) & stamped
+        out_of_range = ((recovered < -self.tolerance) | (recovered > 255 + self.tolerance)).any(axis=2)
+        unrecoverable = hole & ((alpha >= self.max_alpha) | clipped | out_of_range)
+        reversible = hole & ~unrecoverable
+
+        result = image.copy()
+        result[reversible] = np.clip(np.rint(recovered[reversible]), 0, 255).astype(np.uint8)
+        print(f"Reverse blending: {int(reversible.sum())} pixels recovered, "
+              f"{int(unrecoverable.sum())} left to {'the fallback' if self.fallback else 'nobody (no fallback)'}")
+
+        if unrecoverable.any() and self.fallback is not None:
+            result = self.fallback.predict(result, unrecoverable.astype(np.uint8) * 255)
+        return result
+
+    def resolve_mask(self, image, mask=None, min_confidence=DEFAULT_MIN_CONFIDENCE):
+        """
+        Without a mask, the template's own mask is used where the template matches.
+
+        The match score is a correlation, not a detection confidence, so it is compared with
+        `match_threshold` rather than `min_confidence`.
+        """
+        if mask is not None:
+            return super().resolve_mask(image, mask, min_confidence)
+
+        image = open_image(image)
+        position, score = self.locate(split_channels(image)[0])
+        if score < self.match_threshold:
+            print(f"Watermark template not found (match score {score:.2f} < {self.match_threshold:.2f}); "
+                  f"leaving the image unchanged.")
+            return np.zeros((image.height, image.width), dtype=np.uint8), score
+        print(f"Watermark template found at {position} (match score {score:.2f}).")
+        return place_mask(self.template, image.size, position), score
+
+
+def create_reverse_blend_backend(template_path: str = None, fallback_backend: str = "lama",
+                                 max_alpha: float = 0.95, match_threshold: float = 0.5,
+                                 **fallback_options) -> ReverseBlendBackend:
+    """
+    Registry factory: a ReverseBlendBackend with a registered backend as fallback.
+
+    Args:
+        template_path (str): Saved watermark template (.npz).
+        fallback_backend (str, optional): Registered backend for unrecoverable pixels, or an empty
+            string for none. Defaults to "lama".
+        max_alpha (float, optional): See `ReverseBlendBackend`.
+        match_threshold (float, optional): See `ReverseBlendBackend`.
+        **fallback_options: Options for the fallback backend (model_path, radius, ...);
+            those it does not accept are ignored.
+
+    Raises:
+        ValueError: If no template path is given.
+    """
+    if not template_path:
+        raise ValueError("The reverse_blend backend needs a watermark template "
+                         "(WATERMARK_REMOVER_TEMPLATE_PATH or --template)")
+    fallback = None
+    if fallback_backend:
+        fallback = create_backend(fallback_backend, ignore_unknown_options=True, **fallback_options)
+    return ReverseBlendBackend(template_path, fallback=fallback, max_alpha=max_alpha, match_threshold=match_threshold)
+
+
+class PeriodicBlendBackend(InpaintingBackend):
+    name = "periodic_blend"
+    # The lattice is estimated on whole images, so tiling and refinement wrap the fallback instead.
+    whole_image_only = True
+
+    def __init__(self, fallback: InpaintingBackend = None, min_confidence: float = 0.3, max_alpha: float = 0.95):
+        """
+        Removes tiled overlays by reverse blending a per-image periodic estimate.
+
+        Args:
+            fallback (InpaintingBackend, optional): Engine for pixels that cannot be recovered and
+                for images without a usable periodic estimate.
+            min_confidence (float, optional): Periodic confidence needed to reverse blend. Defaults to 0.3.
+            max_alpha (float, optional): See `ReverseBlendBackend`.
+        """
+        self.fallback = fallback
+        self.min_confidence = min_confidence
+        self.max_alpha = max_alpha
+
+    def with_fallback(self, fallback: InpaintingBackend) -> "PeriodicBlendBackend":
+        return PeriodicBlendBackend(fallback, min_confidence=self.min_confidence, max_alpha=self.max_alpha)
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        pattern = detect_periodic_pattern(image)
+        if pattern.confidence >= self.min_confidence and pattern.has_blend_estimate:
+            engine = ReverseBlendBackend(pattern.to_watermark_template(), fallback=self.fallback,
+                                         max_alpha=self.max_alpha, position=(0, 0))
+            return engine.predict(image, mask)
+        if self.fallback is None:
+            print("No periodic overlay estimate and no fallback; leaving the image unchanged.")
+            return image.copy()
+        return self.fallback.predict(image, mask)
+
+    def resolve_mask(self, image, mask=None, min_confidence=DEFAULT_MIN_CONFIDENCE):
+        """Without a mask, the periodic overlay mask is used."""
+        if mask is not None:
+            return super().resolve_mask(image, mask, min_confidence)
+
+        image = open_image(image)
+        # Only the mask is needed here; predict runs the full-resolution alpha/colour fold.
+        pattern = detect_periodic_pattern(split_channels(image)[0], blend_estimate=False)
+        if pattern.confidence < min_confidence:
+            print(f"No periodic overlay found (confidence {pattern.confidence:.2f} < {min_confidence:.2f}); "
+                  f"leaving the image unchanged.")
+            return np.zeros_like(pattern.mask), pattern.confidence
+        return pattern.mask, pattern.confidence
+
+
+def create_periodic_blend_backend(fallback_backend: str = "lama", **fallback_options) -> PeriodicBlendBackend:
+    """
+    Registry factory: a PeriodicBlendBackend with a registered backend as fallback.
+
+    Args:
+        fallback_backend (str, optional): Registered backend for unrecoverable pixels, or an empty
+            string for none. Defaults to "lama".
+        **fallback_options: Options for the fallback backend; those it does not accept are ignored.
+    """
+    fallback = None
+    if fallback_backend:
+        fallback = create_backend(fallback_backend, ignore_unknown_options=True, **fallback_options)
+    return PeriodicBlendBackend(fallback)
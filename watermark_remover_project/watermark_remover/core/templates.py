@@ -0,0 +1,363 @@
+"""
+Library of known watermark logos and a matcher that finds them in new images.
+
+Each template is stored under its name in the template storage
+(watermark_remover.storage: WATERMARK_REMOVER_TEMPLATES_DIR, or a prefix in
+the S3 bucket, so that every API process and worker shares one library):
+
+    <name>/template.png   RGBA: the logo's colours and its alpha (opacity)
+    <name>/meta.json      name, optional scale range, creation time
+
+Matching uses multi-scale normalised cross-correlation on edge maps: a
+semi-transparent logo mixes with whatever is under it, so its colours vary
+from image to image, but its edges stay where they are.
+"""
+
+import io
+import json
+import os
+import re
+import time
+from dataclasses import asdict, dataclass, field
+from typing import List, Optional, Sequence, Tuple, Union
+
+import cv2
+import numpy as np
+from PIL import Image
+
+from watermark_remover.core.estimation import WatermarkTemplate
+from watermark_remover.storage import LocalStorage, Storage, get_template_storage
+from watermark_remover.utils.image_io import split_channels
+
+TEMPLATE_FILE = "template.png"
+META_FILE = "meta.json"
+NAME_PATTERN = re.compile(r"^[A-Za-z0-9][A-Za-z0-9_.-]{0,63}$")
+
+# Scales tried for templates without their own range, relative to the stored size.
+DEFAULT_SCALE_RANGE = (0.5, 2.0)
+# Images are matched at most at this longest side; larger ones are downscaled first.
+MATCH_MAX_SIDE = 1024
+
+
+@dataclass
+class LogoTemplate:
+    name: str
+    # RGB uint8 (h, w, 3) logo colours.
+    image: np.ndarray
+    # uint8 (h, w) opacity, 255 fully opaque.
+    alpha: np.ndarray
+    # (min, max) scale relative to the stored size, or None for DEFAULT_SCALE_RANGE.
+    scale_range: Optional[Tuple[float, float]] = None
+    created_at: float = field(default_factory=time.time)
+
+    @property
+    def size(self) -> Tuple[int, int]:
+        """(width, height) of the stored template."""
+        return self.alpha.shape[1], self.alpha.shape[0]
+
+    def metadata(self) -> dict:
+        """JSON-serialisable description, as stored in meta.json."""
+        width, height = self.size
+        return {
+            "name": self.name,
+            "width": width,
+            "height": height,
+            "scale_range": list(self.scale_range) if self.scale_range else None,
+            "created_at": self.created_at,
+        }
+
+    def to_rgba(self) -> Image.Image:
+        return Image.fromarray(np.dstack([self.image, self.alpha]))
+
+    def to_watermark_template(self) -> WatermarkTemplate:
+        """The template as colour + alpha map, e.g. for the reverse_blend backend."""
+        alpha = self.alpha.astype(np.float32) / 255
+        watermark = self.image.astype(np.float32)
+        return WatermarkTemplate(alpha=alpha, watermark=watermark, mask=((self.alpha > 0) * 255).astype(np.uint8),
+                                 gradient=_edges(_signature(self.image, self.alpha)))
+
+
+@dataclass
+class TemplateMatch:
+    name: str
+    # Top-left corner and size of the match in the input image.
+    x: int
+    y: int
+    width: int
+    height: int
+    scale: float
+    # Normalised cross-correlation in [-1, 1].
+    score: float
+    # uint8 (height, width) mask of the match, 255 on the logo.
+    mask: np.ndarray = field(repr=False, default=None)
+
+    def to_dict(self) -> dict:
+        match = asdict(self)
+        match.pop("mask")
+        return match
+
+
+def read_template_image(path: str) -> Tuple[np.ndarray, Optional[np.ndarray]]:
+    """
+    Reads a logo for the registry: an image file (alpha taken from its transparency, if any)
+    or a `.npz` template from scripts/estimate_watermark.py.
+
+    Returns:
+        tuple: (RGB uint8 (h, w, 3), uint8 (h, w) alpha or None).
+
+    Raises:
+        FileNotFoundError: If path does not exist.
+    """
+    if not os.path.exists(path):
+        raise FileNotFoundError(f"Template image not found: {path}")
+    if path.lower().endswith(".npz"):
+        estimated = WatermarkTemplate.load(path)
+        return (np.clip(np.rint(estimated.watermark), 0, 255).astype(np.uint8),
+                np.clip(np.rint(estimated.alpha * 255), 0, 255).astype(np.uint8))
+    with Image.open(path) as image:
+        return split_channels(image)
+
+
+class TemplateRegistry:
+    def __init__(self, storage: Union[Storage, str]):
+        """
+        Args:
+            storage (Storage | str): Where the templates are stored, or a local folder (created on
+                first write).
+        """
+        self.storage = LocalStorage(storage) if isinstance(storage, str) else storage
+
+    @staticmethod
+    def _key(name: str, file_name: str = "") -> str:
+        if not NAME_PATTERN.match(name or ""):
+            raise ValueError(f"Invalid template name '{name}': use letters, digits, '_', '-' or '.' (max 64)")
+        return f"{name}/{file_name}"
+
+    def names(self) -> List[str]:
+        suffix = "/" + META_FILE
+        return sorted(item.key[:-len(suffix)] for item in self.storage.list()
+                      if item.key.endswith(suffix) and item.key.count("/") == 1)
+
+    def exists(self, name: str) -> bool:
+        return self.storage.read(self._key(name, META_FILE)) is not None
+
+    def list(self) -> List[dict]:
+        """Metadata of all templates, sorted by name."""
+        return [self.get(name).metadata() for name in self.names()]
+
+    def get(self, name: str) -> LogoTemplate:
+        """
+        Raises:
+            FileNotFoundError: If no template is registered under name.
+            ValueError: If name is invalid.
+        """
+        meta = self.storage.read(self._key(name, META_FILE))
+        data = self.storage.read(self._key(name, TEMPLATE_FILE))
+        if meta is None or data is None:
+            raise FileNotFoundError(f"Template not found: {name}")
+        meta = json.loads(meta)
+        with Image.open(io.BytesIO(data)) as image:
+            rgba = np.asarray(image.convert("RGBA"))
+        scale_range = tuple(meta["scale_range"]) if meta.get("scale_range") else None
+        return LogoTemplate(name=name, image=np.ascontiguousarray(rgba[..., :3]), alpha=np.ascontiguousarray(rgba[..., 3]),
+                            scale_range=scale_range, created_at=meta.get("created_at", 0.0))
+
+    def add(self, name: str, image: np.ndarray, alpha: np.ndarray = None, scale_range: Tuple[float, float] = None,
+            overwrite: bool = False) -> LogoTemplate:
+        """
+        Registers a logo.
+
+        Args:
+            name (str): Template name (letters, digits, '_', '-', '.').
+            image (np.ndarray): RGB uint8 (h, w, 3) logo.
+            alpha (np.ndarray, optional): uint8 (h, w) opacity. Defaults to fully opaque.
+            scale_range (tuple, optional): (min, max) scales to search, relative to the logo's size.
+            overwrite (bool, optional): Replace an existing template of the same name. Defaults to False.
+
+        Returns:
+            LogoTemplate: The stored template.
+
+        Raises:
+            ValueError: If the name is invalid or taken (without overwrite), the alpha does not match
+                the image, the scale range is invalid, or the logo is empty.
+        """
+        if self.exists(name) and not overwrite:
+            raise ValueError(f"Template '{name}' already exists")
+        if image.ndim != 3 or image.shape[2] != 3:
+            raise ValueError(f"Template image must be RGB (h, w, 3), got shape {image.shape}")
+        if alpha is None:
+            alpha = np.full(image.shape[:2], 255, dtype=np.uint8)
+        if alpha.shape != image.shape[:2]:
+            raise ValueError(f"Template alpha {alpha.shape[1]}x{alpha.shape[0]} does not match image "
+                             f"{image.shape[1]}x{image.shape[0]}")
+        if not alpha.any():
+            raise ValueError("Template alpha is fully transparent")
+        if scale_range is not None:
+            low, high = scale_range
+            if not 0 < low <= high:
+                raise ValueError(f"Invalid scale range {low}-{high}: need 0 < min <= max")
+            scale_range = (float(low), float(high))
+
+        template = LogoTemplate(name=name, image=image.astype(np.uint8), alpha=alpha.astype(np.uint8),
+                                scale_range=scale_range)
+        buffer = io.BytesIO()
+        template.to_rgba().save(buffer, format="PNG")
+        self.storage.write(self._key(name, TEMPLATE_FILE), buffer.getvalue())
+        # The metadata is written last: a template only exists once it is complete.
+        self.storage.write(self._key(name, META_FILE), json.dumps(template.metadata(), indent=2).encode("utf-8"))
+        print(f"Template '{name}' saved")
+        return template
+
+    def delete(self, name: str) -> None:
+        """
+        Raises:
+            FileNotFoundError: If no template is registered under name.
+        """
+        if not self.exists(name):
+            raise FileNotFoundError(f"Template not found: {name}")
+        self.storage.delete(self._key(name))
+        print(f"Template '{name}' deleted")
+
+
+def get_template_registry() -> TemplateRegistry:
+    """The template library in the configured storage (WATERMARK_REMOVER_STORAGE_BACKEND)."""
+    return TemplateRegistry(get_template_storage())
+
+
+def _signature(image: np.ndarray, alpha: np.ndarray) -> np.ndarray:
+    """What a logo contributes to an image: its colours weighted by opacity, as grey."""
+    gray = cv2.cvtColor(image, cv2.COLOR_RGB2GRAY).astype(np.float32)
+    return gray * (alpha.astype(np.float32) / 255)
+
+
+def _edges(gray: np.ndarray) -> np.ndarray:
+    gray = gray.astype(np.float32)
+    return cv2.magnitude(cv2.Sobel(gray, cv2.CV_32F, 1, 0, ksize=3), cv2.Sobel(gray, cv2.CV_32F, 0, 1, ksize=3))
+
+
+def _scales(scale_range: Tuple[float, float], step: float) -> List[float]:
+    low, high = scale_range
+    scales = [low]
+    while scales[-1] * step <= high:
+        scales.append(scales[-1] * step)
+    if high > scales[-1] * 1.01:
+        scales.append(high)
+    return scales
+
+
+def _iou(a: TemplateMatch, b: TemplateMatch) -> float:
+    width = min(a.x + a.width, b.x + b.width) - max(a.x, b.x)
+    height = min(a.y + a.height, b.y + b.height) - max(a.y, b.y)
+    if width <= 0 or height <= 0:
+        return 0.0
+    intersection = width * height
+    return intersection / (a.width * a.height + b.width * b.height - intersection)
+
+
+def match_templates(image: np.ndarray, templates: Sequence[LogoTemplate], threshold: float = 0.5,
+                    scale_step: float = 1.1, max_matches: int = 5) -> List[TemplateMatch]:
+    """
+    Finds registered logos in an image with multi-scale normalised cross-correlation.
+
+    Args:
+        image (np.ndarray): RGB uint8 (H, W, 3) image.
+        templates (Sequence[LogoTemplate]): Logos to look for.
+        threshold (float, optional): Minimum correlation of a match. Defaults to 0.5.
+        scale_step (float, optional): Ratio between successive scales tried. Defaults to 1.1.
+        max_matches (int, optional): Most matches kept per template. Defaults to 5.
+
+    Returns:
+        List[TemplateMatch]: Non-overlapping matches, best first.
+    """
+    height, width = image.shape[:2]
+    factor = min(1.0, MATCH_MAX_SIDE / max(height, width))
+    work = image if factor == 1 else cv2.resize(image, (round(width * factor), round(height * factor)),
+                                                interpolation=cv2.INTER_AREA)
+    image_edges = _edges(cv2.cvtColor(work, cv2.COLOR_RGB2GRAY))
+
+    candidates = []
+    for template in templates:
+        signature = _signature(template.image, template.alpha)
+        template_width, template_height = template.size
+        for scale in _scales(template.scale_range or DEFAULT_SCALE_RANGE, scale_step):
+            size = (round(template_width * scale * factor), round(template_height * scale * factor))
+            if min(size) < 8 or size[0] > image_edges.shape[1] or size[1] > image_edges.shape[0]:
+                continue
+            template_edges = _edges(cv2.resize(signature, size, interpolation=cv2.INTER_AREA))
+            if float(template_edges.std()) < 1e-3:
+                continue
+            scores = np.nan_to_num(cv2.matchTemplate(image_edges, template_edges, cv2.TM_CCOEFF_NORMED), nan=-1.0)
+            for _ in range(max_matches):
+                _, score, _, (x, y) = cv2.minMaxLoc(scores)
+                if score < threshold:
+                    break
+                candidates.append(TemplateMatch(
+                    name=template.name, x=round(x / factor), y=round(y / factor),
+                    width=round(size[0] / factor), height=round(size[1] / factor), scale=scale, score=float(score)))
+                # Suppress this peak before looking for another occurrence.
+                scores[max(0, y - size[1] // 2):y + size[1] // 2 + 1, max(0, x - size[0] // 2):x + size[0] // 2 + 1] = -1
+
+    matches: List[TemplateMatch] = []
+    counts = {}
+    for candidate in sorted(candidates, key=lambda match: match.score, reverse=True):
+        if counts.get(candidate.name, 0) >= max_matches:
+            continue
+        if any(_iou(candidate, kept) > 0.3 for kept in matches):
+            continue
+        matches.append(candidate)
+        counts[candidate.name] = counts.get(candidate.name, 0) + 1
+
+    by_name = {template.name: template for template in templates}
+    for match in matches:
+        alpha = cv2.resize(by_name[match.name].alpha, (match.width, match.height), interpolation=cv2.INTER_LINEAR)
+        match.mask = ((alpha > 8) * 255).astype(np.uint8)
+    return matches
+
+
+def matches_to_mask(matches: Sequence[TemplateMatch], image_size: Tuple[int, int], grow: int = 2) -> np.ndarray:
+    """
+    Combines matches into one engine mask.
+
+    Args:
+        matches (Sequence[TemplateMatch]): Matches from `match_templates`.
+        image_size (tuple): (width, height) of the image.
+        grow (int, optional): Dilation in pixels to cover anti-aliased logo edges. Defaults to 2.
+
+    Returns:
+        np.ndarray: uint8 (H, W) mask, 255 on matched logos.
+    """
+    width, height = image_size
+    mask = np.zeros((height, width), dtype=np.uint8)
+    for match in matches:
+        x0, y0 = max(match.x, 0), max(match.y, 0)
+        x1, y1 = min(match.x + match.width, width), min(match.y + match.height, height)
+        if x1 > x0 and y1 > y0:
+            region = match.mask[y0 - match.y:y1 - match.y, x0 - match.x:x1 - match.x]
+            mask[y0:y1, x0:x1] = np.maximum(mask[y0:y1, x0:x1], region)
+    if grow > 0 and mask.any():
+        mask = cv2.dilate(mask, np.ones((2 * grow + 1, 2 * grow + 1), np.uint8))
+    return mask
+
+
+def template_mask(image: np.ndarray, registry: TemplateRegistry, names: Sequence[str] = None,
+                  threshold: float = 0.5) -> Tuple[np.ndarray, List[TemplateMatch]]:
+    """
+    Engine mask for the registered templates found in an image.
+
+    Args:
+        image (np.ndarray): RGB uint8 (H, W, 3) image.
+        registry (TemplateRegistry): Where the templates are stored.
+        names (Sequence[str], optional): Templates to look for. Defaults to all registered ones.
+        threshold (float, optional): See `match_templates`.
+
+    Returns:
+        tuple: (uint8 (H, W) mask, matches).
+
+    Raises:
+        FileNotFoundError: If a named template is not registered.
+    """
+    templates = [registry.get(name) for name in (names or registry.names())]
+    matches = match_templates(image, templates, threshold=threshold)
+    for match in matches:
+        print(f"Template '{match.name}' found at ({match.x}, {match.y}), scale {match.scale:.2f}, score {match.score:.2f}")
+    return matches_to_mask(matches, (image.shape[1], image.shape[0])), matches
@@ -0,0 +1,141 @@
+"""
+Crop-around-mask tiled processing for very large images.
+
+Instead of one forward pass over the whole image, the mask is split into
+connected components, each component's bounding box is grown by a context
+margin, overlapping boxes are merged, and every box is inpainted separately.
+Boxes larger than the tile size are split into overlapping tiles that are
+processed in raster order and pasted with a linear feather across the
+overlap, so the working set of the wrapped backend is bounded by the tile
+size regardless of the image size.
+"""
+
+from typing import List, Tuple
+
+import cv2
+import numpy as np
+
+from watermark_remover.core.backends import InpaintingBackend
+
+# (top, left, bottom, right), bottom/right exclusive.
+Box = Tuple[int, int, int, int]
+
+
+def mask_regions(mask: np.ndarray, margin: int) -> List[Box]:
+    """
+    Returns boxes around the connected components of `mask`, each grown by `margin`
+    pixels (clipped to the image) and merged with any box it overlaps.
+    """
+    height, width = mask.shape
+    count, _, stats, _ = cv2.connectedComponentsWithStats((mask > 0).astype(np.uint8), connectivity=8)
+    boxes = []
+    for label in range(1, count):
+        x, y, w, h = stats[label, :4]
+        boxes.append((max(0, y - margin), max(0, x - margin),
+                      min(height, y + h + margin), min(width, x + w + margin)))
+
+    merged = True
+    while merged:
+        merged = False
+        result = []
+        for box in boxes:
+            for index, other in enumerate(result):
+                if box[0] < other[2] and other[0] < box[2] and box[1] < other[3] and other[1] < box[3]:
+                    result[index] = (min(box[0], other[0]), min(box[1], other[1]),
+                                     max(box[2], other[2]), max(box[3], other[3]))
+                    merged = True
+                    break
+            else:
+                result.append(box)
+        boxes = result
+    return sorted(boxes)
+
+
+def _tile_starts(start: int, stop: int, tile_size: int, overlap: int) -> List[int]:
+    if stop - start <= tile_size:
+        return [start]
+    step = tile_size - overlap
+    starts = list(range(start, stop - tile_size, step))
+    # The last tile is aligned to the end so every tile has the full size.
+    starts.append(stop - tile_size)
+    return starts
+
+
+def split_box(box: Box, tile_size: int, overlap: int) -> List[Box]:
+    """Splits a box into tiles of at most `tile_size` that overlap by `overlap` pixels, in raster order."""
+    top, left, bottom, right = box
+    return [(y, x, min(y + tile_size, bottom), min(x + tile_size, right))
+            for y in _tile_starts(top, bottom, tile_size, overlap)
+            for x in _tile_starts(left, right, tile_size, overlap)]
+
+
+class TiledInpainter(InpaintingBackend):
+    def __init__(self, backend: InpaintingBackend, context_margin: int = 128, tile_size: int = 1024,
+                 feather: int = 32):
+        """
+        Args:
+            backend (InpaintingBackend): Engine used on each crop.
+            context_margin (int, optional): Known pixels kept around each mask component
+                                            as context for the engine. Defaults to 128.
+            tile_size (int, optional): Maximum crop side passed to the engine. Defaults to 1024.
+            feather (int, optional): Width of the linear blend between overlapping tiles;
+                                     tiles overlap by twice this. Defaults to 32.
+
+        Raises:
+            ValueError: If the tile is too small for the requested overlap.
+        """
+        if tile_size <= 4 * feather:
+            raise ValueError(f"tile_size ({tile_size}) must be larger than 4 * feather ({4 * feather})")
+        self.backend = backend
+        self.name = backend.name
+        self.context_margin = context_margin
+        self.tile_size = tile_size
+        self.feather = feather
+
+    def predict(self, image: np.ndarray, mask: np.ndarray) -> np.ndarray:
+        result = image.copy()
+        hole = mask > 0
+        overlap = 2 * self.feather
+
+        for box in mask_regions(mask, self.context_margin):
+            for top, left, bottom, right in split_box(box, self.tile_size, overlap):
+                tile_hole = hole[top:bottom, left:right]
+                if not tile_hole.any():
+                    continue
+                # Earlier tiles' fills are already in `result` and act as context here.
+                tile_input = np.ascontiguousarray(result[top:bottom, left:right])
+                filled = self.backend.predict(tile_input, mask[top:bottom, left:right])
+
+                weight = self._feather_weights(box, (top, left, bottom, right), overlap)
+                blended = weight[..., None] * filled + (1 - weight[..., None]) * tile_input
+                blended = np.clip(np.rint(blended), 0, 255).astype(np.uint8)
+                result[top:bottom, left:right][tile_hole] = blended[tile_hole]
+
+        return result
+
+    @staticmethod
+    def _feather_weights(box: Box, tile: Box, overlap: int) -> np.ndarray:
+        """
+        Weight of a new tile: ramps 0 -> 1 across its top/left overlap with tiles already
+        pasted (raster order), and is 1 everywhere else.
+        """
+        top, left, bottom, right = tile
+        ramp_y = np.ones(bottom - top, dtype=np.float32)
+        ramp_x = np.ones(right - left, dtype=np.float32)
+        if top > box[0]:
+            ramp_y[:overlap] = (np.arange(overlap, dtype=np.float32) + 1) / (overlap + 1)
+        if left > box[1]:
+            ramp_x[:overlap] = (np.arange(overlap, dtype=np.float32) + 1) / (overlap + 1)
+        return np.minimum(ramp_y[:, None], ramp_x[None, :])
+
+
+def with_tiling(backend: InpaintingBackend, tile_size: int, context_margin: int = 128,
+                feather: int = 32) -> InpaintingBackend:
+    """Wraps `backend` in a TiledInpainter, or returns it unchanged when tile_size is 0 (tiling disabled)."""
+    if tile_size <= 0:
+        return backend
+    if getattr(backend, "whole_image_only", False):
+        if backend.fallback is None:
+            return backend
+        return backend.with_fallback(with_tiling(backend.fallback, tile_size, context_margin, feather))
+    return TiledInpainter(backend, context_margin=context_margin, tile_size=tile_size, feather=feather)
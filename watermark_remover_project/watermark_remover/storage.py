@@ -0,0 +1,213 @@
+"""
+Storage for job artifacts (uploads, masks, results and their state) and the
+watermark template library.
+
+Artifacts are addressed by keys, relative "/"-separated paths such as
+"<job id>/input". Two backends are available, selected with
+WATERMARK_REMOVER_STORAGE_BACKEND:
+
+- "local" (default): files under WATERMARK_REMOVER_JOBS_DIR (templates under
+  WATERMARK_REMOVER_TEMPLATES_DIR). The API and the workers must share these
+  folders, i.e. run on one host or mount them.
+- "s3": objects in an S3-compatible bucket (AWS S3, MinIO, Ceph, ...), so
+  workers on other hosts can reach them. Configure WATERMARK_REMOVER_S3_BUCKET
+  and, for self-hosted services, WATERMARK_REMOVER_S3_ENDPOINT_URL. Jobs and
+  templates live under the WATERMARK_REMOVER_S3_PREFIX and
+  WATERMARK_REMOVER_S3_TEMPLATES_PREFIX key prefixes. Requires boto3.
+"""
+
+import os
+import shutil
+import uuid
+from dataclasses import dataclass
+from functools import lru_cache
+from typing import Iterator, Optional
+
+from watermark_remover.config import settings
+
+STORAGE_BACKENDS = ("local", "s3")
+
+
+def _check_key(key: str) -> str:
+    parts = (key or "").split("/")
+    # A trailing "/" (an empty last part) makes the key a folder prefix.
+    invalid_parts = any(part in ("", ".", "..") for part in parts[:-1]) or parts[-1] in (".", "..")
+    if not key or "\\" in key or invalid_parts:
+        raise ValueError(f"Invalid storage key '{key}'")
+    return key
+
+
+@dataclass
+class StoredObject:
+    key: str
+    # Size in bytes and last modification time (Unix seconds).
+    size: int
+    modified: float
+
+
+class Storage:
+    """Key-value store of artifact bytes."""
+
+    def read(self, key: str) -> Optional[bytes]:
+        """Contents stored under a key, or None if there are none."""
+        raise NotImplementedError
+
+    def write(self, key: str, data: bytes) -> None:
+        """Stores bytes under a key, replacing them as a whole: readers never see partial contents."""
+        raise NotImplementedError
+
+    def delete(self, prefix: str) -> int:
+        """
+        Deletes every key starting with a prefix (a key, or a folder ending with "/").
+
+        Returns:
+            int: Number of artifacts deleted.
+        """
+        raise NotImplementedError
+
+    def list(self, prefix: str = "") -> Iterator[StoredObject]:
+        """Every stored artifact whose key starts with a prefix (all of them by default), in no particular order."""
+        raise NotImplementedError
+
+
+class LocalStorage(Storage):
+    """Artifacts as files under a root folder."""
+
+    def __init__(self, root: str):
+        self.root = root
+
+    def _path(self, key: str) -> str:
+        return os.path.join(self.root, *_check_key(key).rstrip("/").split("/"))
+
+    def read(self, key: str) -> Optional[bytes]:
+        path = self._path(key)
+        if not os.path.isfile(path):
+            return None
+        with open(path, "rb") as f:
+            return f.read()
+
+    def write(self, key: str, data: bytes) -> None:
+        path = self._path(key)
+        os.makedirs(os.path.dirname(path), exist_ok=True)
+        # Write then rename, so readers never see a partially written file. The temporary name is
+        # unique per call: threads of one process may write the same key at once.
+        temporary = f"{path}.{uuid.uuid4().hex}.tmp"
+        try:
+            with open(temporary, "wb") as f:
+                f.write(data)
+            os.replace(temporary, path)
+        except BaseException:
+            if os.path.exists(temporary):
+                os.remove(temporary)
+            raise
+
+    def delete(self, prefix: str) -> int:
+        path = self._path(prefix)
+        if prefix.endswith("/"):
+            if not os.path.isdir(path):
+                return 0
+            count = sum(len(files) for _, _, files in os.walk(path))
+            shutil.rmtree(path)
+            return count
+        if not os.path.isfile(path):
+            return 0
+        os.remove(path)
+        return 1
+
+    def list(self, prefix: str = "") -> Iterator[StoredObject]:
+        for directory, _, files in os.walk(self.root):
+            for name in files:
+                path = os.path.join(directory, name)
+                key = os.path.relpath(path, self.root).replace(os.sep, "/")
+                if not key.startswith(prefix):
+                    continue
+                try:
+                    stat = os.stat(path)
+                except FileNotFoundError:
+                    # Deleted while listing.
+                    continue
+                yield StoredObject(key, stat.st_size, stat.st_mtime)
+
+
+@lru_cache(maxsize=None)
+def _s3_client(endpoint_url: str, region: str, access_key: str, secret_key: str):
+    try:
+        import boto3
+    except ImportError as e:
+        raise ImportError("The s3 storage backend needs boto3: pip install boto3") from e
+    # Without explicit keys boto3 falls back to its usual credential chain (AWS_* variables, profiles, roles).
+    return boto3.client("s3", endpoint_url=endpoint_url or None, region_name=region or None,
+                        aws_access_key_id=access_key or None, aws_secret_access_key=secret_key or None)
+
+
+class S3Storage(Storage):
+    """Artifacts as objects in an S3-compatible bucket, under an optional key prefix."""
+
+    def __init__(self, bucket: str, prefix: str = "", endpoint_url: str = None, region: str = None,
+                 access_key: str = None, secret_key: str = None, client=None):
+        if not bucket:
+            raise ValueError("The s3 storage backend needs a bucket (WATERMARK_REMOVER_S3_BUCKET)")
+        self.bucket = bucket
+        self.prefix = prefix.strip("/") + "/" if prefix.strip("/") else ""
+        self.client = client or _s3_client(endpoint_url, region, access_key, secret_key)
+
+    def _key(self, key: str) -> str:
+        return self.prefix + _check_key(key)
+
+    def read(self, key: str) -> Optional[bytes]:
+        try:
+            response = self.client.get_object(Bucket=self.bucket, Key=self._key(key))
+        except self.client.exceptions.NoSuchKey:
+            return None
+        return response["Body"].read()
+
+    def write(self, key: str, data: bytes) -> None:
+        # A PUT replaces the object atomically.
+        self.client.put_object(Bucket=self.bucket, Key=self._key(key), Body=data)
+
+    def delete(self, prefix: str) -> int:
+        count, full_key = 0, self._key(prefix)
+        for page in self.client.get_paginator("list_objects_v2").paginate(Bucket=self.bucket, Prefix=full_key):
+            # A plain key must not also delete the keys it is a prefix of.
+            keys = [{"Key": item["Key"]} for item in page.get("Contents", [])
+                    if prefix.endswith("/") or item["Key"] == full_key]
+            if keys:
+                self.client.delete_objects(Bucket=self.bucket, Delete={"Objects": keys, "Quiet": True})
+                count += len(keys)
+        return count
+
+    def list(self, prefix: str = "") -> Iterator[StoredObject]:
+        paginator = self.client.get_paginator("list_objects_v2")
+        for page in paginator.paginate(Bucket=self.bucket, Prefix=self.prefix + prefix):
+            for item in page.get("Contents", []):
+                yield StoredObject(item["Key"][len(self.prefix):], item["Size"], item["LastModified"].timestamp())
+
+
+def _configured_storage(root: str, prefix: str) -> Storage:
+    if settings.storage_backend == "local":
+        return LocalStorage(root)
+    if settings.storage_backend == "s3":
+        return S3Storage(settings.s3_bucket, prefix, settings.s3_endpoint_url, settings.s3_region,
+                         settings.s3_access_key, settings.s3_secret_key)
+    raise ValueError(f"Unknown storage backend '{settings.storage_backend}'. Choose from: {', '.join(STORAGE_BACKENDS)}")
+
+
+def get_storage() -> Storage:
+    """
+    The configured storage backend for job artifacts.
+
+    Raises:
+        ValueError: If WATERMARK_REMOVER_STORAGE_BACKEND is unknown, or s3 has no bucket.
+    """
+    return _configured_storage(settings.jobs_dir, settings.s3_prefix)
+
+
+def get_template_storage() -> Storage:
+    """
+    The configured storage backend for the template library, so that every API process and
+    worker sees the same templates.
+
+    Raises:
+        ValueError: If WATERMARK_REMOVER_STORAGE_BACKEND is unknown, or s3 has no bucket.
+    """
+    return _configured_storage(settings.templates_dir, settings.s3_templates_prefix)
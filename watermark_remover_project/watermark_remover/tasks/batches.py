@@ -0,0 +1,185 @@
+"""
+Batches: many images uploaded at once, processed as one job each.
+
+The uploads (files and/or ZIP archives) are unpacked into named images, one
+job is created per image, and the jobs run as a Celery chord: a group of
+per-image tasks whose callback packs the results and a JSON manifest with
+each file's status and error into a ZIP.
+"""
+
+import io
+import json
+import os
+import posixpath
+import zipfile
+from typing import List, Sequence, Tuple
+
+from celery import chord
+
+from watermark_remover.config import settings
+from watermark_remover.tasks.jobs import RESULT, Batch, JobStore
+
+IMAGE_EXTENSIONS = (".png", ".jpg", ".jpeg", ".webp", ".bmp", ".tif", ".tiff")
+MANIFEST = "manifest.json"
+
+
+def _clean_name(name: str) -> str:
+    """A relative archive path without drive letters, leading slashes or '..' parts."""
+    parts = [part for part in name.replace("\\", "/").split("/") if part not in ("", ".", "..")]
+    return "/".join(parts)
+
+
+def _unique(name: str, taken: set) -> str:
+    stem, extension = posixpath.splitext(name)
+    candidate, counter = name, 1
+    while candidate in taken or candidate == MANIFEST:
+        candidate = f"{stem}-{counter}{extension}"
+        counter += 1
+    taken.add(candidate)
+    return candidate
+
+
+def unpack_uploads(uploads: Sequence[Tuple[str, bytes]], max_files: int = None,
+                   max_bytes: int = None) -> Tuple[List[Tuple[str, bytes]], List[dict]]:
+    """
+    Expands uploaded files and ZIP archives into named images.
+
+    Args:
+        uploads (Sequence[tuple]): (file name, contents) of each uploaded file.
+        max_files (int, optional): Most images in total. Defaults to WATERMARK_REMOVER_BATCH_MAX_FILES.
+        max_bytes (int, optional): Largest total size of the images. Defaults to WATERMARK_REMOVER_BATCH_MAX_MB.
+
+    Returns:
+        tuple: ([(unique relative name, contents)] of the images, [manifest entries of skipped files]).
+            Archive members keep their folder inside the archive; files that are not images are skipped.
+
+    Raises:
+        ValueError: If an archive is corrupt, or there are no images or too many or too large ones.
+    """
+    max_files = settings.batch_max_files if max_files is None else max_files
+    max_bytes = settings.batch_max_mb * 1024 * 1024 if max_bytes is None else max_bytes
+    images, skipped, taken = [], [], set()
+    total = 0
+
+    def add(name, read, size):
+        nonlocal total
+        name = _clean_name(name)
+        if not name.lower().endswith(IMAGE_EXTENSIONS):
+            skipped.append({"filename": name, "status": "skipped", "error": "Not an image file"})
+            return
+        if len(images) >= max_files:
+            raise ValueError(f"Batch has more than {max_files} images")
+        # Checked before reading, so archives cannot expand beyond the limit.
+        total += size
+        if total > max_bytes:
+            raise ValueError(f"Batch images exceed {max_bytes // (1024 * 1024)} MB")
+        images.append((_unique(name, taken), read()))
+
+    for filename, data in uploads:
+        if filename.lower().endswith(".zip") or zipfile.is_zipfile(io.BytesIO(data)):
+            try:
+                with zipfile.ZipFile(io.BytesIO(data)) as archive:
+                    for member in archive.infolist():
+                        base = posixpath.basename(member.filename)
+                        if member.is_dir() or member.filename.startswith("__MACOSX/") or base.startswith("."):
+                            continue
+                        add(member.filename, lambda member=member: archive.read(member), member.file_size)
+            except zipfile.BadZipFile as e:
+                raise ValueError(f"Could not read archive '{filename}': {e}") from e
+        else:
+            add(os.path.basename(filename or "image"), lambda data=data: data, len(data))
+
+    if not images:
+        raise ValueError("Batch contains no images")
+    return images, skipped
+
+
+def submit_batch(store: JobStore, images: Sequence[Tuple[str, bytes]], options: dict,
+                 skipped: Sequence[dict] = (), callback_url: str = None, base_url: str = None) -> Batch:
+    """
+    Creates one job per image and queues them as a chord that builds the result archive.
+
+    Args:
+        store (JobStore): The job store.
+        images (Sequence[tuple]): (name, contents) from `unpack_uploads`.
+        options (dict): RemovalOptions as a dict, applied to every image.
+        skipped (Sequence[dict], optional): Manifest entries of files that were not processed.
+        callback_url (str, optional): Webhook notified once the result archive is built (or fails).
+        base_url (str, optional): The API's URL, used in the webhook payload.
+
+    Returns:
+        Batch: The new batch.
+    """
+    # Imported here: the task module imports this one.
+    from watermark_remover.tasks.processing import build_batch_archive_task, remove_watermark_task
+
+    jobs = [store.create(data, name, options) for name, data in images]
+    batch = store.create_batch([job.id for job in jobs], [name for name, _ in images], callback_url=callback_url,
+                               base_url=base_url)
+    if skipped:
+        batch = store.update_batch(batch.id, files=batch.files + list(skipped))
+    chord(remove_watermark_task.s(job.id) for job in jobs)(build_batch_archive_task.s(batch.id))
+    return store.get_batch(batch.id)
+
+
+def batch_progress(store: JobStore, batch: Batch) -> dict:
+    """The batch's state with every file's job status and the overall progress."""
+    files, progress = [], []
+    for entry in batch.files:
+        if "job_id" not in entry:
+            files.append(dict(entry))
+            continue
+        try:
+            job = store.get(entry["job_id"])
+        except FileNotFoundError:
+            files.append({**entry, "status": "failed", "error": "Job no longer exists"})
+            progress.append(1.0)
+            continue
+        files.append({**entry, "status": job.status, "progress": job.progress, "error": job.error})
+        progress.append(1.0 if job.finished else job.progress)
+    state = batch.to_dict()
+    state.update(files=files, progress=round(sum(progress) / max(len(progress), 1), 3))
+    return state
+
+
+def build_result_archive(store: JobStore, batch: Batch) -> Tuple[bytes, dict]:
+    """
+    Packs the results of a batch's jobs into a ZIP with a JSON manifest.
+
+    Returns:
+        tuple: (ZIP bytes, manifest). Results keep their upload names; the manifest
+            (`manifest.json` in the archive) lists every file with its status and error.
+    """
+    buffer = io.BytesIO()
+    entries = []
+    with zipfile.ZipFile(buffer, "w", compression=zipfile.ZIP_DEFLATED) as archive:
+        for entry in batch.files:
+            if "job_id" not in entry:
+                entries.append(dict(entry))
+                continue
+            record = {"filename": entry["filename"], "job_id": entry["job_id"], "status": "failed", "error": None}
+            try:
+                job = store.get(entry["job_id"])
+                record.update(status=job.status, error=job.error, headers=job.headers)
+                content = store.read(job.id, RESULT) if job.status == "succeeded" else None
+            except FileNotFoundError:
+                content = None
+                record["error"] = "Job no longer exists"
+            if content is not None:
+                # Images are already compressed; storing them avoids a pointless second pass.
+                archive.writestr(entry["filename"], content, compress_type=zipfile.ZIP_STORED)
+                record["output"] = entry["filename"]
+            elif record["status"] == "succeeded":
+                record.update(status="failed", error="Result missing")
+            entries.append(record)
+
+        manifest = {
+            "batch_id": batch.id,
+            "created_at": batch.created_at,
+            "succeeded": sum(record["status"] == "succeeded" for record in entries),
+            "failed": sum(record["status"] == "failed" for record in entries),
+            "skipped": sum(record["status"] == "skipped" for record in entries),
+            "files": entries,
+        }
+        archive.writestr(MANIFEST, json.dumps(manifest, indent=2))
+    return buffer.getvalue(), manifest
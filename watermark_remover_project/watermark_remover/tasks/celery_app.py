@@ -0,0 +1,43 @@
+"""
+Celery application for background processing.
+
+Start a worker from the project root with:
+
+    celery -A watermark_remover.tasks.celery_app worker --loglevel=info
+
+The broker is WATERMARK_REMOVER_CELERY_BROKER_URL (default a local Redis).
+Job state and artifacts live in the job store (watermark_remover.tasks.jobs);
+the result backend (WATERMARK_REMOVER_CELERY_RESULT_BACKEND) is only needed
+to join the per-image tasks of a batch (a chord). With
+WATERMARK_REMOVER_CELERY_EAGER=1 tasks run synchronously in the calling
+process, without a broker.
+
+Expired jobs are deleted by a periodic task; run the scheduler with
+
+    celery -A watermark_remover.tasks.celery_app beat --loglevel=info
+"""
+
+from celery import Celery
+
+from watermark_remover.config import settings
+
+celery_app = Celery(
+    "watermark_remover",
+    broker=settings.celery_broker_url,
+    backend=settings.celery_result_backend,
+    include=["watermark_remover.tasks.processing", "watermark_remover.tasks.webhooks",
+             "watermark_remover.tasks.retention"],
+)
+celery_app.conf.update(
+    task_serializer="json",
+    accept_content=["json"],
+    result_serializer="json",
+    task_always_eager=settings.celery_eager,
+    # Jobs are long and CPU-bound: take one at a time and only acknowledge finished ones.
+    worker_prefetch_multiplier=1,
+    task_acks_late=True,
+)
+if settings.cleanup_interval > 0:
+    celery_app.conf.beat_schedule = {
+        "cleanup-expired-jobs": {"task": "watermark_remover.cleanup", "schedule": float(settings.cleanup_interval)},
+    }
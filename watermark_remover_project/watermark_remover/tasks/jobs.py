@@ -0,0 +1,228 @@
+"""
+Job store shared by the API and the Celery workers.
+
+Each job is a folder in the configured storage (watermark_remover.storage:
+WATERMARK_REMOVER_JOBS_DIR, or an S3-compatible bucket) named by its id, with
+the upload (`input`), an optional mask (`mask`), the output (`result`) and
+the job's state in `job.json`. The API creates jobs and reads their state;
+the worker processing a job is the only writer afterwards.
+
+A batch groups the jobs of one multi-image upload. Its folder holds the
+state in `batch.json` and, once every job has finished, the ZIP of results
+(`result`).
+
+Jobs and batches submitted with a callback URL also keep the log of their
+webhook deliveries in `deliveries.json` (watermark_remover.tasks.webhooks).
+"""
+
+import json
+import re
+import time
+import uuid
+from dataclasses import asdict, dataclass, field, fields
+from typing import Dict, List, Optional
+
+from watermark_remover.storage import Storage, get_storage
+
+JOB_STATUSES = ("queued", "running", "succeeded", "failed")
+
+INPUT = "input"
+MASK = "mask"
+RESULT = "result"
+METADATA = "job.json"
+BATCH_METADATA = "batch.json"
+DELIVERIES = "deliveries.json"
+
+# A batch is "running" until its result archive has been built.
+BATCH_STATUSES = ("running", "succeeded", "failed")
+
+_JOB_ID = re.compile(r"^[0-9a-f]{32}$")
+
+
+def is_record_id(value: str) -> bool:
+    """Whether a string has the form of a job or batch id."""
+    return bool(_JOB_ID.match(value or ""))
+
+
+@dataclass
+class Job:
+    id: str
+    # One of JOB_STATUSES.
+    status: str = "queued"
+    # Completed fraction in [0, 1] and the current processing stage.
+    progress: float = 0.0
+    stage: str = "queued"
+    filename: str = None
+    mask_filename: str = None
+    # RemovalOptions as a dict (watermark_remover.core.pipeline).
+    options: dict = field(default_factory=dict)
+    # Set on success: media type of the result and headers describing the mask.
+    media_type: str = None
+    headers: Dict[str, str] = field(default_factory=dict)
+    error: str = None
+    # Webhook notified when the job finishes, and the API's URL the notification points back to.
+    callback_url: str = None
+    base_url: str = None
+    created_at: float = 0.0
+    updated_at: float = 0.0
+
+    @property
+    def finished(self) -> bool:
+        return self.status in ("succeeded", "failed")
+
+    def to_dict(self) -> dict:
+        return asdict(self)
+
+
+@dataclass
+class Batch:
+    id: str
+    # One of BATCH_STATUSES.
+    status: str = "running"
+    # {"filename": name in the upload and the result archive, "job_id": id} per image.
+    files: List[Dict[str, str]] = field(default_factory=list)
+    error: str = None
+    callback_url: str = None
+    base_url: str = None
+    created_at: float = 0.0
+    updated_at: float = 0.0
+
+    def to_dict(self) -> dict:
+        return asdict(self)
+
+
+class JobStore:
+    """Jobs and batches stored as folders (key prefixes) in a Storage."""
+
+    def __init__(self, storage: Storage):
+        self.storage = storage
+
+    def _key(self, job_id: str, name: str = "") -> str:
+        if not is_record_id(job_id):
+            raise FileNotFoundError(f"Job not found: {job_id}")
+        return f"{job_id}/{name}"
+
+    def _write_metadata(self, record, name: str = METADATA) -> None:
+        self.storage.write(self._key(record.id, name), json.dumps(record.to_dict()).encode("utf-8"))
+
+    def _read_metadata(self, record_id: str, record_type, name: str):
+        data = self.storage.read(self._key(record_id, name))
+        if data is None:
+            raise FileNotFoundError(f"{record_type.__name__} not found: {record_id}")
+        values = json.loads(data)
+        known = {record_field.name for record_field in fields(record_type)}
+        return record_type(**{key: value for key, value in values.items() if key in known})
+
+    def create(self, image_bytes: bytes, filename: str, options: dict = None, mask_bytes: bytes = None,
+               mask_filename: str = None, callback_url: str = None, base_url: str = None) -> Job:
+        """
+        Stores an upload as a new queued job.
+
+        Returns:
+            Job: The new job.
+        """
+        now = time.time()
+        job = Job(id=uuid.uuid4().hex, filename=filename, mask_filename=mask_filename if mask_bytes else None,
+                  options=dict(options or {}), callback_url=callback_url, base_url=base_url,
+                  created_at=now, updated_at=now)
+        self.write(job.id, INPUT, image_bytes)
+        if mask_bytes:
+            self.write(job.id, MASK, mask_bytes)
+        self._write_metadata(job)
+        return job
+
+    def get(self, job_id: str) -> Job:
+        """
+        Raises:
+            FileNotFoundError: If the job does not exist.
+        """
+        return self._read_metadata(job_id, Job, METADATA)
+
+    def update(self, job_id: str, **changes) -> Job:
+        """
+        Changes fields of a job's state.
+
+        Raises:
+            FileNotFoundError: If the job does not exist.
+            ValueError: If a status is unknown.
+        """
+        if "status" in changes and changes["status"] not in JOB_STATUSES:
+            raise ValueError(f"Unknown job status '{changes['status']}'")
+        job = self.get(job_id)
+        for key, value in changes.items():
+            setattr(job, key, value)
+        job.updated_at = time.time()
+        self._write_metadata(job)
+        return job
+
+    def create_batch(self, job_ids: List[str], filenames: List[str], callback_url: str = None,
+                     base_url: str = None) -> Batch:
+        """
+        Records the jobs of a multi-image upload as a new batch.
+
+        Returns:
+            Batch: The new batch.
+        """
+        now = time.time()
+        batch = Batch(id=uuid.uuid4().hex, callback_url=callback_url, base_url=base_url, created_at=now,
+                      updated_at=now,
+                      files=[{"filename": name, "job_id": job_id} for name, job_id in zip(filenames, job_ids)])
+        self._write_metadata(batch, BATCH_METADATA)
+        return batch
+
+    def get_batch(self, batch_id: str) -> Batch:
+        """
+        Raises:
+            FileNotFoundError: If the batch does not exist.
+        """
+        return self._read_metadata(batch_id, Batch, BATCH_METADATA)
+
+    def update_batch(self, batch_id: str, **changes) -> Batch:
+        """
+        Changes fields of a batch's state.
+
+        Raises:
+            FileNotFoundError: If the batch does not exist.
+            ValueError: If a status is unknown.
+        """
+        if "status" in changes and changes["status"] not in BATCH_STATUSES:
+            raise ValueError(f"Unknown batch status '{changes['status']}'")
+        batch = self.get_batch(batch_id)
+        for key, value in changes.items():
+            setattr(batch, key, value)
+        batch.updated_at = time.time()
+        self._write_metadata(batch, BATCH_METADATA)
+        return batch
+
+    def read(self, job_id: str, name: str) -> Optional[bytes]:
+        """Contents of a job or batch artifact (INPUT, MASK or RESULT), or None if it does not exist."""
+        return self.storage.read(self._key(job_id, name))
+
+    def write(self, job_id: str, name: str, data: bytes) -> None:
+        self.storage.write(self._key(job_id, name), data)
+
+    def append_delivery(self, record_id: str, entry: dict) -> None:
+        """Adds an attempt to the webhook delivery log of a job or batch."""
+        log = self.deliveries(record_id)
+        log.append(entry)
+        self.write(record_id, DELIVERIES, json.dumps(log).encode("utf-8"))
+
+    def deliveries(self, record_id: str) -> List[dict]:
+        """The webhook delivery attempts of a job or batch, oldest first."""
+        data = self.read(record_id, DELIVERIES)
+        return json.loads(data) if data else []
+
+    def delete(self, job_id: str) -> None:
+        """
+        Deletes a job or batch and its artifacts.
+
+        Raises:
+            FileNotFoundError: If the job does not exist.
+        """
+        if not self.storage.delete(self._key(job_id)):
+            raise FileNotFoundError(f"Job not found: {job_id}")
+
+
+def get_job_store() -> JobStore:
+    """The job store in the configured storage (WATERMARK_REMOVER_STORAGE_BACKEND)."""
+    return JobStore(get_storage())
@@ -0,0 +1,81 @@
+"""
+Celery tasks wrapping the core engine.
+"""
+
+from watermark_remover.core.pipeline import RemovalOptions, remove_watermark_bytes
+from watermark_remover.tasks.batches import build_result_archive
+from watermark_remover.tasks.celery_app import celery_app
+from watermark_remover.tasks.jobs import INPUT, MASK, RESULT, get_job_store
+from watermark_remover.tasks.webhooks import notify
+
+
+@celery_app.task(name="watermark_remover.remove_watermark")
+def remove_watermark_task(job_id: str) -> dict:
+    """
+    Processes a queued job: reads its upload from the job store, removes the watermark
+    and stores the result, reporting progress in the job's state along the way.
+    Failures are recorded in the job's state rather than raised, so the other images
+    of a batch (a chord) still get packed. Either way the job's webhook, if any, is queued.
+
+    Args:
+        job_id (str): Id of a job created with `JobStore.create`.
+
+    Returns:
+        dict: The finished job's state; its status is "succeeded" or "failed" (also when the job
+            no longer exists).
+    """
+    store = get_job_store()
+    try:
+        job = store.update(job_id, status="running", stage="started", progress=0.0)
+    except FileNotFoundError as e:
+        # Deleted or expired while queued. Returning rather than raising lets a batch's chord callback run.
+        print(f"Job {job_id} skipped: {e}")
+        return {"id": job_id, "status": "failed", "error": f"{type(e).__name__}: {e}"}
+    print(f"Processing job {job_id} ({job.filename})")
+    try:
+        result = remove_watermark_bytes(
+            store.read(job_id, INPUT),
+            job.filename,
+            RemovalOptions.from_dict(job.options),
+            mask_bytes=store.read(job_id, MASK),
+            mask_filename=job.mask_filename,
+            progress=lambda fraction, stage: store.update(job_id, progress=round(fraction, 3), stage=stage),
+        )
+        store.write(job_id, RESULT, result.content)
+    except Exception as e:
+        print(f"Job {job_id} failed: {e}")
+        job = store.update(job_id, status="failed", stage="failed", error=f"{type(e).__name__}: {e}")
+    else:
+        job = store.update(job_id, status="succeeded", stage="done", progress=1.0, media_type=result.media_type,
+                           headers=result.headers)
+    notify(job_id, "job", job.callback_url)
+    return job.to_dict()
+
+
+@celery_app.task(name="watermark_remover.build_batch_archive")
+def build_batch_archive_task(job_states: list, batch_id: str) -> dict:
+    """
+    Chord callback of a batch: packs the results of its jobs and a manifest into a ZIP,
+    then queues the batch's webhook, if any.
+
+    Args:
+        job_states (list): States returned by the batch's `remove_watermark_task`s (unused;
+            the job store is authoritative).
+        batch_id (str): Id of a batch created with `JobStore.create_batch`.
+
+    Returns:
+        dict: The manifest.
+    """
+    store = get_job_store()
+    try:
+        content, manifest = build_result_archive(store, store.get_batch(batch_id))
+        store.write(batch_id, RESULT, content)
+    except Exception as e:
+        print(f"Batch {batch_id} failed: {e}")
+        batch = store.update_batch(batch_id, status="failed", error=f"{type(e).__name__}: {e}")
+        notify(batch_id, "batch", batch.callback_url)
+        raise
+    batch = store.update_batch(batch_id, status="succeeded")
+    notify(batch_id, "batch", batch.callback_url)
+    print(f"Batch {batch_id}: {manifest['succeeded']} succeeded, {manifest['failed']} failed")
+    return manifest
@@ -0,0 +1,175 @@
+"""
+Retention of job and batch artifacts.
+
+Finished jobs and batches expire WATERMARK_REMOVER_RESULT_TTL seconds after
+they finish. Jobs still queued or running, batches still running and folders
+without a readable state (interrupted uploads) are given up on
+WATERMARK_REMOVER_JOB_MAX_AGE seconds after they were created. The jobs of a
+batch are kept, and deleted, with the batch.
+
+Celery beat runs `cleanup_task` every WATERMARK_REMOVER_CLEANUP_INTERVAL
+seconds (see celery_app); start it next to the workers with
+
+    celery -A watermark_remover.tasks.celery_app beat --loglevel=info
+
+Each run records what it deleted and how many bytes it reclaimed, together
+with running totals, in `cleanup.json` at the root of the storage.
+"""
+
+import json
+import time
+from typing import Optional, Tuple
+
+from watermark_remover.config import settings
+from watermark_remover.tasks.celery_app import celery_app
+from watermark_remover.tasks.jobs import BATCH_METADATA, METADATA, Batch, JobStore, get_job_store, is_record_id
+
+CLEANUP_STATS = "cleanup.json"
+
+# Counters summed over all runs in the statistics.
+_TOTALS = ("jobs_deleted", "batches_deleted", "orphans_deleted", "files_deleted", "bytes_reclaimed")
+
+
+def expires_at(record) -> Optional[float]:
+    """Time (Unix seconds) after which a job or batch is deleted, or None if it is kept indefinitely."""
+    if record.status in ("succeeded", "failed"):
+        return record.updated_at + settings.result_ttl if settings.result_ttl > 0 else None
+    return record.created_at + settings.job_max_age if settings.job_max_age > 0 else None
+
+
+def is_expired(record, now: float = None) -> bool:
+    expiry = expires_at(record)
+    return expiry is not None and (now or time.time()) >= expiry
+
+
+def delete_record(store: JobStore, record_id: str) -> Tuple[int, int]:
+    """
+    Deletes a job or batch folder, measuring it first.
+
+    Returns:
+        tuple: (files deleted, bytes reclaimed).
+    """
+    size = sum(item.size for item in store.storage.list(f"{record_id}/"))
+    return store.storage.delete(f"{record_id}/"), size
+
+
+def delete_batch(store: JobStore, batch: Batch) -> Tuple[int, int]:
+    """Deletes a batch and its jobs. Returns (files deleted, bytes reclaimed)."""
+    files, size = delete_record(store, batch.id)
+    for entry in batch.files:
+        if "job_id" in entry:
+            job_files, job_size = delete_record(store, entry["job_id"])
+            files, size = files + job_files, size + job_size
+    return files, size
+
+
+def cleanup(store: JobStore, now: float = None) -> dict:
+    """
+    Deletes expired jobs, batches and abandoned folders.
+
+    Args:
+        store (JobStore): The job store.
+        now (float, optional): Current time (Unix seconds). Defaults to the clock.
+
+    Returns:
+        dict: Metrics of the run: jobs, batches and abandoned folders deleted, files deleted,
+            bytes reclaimed, folders and bytes kept, and duration in seconds.
+    """
+    started = time.time()
+    now = now or started
+    # Folder id -> names, total size and newest modification, from one listing.
+    folders = {}
+    for item in store.storage.list():
+        record_id, _, name = item.key.partition("/")
+        if not name or not is_record_id(record_id):
+            continue
+        folder = folders.setdefault(record_id, {"names": set(), "size": 0, "modified": 0.0})
+        folder["names"].add(name)
+        folder["size"] += item.size
+        folder["modified"] = max(folder["modified"], item.modified)
+
+    metrics = {name: 0 for name in _TOTALS}
+    deleted, kept_jobs = set(), set()
+
+    def remove(record_id, kind):
+        files, size = delete_record(store, record_id)
+        deleted.add(record_id)
+        metrics[kind] += 1
+        metrics["files_deleted"] += files
+        metrics["bytes_reclaimed"] += size
+
+    # Batches first: their jobs live as long as they do.
+    for record_id, folder in folders.items():
+        if BATCH_METADATA not in folder["names"]:
+            continue
+        try:
+            batch = store.get_batch(record_id)
+        except (FileNotFoundError, ValueError, TypeError):
+            continue
+        job_ids = [entry["job_id"] for entry in batch.files if "job_id" in entry]
+        if is_expired(batch, now):
+            remove(record_id, "batches_deleted")
+            for job_id in job_ids:
+                if job_id in folders and job_id not in deleted:
+                    remove(job_id, "jobs_deleted")
+        else:
+            kept_jobs.update(job_ids)
+
+    for record_id, folder in folders.items():
+        if record_id in deleted or record_id in kept_jobs or BATCH_METADATA in folder["names"]:
+            continue
+        try:
+            job = store.get(record_id) if METADATA in folder["names"] else None
+        except (ValueError, TypeError):
+            # Unreadable state: treated as abandoned.
+            job = None
+        except FileNotFoundError:
+            # Deleted since the listing.
+            continue
+        if job is not None:
+            if is_expired(job, now):
+                remove(record_id, "jobs_deleted")
+        elif settings.job_max_age > 0 and folder["modified"] + settings.job_max_age <= now:
+            remove(record_id, "orphans_deleted")
+
+    metrics.update(
+        folders_kept=len(folders) - len(deleted),
+        bytes_kept=sum(folder["size"] for record_id, folder in folders.items() if record_id not in deleted),
+        started_at=started,
+        duration=round(time.time() - started, 3),
+    )
+    return metrics
+
+
+def cleanup_stats(store: JobStore) -> dict:
+    """The last cleanup run's metrics ("last_run") and totals over all runs ("totals"); empty before the first run."""
+    data = store.storage.read(CLEANUP_STATS)
+    return json.loads(data) if data else {}
+
+
+def _record_stats(store: JobStore, metrics: dict) -> dict:
+    stats = cleanup_stats(store)
+    totals = stats.get("totals", {})
+    totals["runs"] = totals.get("runs", 0) + 1
+    for name in _TOTALS:
+        totals[name] = totals.get(name, 0) + metrics[name]
+    stats = {"last_run": metrics, "totals": totals}
+    store.storage.write(CLEANUP_STATS, json.dumps(stats).encode("utf-8"))
+    return stats
+
+
+@celery_app.task(name="watermark_remover.cleanup")
+def cleanup_task() -> dict:
+    """
+    Periodic cleanup (Celery beat): deletes expired jobs and batches and records the metrics.
+
+    Returns:
+        dict: Metrics of the run (see `cleanup`).
+    """
+    store = get_job_store()
+    metrics = cleanup(store)
+    _record_stats(store, metrics)
+    print(f"Cleanup: deleted {metrics['jobs_deleted']} jobs, {metrics['batches_deleted']} batches and "
+          f"{metrics['orphans_deleted']} abandoned folders, reclaiming {metrics['bytes_reclaimed'] / 1024 / 1024:.1f} MB "
+          f"in {metrics['duration']:g}s")
+    return metrics
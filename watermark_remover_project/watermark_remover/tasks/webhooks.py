@@ -0,0 +1,236 @@
+"""
+Webhook callbacks on job and batch completion.
+
+When a job or batch submitted with a `callback_url` finishes (or fails), a
+JSON payload with its status and result location is POSTed to that URL. The
+body is signed with HMAC-SHA256 using WATERMARK_REMOVER_WEBHOOK_SECRET:
+
+    X-Watermark-Remover-Timestamp: <unix seconds>
+    X-Watermark-Remover-Signature: sha256=<hex HMAC of "<timestamp>.<body>">
+
+Callback hosts must resolve to public addresses, so callbacks cannot probe
+the server's own network (loopback, private ranges, cloud metadata at
+169.254.169.254); internal receivers are allowed by name with
+WATERMARK_REMOVER_WEBHOOK_ALLOWED_HOSTS.
+
+Receivers should recompute the signature (see `verify_signature`) and reject
+old timestamps. Deliveries that fail (network error, timeout or a non-2xx
+status; redirects are not followed) are retried with exponential backoff, and
+every attempt is recorded in the record's delivery log.
+"""
+
+import hashlib
+import hmac
+import ipaddress
+import json
+import socket
+import time
+import urllib.error
+import urllib.request
+import uuid
+from typing import Optional
+from urllib.parse import urlparse
+
+from watermark_remover.config import settings
+from watermark_remover.tasks.batches import batch_progress
+from watermark_remover.tasks.celery_app import celery_app
+from watermark_remover.tasks.jobs import JobStore, get_job_store
+
+SIGNATURE_HEADER = "X-Watermark-Remover-Signature"
+TIMESTAMP_HEADER = "X-Watermark-Remover-Timestamp"
+DELIVERY_HEADER = "X-Watermark-Remover-Delivery"
+EVENT_HEADER = "X-Watermark-Remover-Event"
+
+# Longest wait between two attempts, in seconds.
+MAX_BACKOFF = 3600
+
+
+def _is_public(address: str) -> bool:
+    ip = ipaddress.ip_address(address.split("%", 1)[0])
+    if ip.version == 6 and ip.ipv4_mapped:
+        ip = ip.ipv4_mapped
+    # is_global also excludes shared address space (100.64.0.0/10, carrier-grade NAT), which is not
+    # private; multicast ranges count as global but are never a valid webhook receiver.
+    return ip.is_global and not ip.is_multicast
+
+
+def check_callback_host(url: str) -> None:
+    """
+    Refuses callback hosts with an address that is not globally routable (private, shared,
+    loopback, link-local, reserved, ...) or multicast, unless the host is listed in
+    WATERMARK_REMOVER_WEBHOOK_ALLOWED_HOSTS.
+
+    Raises:
+        ValueError: If the host cannot be resolved or any of its addresses is not public.
+    """
+    host = (urlparse(url).hostname or "").lower()
+    allowed = {name.strip().lower() for name in (settings.webhook_allowed_hosts or "").split(",") if name.strip()}
+    if host in allowed:
+        return
+    try:
+        addresses = {info[4][0] for info in socket.getaddrinfo(host, None, proto=socket.IPPROTO_TCP)}
+    except (socket.gaierror, UnicodeError):
+        raise ValueError(f"callback_url host '{host}' could not be resolved") from None
+    blocked = sorted(address for address in addresses if not _is_public(address))
+    if blocked:
+        raise ValueError(f"callback_url host '{host}' resolves to a non-public address ({', '.join(blocked)}); "
+                         f"allow it with WATERMARK_REMOVER_WEBHOOK_ALLOWED_HOSTS")
+
+
+def validate_callback_url(url: str) -> str:
+    """
+    Raises:
+        ValueError: If the URL is not an absolute http(s) URL, its host is not public (see
+            `check_callback_host`) or no signing secret is configured.
+    """
+    parsed = urlparse(url or "")
+    if parsed.scheme not in ("http", "https") or not parsed.netloc:
+        raise ValueError(f"callback_url must be an absolute http(s) URL, got '{url}'")
+    if not settings.webhook_secret:
+        raise ValueError("Webhooks are not configured on this server (WATERMARK_REMOVER_WEBHOOK_SECRET is not set)")
+    check_callback_host(url)
+    return url
+
+
+def sign(body: bytes, timestamp: str, secret: str) -> str:
+    """Signature header value for a payload: "sha256=" and the hex HMAC of "<timestamp>.<body>"."""
+    digest = hmac.new(secret.encode("utf-8"), timestamp.encode("ascii") + b"." + body, hashlib.sha256)
+    return "sha256=" + digest.hexdigest()
+
+
+def verify_signature(body: bytes, timestamp: str, signature: str, secret: str, max_age: float = 300) -> bool:
+    """
+    Checks a received webhook: the signature must match and the timestamp be at most max_age seconds old.
+    """
+    try:
+        age = time.time() - float(timestamp)
+    except (TypeError, ValueError):
+        return False
+    return abs(age) <= max_age and hmac.compare_digest(sign(body, timestamp, secret), signature or "")
+
+
+def backoff_delay(retries: int) -> float:
+    """Seconds to wait before the next attempt after `retries` failed retries: doubling, capped."""
+    return min(settings.webhook_backoff * 2 ** retries, MAX_BACKOFF)
+
+
+def webhook_payload(store: JobStore, record_id: str, kind: str = "job") -> dict:
+    """
+    The JSON payload describing a finished job or batch.
+
+    Raises:
+        FileNotFoundError: If the job or batch does not exist.
+    """
+    if kind == "job":
+        record = store.get(record_id)
+        path = f"/v1/jobs/{record_id}"
+        details = {"filename": record.filename, "headers": record.headers}
+    else:
+        record = store.get_batch(record_id)
+        path = f"/v1/batches/{record_id}"
+        files = batch_progress(store, record)["files"]
+        details = {status: sum(entry.get("status") == status for entry in files)
+                   for status in ("succeeded", "failed", "skipped")}
+    base_url = (record.base_url or "").rstrip("/")
+    return {
+        "event": f"{kind}.{'succeeded' if record.status == 'succeeded' else 'failed'}",
+        "type": kind,
+        "id": record_id,
+        "status": record.status,
+        "error": record.error,
+        "status_url": base_url + path,
+        "result_url": base_url + path + "/result" if record.status == "succeeded" else None,
+        "created_at": record.created_at,
+        "finished_at": record.updated_at,
+        **details,
+    }
+
+
+class _NoRedirect(urllib.request.HTTPRedirectHandler):
+    def redirect_request(self, *args, **kwargs):
+        return None
+
+
+def post_webhook(url: str, payload: dict, secret: str, delivery_id: str, timeout: float = None) -> dict:
+    """
+    POSTs a signed payload once.
+
+    Returns:
+        dict: The delivery log entry: delivery id, event, url, HTTP status (None without a response),
+            error, whether it was delivered (2xx), duration and time sent.
+    """
+    body = json.dumps(payload, sort_keys=True).encode("utf-8")
+    timestamp = str(int(time.time()))
+    request = urllib.request.Request(url, data=body, method="POST", headers={
+        "Content-Type": "application/json",
+        "User-Agent": "watermark-remover-webhook",
+        SIGNATURE_HEADER: sign(body, timestamp, secret),
+        TIMESTAMP_HEADER: timestamp,
+        DELIVERY_HEADER: delivery_id,
+        EVENT_HEADER: payload["event"],
+    })
+    status_code, error = None, None
+    started = time.time()
+    try:
+        # Checked again on every attempt: the host may resolve differently than at submission.
+        check_callback_host(url)
+        with urllib.request.build_opener(_NoRedirect).open(request, timeout=timeout or settings.webhook_timeout) as response:
+            status_code = response.status
+    except urllib.error.HTTPError as e:
+        status_code, error = e.code, f"HTTP {e.code}"
+    except (urllib.error.URLError, OSError) as e:
+        error = str(getattr(e, "reason", e))
+    except ValueError as e:
+        error = str(e)
+    return {
+        "delivery_id": delivery_id,
+        "event": payload["event"],
+        "url": url,
+        "status_code": status_code,
+        "error": error,
+        "delivered": status_code is not None and 200 <= status_code < 300,
+        "duration_ms": round((time.time() - started) * 1000),
+        "sent_at": started,
+    }
+
+
+@celery_app.task(bind=True, name="watermark_remover.deliver_webhook", max_retries=None)
+def deliver_webhook_task(self, record_id: str, kind: str = "job", delivery_id: str = None) -> dict:
+    """
+    Delivers the completion webhook of a job or batch, retrying with exponential backoff
+    up to WATERMARK_REMOVER_WEBHOOK_MAX_RETRIES times. Every attempt is appended to the
+    record's delivery log.
+
+    Args:
+        record_id (str): Job or batch id.
+        kind (str, optional): "job" or "batch". Defaults to "job".
+        delivery_id (str, optional): Id shared by all attempts, so receivers can deduplicate.
+
+    Returns:
+        dict: The log entry of the last attempt.
+    """
+    store = get_job_store()
+    record = store.get(record_id) if kind == "job" else store.get_batch(record_id)
+    entry = post_webhook(record.callback_url, webhook_payload(store, record_id, kind), settings.webhook_secret,
+                         delivery_id or uuid.uuid4().hex)
+    entry["attempt"] = self.request.retries + 1
+    store.append_delivery(record_id, entry)
+    if not entry["delivered"]:
+        if self.request.retries < settings.webhook_max_retries:
+            delay = backoff_delay(self.request.retries)
+            print(f"Webhook for {kind} {record_id} failed ({entry['error']}); retrying in {delay:g}s")
+            raise self.retry(countdown=delay, kwargs={"record_id": record_id, "kind": kind,
+                                                      "delivery_id": entry["delivery_id"]}, args=())
+        print(f"Webhook for {kind} {record_id} failed after {entry['attempt']} attempts; giving up")
+    return entry
+
+
+def notify(record_id: str, kind: str, callback_url: Optional[str]) -> None:
+    """Queues the completion webhook of a job or batch if it has a callback URL."""
+    if not callback_url:
+        return
+    try:
+        deliver_webhook_task.delay(record_id, kind, uuid.uuid4().hex)
+    except Exception as e:
+        # A broken broker must not turn a finished job into a failed one.
+        print(f"Could not queue webhook for {kind} {record_id}: {e}")
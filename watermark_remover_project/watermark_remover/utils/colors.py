@@ -0,0 +1,67 @@
+"""
+Colour parsing and perceptual colour distances (CIE Lab).
+"""
+
+from typing import Sequence, Tuple, Union
+
+import cv2
+import numpy as np
+
+# Typical overlay colours; anything else can be given as "#rrggbb".
+COLOR_PRESETS = {
+    "orange": (255, 140, 0),
+    "yellow": (255, 220, 0),
+    "red": (255, 40, 40),
+    "white": (255, 255, 255),
+    "black": (0, 0, 0),
+}
+
+ColorInput = Union[str, Sequence[int]]
+
+
+def parse_color(color: ColorInput) -> Tuple[int, int, int]:
+    """
+    Returns an RGB tuple for a preset name (see COLOR_PRESETS), "#rrggbb" or an (r, g, b) sequence.
+
+    Raises:
+        ValueError: If the colour cannot be parsed.
+    """
+    if isinstance(color, str):
+        name = color.strip().lower()
+        if name in COLOR_PRESETS:
+            return COLOR_PRESETS[name]
+        if name.startswith("#") and len(name) == 7:
+            try:
+                return tuple(int(name[i:i + 2], 16) for i in (1, 3, 5))
+            except ValueError:
+                pass
+        raise ValueError(f"Unknown colour '{color}'. Use #rrggbb or one of: {', '.join(COLOR_PRESETS)}")
+    if len(color) != 3:
+        raise ValueError(f"Colour must have 3 components, got {color}")
+    try:
+        components = tuple(int(component) for component in color)
+    except (TypeError, ValueError):
+        raise ValueError(f"Colour components must be integers, got {color}") from None
+    # Out-of-range values would silently wrap around in uint8.
+    if not all(0 <= component <= 255 for component in components):
+        raise ValueError(f"Colour components must be in 0-255, got {color}")
+    return components
+
+
+def to_lab(rgb: np.ndarray) -> np.ndarray:
+    """CIE Lab of (..., 3) uint8 RGB values as float32, with L in 0-100 and a/b centred on 0."""
+    lab = cv2.cvtColor(rgb.reshape(-1, 1, 3).astype(np.uint8), cv2.COLOR_RGB2LAB).astype(np.float32)
+    # OpenCV stores 8-bit L scaled to 0-255 and a/b offset by 128.
+    lab = (lab[:, 0] - np.array([0, 128, 128], dtype=np.float32)) * np.array([100 / 255, 1, 1], dtype=np.float32)
+    return lab.reshape(rgb.shape)
+
+
+def lab_distance(rgb: np.ndarray, color: Tuple[int, int, int], lab: np.ndarray = None) -> np.ndarray:
+    """
+    Euclidean distance in CIE Lab between (..., 3) uint8 RGB values and one colour.
+
+    `lab` can pass `to_lab(rgb)` when comparing the same pixels with several colours.
+    """
+    lab = to_lab(rgb) if lab is None else lab
+    target = to_lab(np.uint8([[color]]))[0, 0]
+    return np.linalg.norm(lab - target, axis=-1)
@@ -0,0 +1,235 @@
+"""
+Round-tripping images through the RGB-only inpainting engines.
+
+Engines work on 8-bit RGB, but inputs come as RGBA, grayscale, palette,
+CMYK or 16-bit images carrying ICC profiles and EXIF/XMP metadata. The
+helpers here extract the RGB (and alpha) planes for the engine, merge the
+result back into the original mode changing only masked pixels, and save
+with the original's colour profile, metadata and JPEG quantisation.
+"""
+
+import io
+import os
+from typing import Optional, Tuple, Union
+
+from PIL import Image, JpegImagePlugin
+from PIL.PngImagePlugin import PngInfo
+import numpy as np
+
+ImageInput = Union[Image.Image, np.ndarray, bytes]
+
+SIXTEEN_BIT_MODES = ("I;16", "I;16B", "I;16L", "I")
+ALPHA_MODES = ("RGBA", "LA")
+# TIFF tag holding XMP packets.
+TIFF_XMP_TAG = 700
+
+
+def open_image(image: ImageInput) -> Image.Image:
+    """
+    Returns a loaded PIL image for a PIL image, a numpy array or encoded file bytes.
+
+    Raises:
+        ValueError: If the input type is unsupported or the bytes are not a readable image.
+    """
+    if isinstance(image, Image.Image):
+        return image
+    if isinstance(image, np.ndarray):
+        return Image.fromarray(image)
+    if isinstance(image, (bytes, bytearray)):
+        try:
+            opened = Image.open(io.BytesIO(image))
+            opened.load()
+        except (OSError, Image.DecompressionBombError) as e:
+            raise ValueError(f"Could not decode image: {e}") from e
+        return opened
+    raise ValueError(f"Unsupported image type: {type(image).__name__}")
+
+
+def format_for_extension(filename: str, default: str = "PNG") -> str:
+    """PIL format name for a filename's extension, e.g. "photo.JPG" -> "JPEG"."""
+    extension = os.path.splitext(filename or "")[1].lower()
+    return Image.registered_extensions().get(extension, default)
+
+
+def has_alpha(image: Image.Image) -> bool:
+    return image.mode in ALPHA_MODES or (image.mode == "P" and "transparency" in image.info)
+
+
+def split_channels(image: Image.Image) -> Tuple[np.ndarray, Optional[np.ndarray]]:
+    """
+    Extracts the planes an engine works on.
+
+    Returns:
+        tuple: (rgb, alpha). rgb is a uint8 (H, W, 3) array; 16-bit images are scaled
+        down to 8 bits. alpha is a uint8 (H, W) array, or None if the image has no
+        transparency.
+    """
+    if image.mode in SIXTEEN_BIT_MODES:
+        data = np.asarray(image).astype(np.float64)
+        gray = np.clip(np.rint(data / 257), 0, 255).astype(np.uint8)
+        return np.repeat(gray[..., None], 3, axis=2), None
+
+    if has_alpha(image):
+        rgba = np.asarray(image.convert("RGBA"))
+        return np.ascontiguousarray(rgba[..., :3]), np.ascontiguousarray(rgba[..., 3])
+
+    return np.asarray(image.convert("RGB")), None
+
+
+def merge_channels(original: Image.Image, rgb: np.ndarray, mask: np.ndarray,
+                   alpha: Optional[np.ndarray] = None) -> Image.Image:
+    """
+    Writes the inpainted pixels back into an image of the original's mode.
+
+    Only pixels under `mask` change; everything else, including the palette of
+    "P" images and the full precision of 16-bit images, is kept.
+
+    Args:
+        original (PIL.Image.Image): The image as loaded.
+        rgb (np.ndarray): Engine output, uint8 (H, W, 3).
+        mask (np.ndarray): (H, W) mask; non-zero pixels were inpainted.
+        alpha (np.ndarray, optional): Alpha to store for images with transparency;
+            defaults to the original alpha.
+
+    Returns:
+        PIL.Image.Image: Image in original.mode with original.info preserved.
+    """
+    hole = mask > 0
+
+    if original.mode in SIXTEEN_BIT_MODES:
+        data = np.array(original)
+        gray = np.asarray(Image.fromarray(rgb).convert("L")).astype(np.float64) * 257
+        data[hole] = gray[hole].astype(data.dtype)
+        result = Image.fromarray(data)
+        result.info = dict(original.info)
+        return result
+
+    if original.mode == "P":
+        indices = np.array(original)
+        quantized = np.asarray(Image.fromarray(rgb).quantize(palette=original, dither=Image.Dither.NONE))
+        transparency = original.info.get("transparency")
+        if alpha is not None and isinstance(transparency, int):
+            quantized = np.where(alpha < 128, transparency, quantized)
+        indices[hole] = quantized[hole]
+        result = Image.frombytes("P", original.size, indices.astype(np.uint8).tobytes())
+        result.putpalette(original.getpalette())
+        result.info = dict(original.info)
+        return result
+
+    result = original.copy()
+    converted = Image.fromarray(rgb).convert(original.mode)
+    result.paste(converted, mask=Image.fromarray(hole.astype(np.uint8) * 255))
+    if original.mode in ("RGBA", "LA"):
+        # The paste above made masked pixels opaque; put the alpha plane back.
+        if alpha is None:
+            alpha = np.asarray(original.getchannel("A"))
+        result.putalpha(Image.fromarray(alpha))
+    return result
+
+
+def read_xmp(image: Image.Image) -> Optional[bytes]:
+    """Returns the raw XMP packet of an image, if any."""
+    xmp = image.info.get("xmp") or image.info.get("XML:com.adobe.xmp")
+    if xmp is None and hasattr(image, "tag_v2"):
+        xmp = image.tag_v2.get(TIFF_XMP_TAG)
+    if isinstance(xmp, str):
+        xmp = xmp.encode("utf-8")
+    return xmp
+
+
+def _prepare_for_save(image: Image.Image, image_format: str, source: Image.Image, keep_metadata: bool,
+                      jpeg_quality: int):
+    params = {}
+
+    icc_profile = source.info.get("icc_profile")
+    if icc_profile:
+        params["icc_profile"] = icc_profile
+
+    xmp = None
+    if keep_metadata:
+        exif = source.getexif()
+        if len(exif):
+            params["exif"] = exif.tobytes()
+        xmp = read_xmp(source)
+
+    if image_format == "JPEG":
+        if image.mode in SIXTEEN_BIT_MODES:
+            image = Image.fromarray(split_channels(image)[0][..., 0])
+        elif image.mode not in ("RGB", "L", "CMYK"):
+            # JPEG has no alpha or palette.
+            image = image.convert("L" if image.mode in ("LA", "1") else "RGB")
+        if jpeg_quality:
+            params["quality"] = jpeg_quality
+        elif source.format == "JPEG":
+            params["qtables"] = source.quantization
+            subsampling = JpegImagePlugin.get_sampling(source)
+            if subsampling != -1:
+                params["subsampling"] = subsampling
+        else:
+            params["quality"] = 95
+        if xmp:
+            params["xmp"] = xmp
+    elif image_format == "PNG":
+        if xmp:
+            png_info = PngInfo()
+            png_info.add_itxt("XML:com.adobe.xmp", xmp.decode("utf-8", errors="replace"))
+            params["pnginfo"] = png_info
+        if "transparency" in image.info:
+            params["transparency"] = image.info["transparency"]
+    elif image_format == "WEBP":
+        params["quality"] = jpeg_quality or 95
+        params["lossless"] = bool(source.info.get("lossless")) if source.format == "WEBP" else False
+        if xmp:
+            params["xmp"] = xmp
+    elif image_format == "TIFF":
+        if source.format == "TIFF" and source.info.get("compression"):
+            params["compression"] = source.info["compression"]
+        if xmp:
+            params["tiffinfo"] = {TIFF_XMP_TAG: xmp}
+
+    return image, params
+
+
+def save_image(image: Image.Image, output_path: str, source: Image.Image = None, keep_metadata: bool = True,
+               jpeg_quality: int = None) -> str:
+    """
+    Saves `image` with the colour profile and, optionally, the metadata of `source`.
+
+    The ICC profile is always embedded so colours render as in the input. With
+    keep_metadata, EXIF and XMP are carried over as well. JPEG outputs reuse the
+    source's quantisation tables when it was a JPEG and no quality is given, so
+    re-encoding does not silently change quality.
+
+    Args:
+        image (PIL.Image.Image): Image to save.
+        output_path (str): Destination; the format follows the extension.
+        source (PIL.Image.Image, optional): The originally loaded image. Defaults to `image`,
+            whose info carries the source's profile and metadata after `merge_channels`.
+        keep_metadata (bool, optional): Carry EXIF/XMP over. Defaults to True.
+        jpeg_quality (int, optional): Explicit JPEG/WebP quality (1-95).
+
+    Returns:
+        str: output_path.
+    """
+    image_format = format_for_extension(output_path, default=None)
+    image, params = _prepare_for_save(image, image_format, source or image, keep_metadata, jpeg_quality)
+
+    output_dir = os.path.dirname(output_path)
+    if output_dir:
+        os.makedirs(output_dir, exist_ok=True)
+    image.save(output_path, format=image_format, **params)
+    return output_path
+
+
+def encode_image(image: Image.Image, image_format: str, source: Image.Image = None, keep_metadata: bool = True,
+                 jpeg_quality: int = None) -> bytes:
+    """
+    In-memory counterpart of `save_image`: encodes `image` as `image_format` (e.g. "PNG", "JPEG").
+
+    Returns:
+        bytes: The encoded file.
+    """
+    image, params = _prepare_for_save(image, image_format, source or image, keep_metadata, jpeg_quality)
+    buffer = io.BytesIO()
+    image.save(buffer, format=image_format, **params)
+    return buffer.getvalue()
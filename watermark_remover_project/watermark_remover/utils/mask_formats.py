@@ -0,0 +1,703 @@
+"""
+Mask import/export in annotation formats used by external labelling tools.
+
+- COCO: a COCO JSON document whose annotations carry the mask as run-length
+  encoding (RLE, compressed string or uncompressed counts, exact) or polygons.
+  Polygons cannot hold holes and drop components smaller than a triangle.
+- VOC: Pascal VOC-style palette PNG with class indices, 0 background,
+  1 watermark and 255 "void".
+- SVG: filled paths (even-odd, so holes survive) with vertices at pixel
+  coordinates. Paths, polygons, rectangles, circles and ellipses are read back,
+  including curves, arcs, transforms and a viewBox scaled to the image.
+
+Plain binary images (white marks the watermark) remain handled by
+`watermark_remover.utils.masks.load_mask`.
+"""
+
+import io
+import json
+import math
+import os
+import re
+import xml.etree.ElementTree as ElementTree
+from typing import List, Sequence, Tuple, Union
+
+import cv2
+import numpy as np
+from PIL import Image
+
+from watermark_remover.utils.masks import MaskInput, load_mask
+
+IMPORT_FORMATS = ("auto", "image", "coco", "voc", "svg")
+EXPORT_FORMATS = ("png", "coco", "coco_polygons", "voc", "svg")
+
+MEDIA_TYPES = {
+    "png": "image/png",
+    "voc": "image/png",
+    "coco": "application/json",
+    "coco_polygons": "application/json",
+    "svg": "image/svg+xml",
+}
+
+WATERMARK_CATEGORY = {"id": 1, "name": "watermark", "supercategory": "overlay"}
+
+# Pascal VOC colour map: background, class 1, and "void" at index 255.
+VOC_BACKGROUND = (0, 0, 0)
+VOC_WATERMARK = (128, 0, 0)
+VOC_VOID = (224, 224, 192)
+
+# Line segments per Bézier curve when reading SVG paths.
+CURVE_SEGMENTS = 16
+
+
+def _binary(mask: np.ndarray) -> np.ndarray:
+    return (np.asarray(mask) > 0).astype(np.uint8)
+
+
+# COCO run-length encoding ----------------------------------------------------
+
+def _counts_to_string(counts: Sequence[int]) -> str:
+    """COCO's compressed RLE: LEB128-like 6-bit chunks of count deltas, offset into printable ASCII."""
+    chars = []
+    for i, count in enumerate(counts):
+        value = int(count) - (int(counts[i - 2]) if i > 2 else 0)
+        more = True
+        while more:
+            chunk = value & 0x1F
+            value >>= 5
+            more = value != -1 if chunk & 0x10 else value != 0
+            if more:
+                chunk |= 0x20
+            chars.append(chr(chunk + 48))
+    return "".join(chars)
+
+
+def _string_to_counts(text: str) -> List[int]:
+    counts = []
+    position = 0
+    while position < len(text):
+        value = 0
+        shift = 0
+        more = True
+        while more:
+            if position >= len(text):
+                raise ValueError("Truncated COCO RLE string")
+            chunk = ord(text[position]) - 48
+            value |= (chunk & 0x1F) << shift
+            more = bool(chunk & 0x20)
+            position += 1
+            shift += 5
+            if not more and chunk & 0x10:
+                value |= -1 << shift
+        if len(counts) > 2:
+            value += counts[-2]
+        counts.append(value)
+    return counts
+
+
+def encode_rle(mask: np.ndarray, compress: bool = True) -> dict:
+    """
+    COCO RLE of a mask: runs of the column-major flattened mask, starting with background.
+
+    Args:
+        mask (np.ndarray): (H, W) mask; non-zero pixels are the watermark.
+        compress (bool, optional): Encode counts as COCO's compact string rather than a list. Defaults to True.
+
+    Returns:
+        dict: {"size": [H, W], "counts": str | list}.
+    """
+    height, width = mask.shape[:2]
+    flat = _binary(mask).flatten(order="F")
+    changes = np.flatnonzero(np.diff(flat)) + 1
+    counts = np.diff(np.concatenate([[0], changes, [flat.size]])).tolist()
+    if flat.size and flat[0]:
+        counts = [0] + counts
+    return {"size": [height, width], "counts": _counts_to_string(counts) if compress else counts}
+
+
+def decode_rle(rle: dict, size: Tuple[int, int] = None) -> np.ndarray:
+    """
+    Decodes a COCO RLE (compressed or uncompressed) to a uint8 (H, W) mask, 255 on the watermark.
+
+    Args:
+        rle (dict): {"size": [H, W], "counts": str | list}.
+        size (tuple, optional): Expected (width, height). Uploaded RLEs should always pass the image
+            size: the mask is allocated at the RLE's own size, which is otherwise trusted.
+
+    Raises:
+        ValueError: If the RLE is malformed, its size differs from `size` or its runs do not cover the image.
+    """
+    try:
+        height, width = (int(value) for value in rle["size"])
+        counts = rle["counts"]
+    except (KeyError, TypeError, ValueError):
+        raise ValueError("COCO RLE needs 'size' [height, width] and 'counts'") from None
+    if size is not None and (width, height) != tuple(size):
+        raise ValueError(f"COCO RLE size {width}x{height} does not match image size {size[0]}x{size[1]}")
+    if isinstance(counts, bytes):
+        counts = counts.decode("ascii")
+    counts = _string_to_counts(counts) if isinstance(counts, str) else [int(count) for count in counts]
+    if any(count < 0 for count in counts) or sum(counts) != height * width:
+        raise ValueError(f"COCO RLE runs cover {sum(counts)} pixels, expected {height * width}")
+    values = np.arange(len(counts)) % 2
+    flat = np.repeat(values.astype(np.uint8), counts)
+    return flat.reshape((height, width), order="F") * 255
+
+
+# Polygons ---------------------------------------------------------------------
+
+def mask_to_polygons(mask: np.ndarray) -> List[List[float]]:
+    """Outer contours of a mask as COCO polygons ([x1, y1, x2, y2, ...] per component)."""
+    contours = cv2.findContours(_binary(mask), cv2.RETR_EXTERNAL, cv2.CHAIN_APPROX_SIMPLE)[-2]
+    return [contour.reshape(-1).astype(float).tolist() for contour in contours if len(contour) >= 3]
+
+
+def polygons_to_mask(polygons: Sequence[Sequence[float]], size: Tuple[int, int]) -> np.ndarray:
+    """
+    Rasterises COCO polygons at (width, height).
+
+    Raises:
+        ValueError: If a polygon has an odd number of coordinates or fewer than 3 points.
+    """
+    width, height = size
+    mask = np.zeros((height, width), dtype=np.uint8)
+    for polygon in polygons:
+        coordinates = np.asarray(polygon, dtype=np.float64)
+        if coordinates.ndim != 1 or coordinates.size % 2 or coordinates.size < 6:
+            raise ValueError("COCO polygons must be flat [x1, y1, x2, y2, ...] lists of at least 3 points")
+        cv2.fillPoly(mask, [np.round(coordinates.reshape(-1, 2)).astype(np.int32)], 255)
+    return mask
+
+
+# COCO documents ---------------------------------------------------------------
+
+def to_coco(mask: np.ndarray, file_name: str = "image.png", polygons: bool = False, score: float = None) -> dict:
+    """
+    A COCO JSON document with the mask as the single "watermark" annotation of one image.
+
+    Args:
+        mask (np.ndarray): (H, W) mask.
+        file_name (str, optional): `file_name` of the image entry. Defaults to "image.png".
+        polygons (bool, optional): Store polygons instead of RLE (editable in more tools, but lossy).
+            Defaults to False.
+        score (float, optional): Detection confidence, stored as the annotation's `score`.
+
+    Returns:
+        dict: The COCO document.
+    """
+    height, width = mask.shape[:2]
+    binary = _binary(mask)
+    segmentation = mask_to_polygons(binary) if polygons else encode_rle(binary)
+    annotations = []
+    if binary.any():
+        x, y, w, h = cv2.boundingRect(binary)
+        annotation = {
+            "id": 1,
+            "image_id": 1,
+            "category_id": WATERMARK_CATEGORY["id"],
+            "segmentation": segmentation,
+            "area": int(binary.sum()),
+            "bbox": [x, y, w, h],
+            "iscrowd": 0 if polygons else 1,
+        }
+        if score is not None:
+            annotation["score"] = round(float(score), 3)
+        annotations.append(annotation)
+    return {
+        "images": [{"id": 1, "file_name": os.path.basename(file_name), "width": width, "height": height}],
+        "annotations": annotations,
+        "categories": [WATERMARK_CATEGORY],
+    }
+
+
+def _segmentation_mask(segmentation, size: Tuple[int, int]) -> np.ndarray:
+    if isinstance(segmentation, dict):
+        return decode_rle(segmentation, size)
+    if isinstance(segmentation, list):
+        return polygons_to_mask(segmentation, size)
+    raise ValueError(f"Unsupported COCO segmentation: {type(segmentation).__name__}")
+
+
+def read_coco(document: Union[str, bytes, dict, list], size: Tuple[int, int], image_name: str = None) -> np.ndarray:
+    """
+    Reads a mask from COCO annotations.
+
+    Args:
+        document: COCO JSON (text, bytes or parsed). A full document (all annotations of one image
+            are combined), a single annotation, a bare RLE, or a list of polygons.
+        size (tuple): (width, height) of the image.
+        image_name (str, optional): `file_name` of the image in documents listing several images.
+
+    Returns:
+        np.ndarray: uint8 (H, W) mask, 255 on the watermark.
+
+    Raises:
+        ValueError: If the JSON is invalid, the image cannot be identified or the sizes disagree.
+    """
+    if isinstance(document, (str, bytes, bytearray)):
+        try:
+            document = json.loads(document)
+        except (json.JSONDecodeError, UnicodeDecodeError) as e:
+            raise ValueError(f"Invalid COCO JSON: {e}") from e
+    width, height = size
+
+    if isinstance(document, dict) and "annotations" in document:
+        images = document.get("images") or []
+        if image_name is not None and images:
+            wanted = os.path.basename(image_name)
+            images = [image for image in images if os.path.basename(str(image.get("file_name", ""))) == wanted]
+            if not images:
+                raise ValueError(f"COCO document has no image named '{wanted}'")
+        if len(images) > 1:
+            raise ValueError("COCO document lists several images; give the image name to pick one")
+        image_id = images[0].get("id") if images else None
+        if images and (images[0].get("width", width), images[0].get("height", height)) != (width, height):
+            raise ValueError(f"COCO image size {images[0].get('width')}x{images[0].get('height')} "
+                             f"does not match image size {width}x{height}")
+        segmentations = [annotation["segmentation"] for annotation in document["annotations"]
+                         if "segmentation" in annotation and (image_id is None or annotation.get("image_id") == image_id)]
+    elif isinstance(document, dict) and "segmentation" in document:
+        segmentations = [document["segmentation"]]
+    elif isinstance(document, (dict, list)):
+        segmentations = [document]
+    else:
+        raise ValueError("Unrecognised COCO annotation")
+
+    mask = np.zeros((height, width), dtype=np.uint8)
+    for segmentation in segmentations:
+        mask = np.maximum(mask, _segmentation_mask(segmentation, size))
+    return mask
+
+
+# Pascal VOC palette PNGs ---------------------------------------------------------
+
+def to_voc_png(mask: np.ndarray) -> bytes:
+    """Encodes a mask as a VOC-style palette PNG: index 0 background, 1 watermark."""
+    palette = list(VOC_BACKGROUND) + list(VOC_WATERMARK) + [0, 0, 0] * 253 + list(VOC_VOID)
+    image = Image.fromarray(_binary(mask), mode="P")
+    image.putpalette(palette)
+    buffer = io.BytesIO()
+    image.save(buffer, format="PNG")
+    return buffer.getvalue()
+
+
+def is_voc_image(image: Image.Image) -> bool:
+    """True for palette images using the VOC colour map (class 1 is dark red)."""
+    if image.mode != "P":
+        return False
+    palette = image.getpalette() or []
+    return tuple(palette[3:6]) == VOC_WATERMARK
+
+
+def read_voc(image: Image.Image) -> np.ndarray:
+    """
+    Mask from a VOC-style class-index image: any class other than background (0) and void (255).
+    Non-palette images are read as plain binary masks.
+    """
+    if image.mode != "P":
+        return load_mask(image, image.size)
+    indices = np.asarray(image)
+    return ((indices != 0) & (indices != 255)).astype(np.uint8) * 255
+
+
+# SVG -------------------------------------------------------------------------------
+
+def to_svg(mask: np.ndarray) -> str:
+    """Encodes a mask as an SVG document with one even-odd filled path per outer contour and its holes."""
+    height, width = mask.shape[:2]
+    contours, hierarchy = cv2.findContours(_binary(mask), cv2.RETR_CCOMP, cv2.CHAIN_APPROX_SIMPLE)[-2:]
+
+    def subpath(contour):
+        points = contour.reshape(-1, 2)
+        return "M " + " L ".join(f"{x} {y}" for x, y in points) + " Z"
+
+    paths = []
+    for index, contour in enumerate(contours):
+        # RETR_CCOMP: top-level entries (no parent) are outer contours, their children holes.
+        if hierarchy[0][index][3] != -1:
+            continue
+        parts = [subpath(contour)]
+        child = hierarchy[0][index][2]
+        while child != -1:
+            parts.append(subpath(contours[child]))
+            child = hierarchy[0][child][0]
+        paths.append(f'  <path d="{" ".join(parts)}"/>')
+    return (f'<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" '
+            f'viewBox="0 0 {width} {height}">\n'
+            f'<g id="watermark" fill="#ffffff" fill-rule="evenodd">\n' + "\n".join(paths) + "\n</g>\n</svg>\n")
+
+
+_NUMBER = r"[-+]?(?:\d*\.\d+|\d+\.?)(?:[eE][-+]?\d+)?"
+_PATH_TOKEN = re.compile(rf"[MmLlHhVvCcSsQqTtAaZz]|{_NUMBER}")
+_TRANSFORM = re.compile(r"(matrix|translate|scale|rotate|skewX|skewY)\s*\(([^)]*)\)")
+
+
+def _numbers(text: str) -> List[float]:
+    return [float(value) for value in re.findall(_NUMBER, text or "")]
+
+
+def _parse_transform(text: str) -> np.ndarray:
+    matrix = np.eye(3)
+    for name, arguments in _TRANSFORM.findall(text or ""):
+        values = _numbers(arguments)
+        if name == "matrix" and len(values) == 6:
+            a, b, c, d, e, f = values
+            step = np.array([[a, c, e], [b, d, f], [0, 0, 1]])
+        elif name == "translate" and values:
+            step = np.array([[1, 0, values[0]], [0, 1, values[1] if len(values) > 1 else 0], [0, 0, 1]])
+        elif name == "scale" and values:
+            sy = values[1] if len(values) > 1 else values[0]
+            step = np.diag([values[0], sy, 1.0])
+        elif name == "rotate" and values:
+            angle = math.radians(values[0])
+            cx, cy = (values[1], values[2]) if len(values) == 3 else (0, 0)
+            cos, sin = math.cos(angle), math.sin(angle)
+            step = (np.array([[1, 0, cx], [0, 1, cy], [0, 0, 1]]) @ np.array([[cos, -sin, 0], [sin, cos, 0], [0, 0, 1]])
+                    @ np.array([[1, 0, -cx], [0, 1, -cy], [0, 0, 1]]))
+        elif name in ("skewX", "skewY") and values:
+            shear = math.tan(math.radians(values[0]))
+            step = np.array([[1, shear, 0], [0, 1, 0], [0, 0, 1]]) if name == "skewX" else \
+                np.array([[1, 0, 0], [shear, 1, 0], [0, 0, 1]])
+        else:
+            raise ValueError(f"Invalid SVG transform: {name}({arguments})")
+        matrix = matrix @ step
+    return matrix
+
+
+def _bezier(points: np.ndarray) -> np.ndarray:
+    """Samples a quadratic or cubic Bézier curve given its control points (excluding the start)."""
+    t = np.linspace(0, 1, CURVE_SEGMENTS + 1)[1:, None]
+    if len(points) == 3:
+        p0, p1, p2 = points
+        return (1 - t) ** 2 * p0 + 2 * (1 - t) * t * p1 + t ** 2 * p2
+    p0, p1, p2, p3 = points
+    return (1 - t) ** 3 * p0 + 3 * (1 - t) ** 2 * t * p1 + 3 * (1 - t) * t ** 2 * p2 + t ** 3 * p3
+
+
+def _arc(start: np.ndarray, rx: float, ry: float, rotation: float, large: bool, sweep: bool,
+         end: np.ndarray) -> np.ndarray:
+    """Samples an SVG elliptical arc (endpoint parameterisation, SVG 1.1 appendix F.6)."""
+    if rx == 0 or ry == 0 or np.allclose(start, end):
+        return end[None]
+    rx, ry = abs(rx), abs(ry)
+    phi = math.radians(rotation)
+    cos, sin = math.cos(phi), math.sin(phi)
+    dx, dy = (start - end) / 2
+    x1, y1 = cos * dx + sin * dy, -sin * dx + cos * dy
+    scale = x1 ** 2 / rx ** 2 + y1 ** 2 / ry ** 2
+    if scale > 1:
+        rx, ry = rx * math.sqrt(scale), ry * math.sqrt(scale)
+    numerator = max(rx ** 2 * ry ** 2 - rx ** 2 * y1 ** 2 - ry ** 2 * x1 ** 2, 0)
+    factor = math.sqrt(numerator / (rx ** 2 * y1 ** 2 + ry ** 2 * x1 ** 2))
+    if large == sweep:
+        factor = -factor
+    cx1, cy1 = factor * rx * y1 / ry, -factor * ry * x1 / rx
+    center = np.array([cos * cx1 - sin * cy1, sin * cx1 + cos * cy1]) + (start + end) / 2
+
+    def angle(u, v):
+        return math.atan2(u[0] * v[1] - u[1] * v[0], u[0] * v[0] + u[1] * v[1])
+
+    theta = angle((1, 0), ((x1 - cx1) / rx, (y1 - cy1) / ry))
+    delta = angle(((x1 - cx1) / rx, (y1 - cy1) / ry), ((-x1 - cx1) / rx, (-y1 - cy1) / ry))
+    if not sweep and delta > 0:
+        delta -= 2 * math.pi
+    elif sweep and delta < 0:
+        delta += 2 * math.pi
+    angles = theta + delta * np.linspace(0, 1, CURVE_SEGMENTS + 1)[1:]
+    x, y = rx * np.cos(angles), ry * np.sin(angles)
+    return np.stack([cos * x - sin * y, sin * x + cos * y], axis=1) + center
+
+
+def parse_svg_path(d: str) -> List[np.ndarray]:
+    """
+    Flattens SVG path data to polygons, one (N, 2) array per subpath.
+
+    Raises:
+        ValueError: If the path data is malformed.
+    """
+    tokens = _PATH_TOKEN.findall(d or "")
+    subpaths, points = [], []
+    position = np.zeros(2)
+    start = np.zeros(2)
+    last_control = None
+    command = None
+    index = 0
+
+    def take(count):
+        nonlocal index
+        values = tokens[index:index + count]
+        if len(values) < count or any(value.isalpha() for value in values):
+            raise ValueError(f"Malformed SVG path near '{' '.join(tokens[max(index - 1, 0):index + count])}'")
+        index += count
+        return [float(value) for value in values]
+
+    def close():
+        nonlocal points
+        if len(points) >= 3:
+            subpaths.append(np.array(points))
+        points = []
+
+    while index < len(tokens):
+        if tokens[index].isalpha():
+            command = tokens[index]
+            index += 1
+        elif command is None:
+            raise ValueError("SVG path data must start with a command")
+        relative = command.islower()
+        origin = position if relative else np.zeros(2)
+        upper = command.upper()
+        previous = last_control
+        last_control = None
+
+        if upper == "Z":
+            close()
+            position = start.copy()
+            command = None
+            continue
+        if upper == "M":
+            close()
+            position = origin + take(2)
+            start = position.copy()
+            points = [position]
+            # Further coordinate pairs are implicit line-tos.
+            command = "l" if relative else "L"
+            continue
+        if not points:
+            # Drawing after Z without a moveto starts a new subpath at the previous start.
+            points = [position]
+        if upper == "L":
+            position = origin + take(2)
+            points.append(position)
+        elif upper == "H":
+            position = np.array([take(1)[0] + (position[0] if relative else 0), position[1]])
+            points.append(position)
+        elif upper == "V":
+            position = np.array([position[0], take(1)[0] + (position[1] if relative else 0)])
+            points.append(position)
+        elif upper in ("C", "S"):
+            if upper == "C":
+                control1 = origin + take(2)
+            else:
+                # S reflects the previous cubic control point.
+                control1 = 2 * position - previous[0] if previous is not None and previous[1] == "C" else position
+            control2, end = origin + take(2), origin + take(2)
+            points.extend(_bezier(np.array([position, control1, control2, end])))
+            last_control = (control2, "C")
+            position = end
+        elif upper in ("Q", "T"):
+            if upper == "Q":
+                control = origin + take(2)
+            else:
+                control = 2 * position - previous[0] if previous is not None and previous[1] == "Q" else position
+            end = origin + take(2)
+            points.extend(_bezier(np.array([position, control, end])))
+            last_control = (control, "Q")
+            position = end
+        elif upper == "A":
+            rx, ry, rotation, large, sweep = take(5)
+            end = origin + take(2)
+            points.extend(_arc(position, rx, ry, rotation, bool(large), bool(sweep), end))
+            position = end
+    close()
+    return subpaths
+
+
+def _tag(element) -> str:
+    return element.tag.rsplit("}", 1)[-1]
+
+
+def _style(element, name: str, default: str = None) -> str:
+    for declaration in (element.get("style") or "").split(";"):
+        key, _, value = declaration.partition(":")
+        if key.strip() == name:
+            return value.strip()
+    return element.get(name, default)
+
+
+def _length(element, name: str) -> float:
+    values = _numbers(element.get(name, "0"))
+    return values[0] if values else 0.0
+
+
+def _element_polygons(element) -> List[np.ndarray]:
+    tag = _tag(element)
+    if tag == "path":
+        return parse_svg_path(element.get("d"))
+    if tag in ("polygon", "polyline"):
+        values = _numbers(element.get("points"))
+        return [np.array(values[:len(values) // 2 * 2]).reshape(-1, 2)] if len(values) >= 6 else []
+    if tag == "rect":
+        x, y, w, h = (_length(element, name) for name in ("x", "y", "width", "height"))
+        return [np.array([[x, y], [x + w, y], [x + w, y + h], [x, y + h]])] if w > 0 and h > 0 else []
+    if tag in ("circle", "ellipse"):
+        cx, cy = _length(element, "cx"), _length(element, "cy")
+        rx = _length(element, "r" if tag == "circle" else "rx")
+        ry = rx if tag == "circle" else _length(element, "ry")
+        angles = np.linspace(0, 2 * math.pi, 4 * CURVE_SEGMENTS, endpoint=False)
+        return [np.stack([cx + rx * np.cos(angles), cy + ry * np.sin(angles)], axis=1)] if rx > 0 and ry > 0 else []
+    return []
+
+
+def read_svg(document: Union[str, bytes], size: Tuple[int, int]) -> np.ndarray:
+    """
+    Rasterises the filled shapes of an SVG document to a mask.
+
+    Shapes with `fill="none"` and everything inside <defs>, <clipPath>, <mask> and <pattern> are ignored.
+    A viewBox is scaled to the image size; without one, SVG user units are image pixels.
+
+    Args:
+        document (str | bytes): The SVG document.
+        size (tuple): (width, height) of the image.
+
+    Returns:
+        np.ndarray: uint8 (H, W) mask, 255 inside the shapes.
+
+    Raises:
+        ValueError: If the document is not valid SVG.
+    """
+    try:
+        root = ElementTree.fromstring(document)
+    except ElementTree.ParseError as e:
+        raise ValueError(f"Invalid SVG: {e}") from e
+    if _tag(root) != "svg":
+        raise ValueError(f"Expected an <svg> document, got <{_tag(root)}>")
+    width, height = size
+
+    base = np.eye(3)
+    view_box = _numbers(root.get("viewBox"))
+    if len(view_box) == 4 and view_box[2] > 0 and view_box[3] > 0:
+        min_x, min_y, box_width, box_height = view_box
+        base = np.diag([width / box_width, height / box_height, 1.0]) @ \
+            np.array([[1, 0, -min_x], [0, 1, -min_y], [0, 0, 1]])
+
+    mask = np.zeros((height, width), dtype=np.uint8)
+
+    def visit(element, matrix, fill_rule, filled):
+        if _tag(element) in ("defs", "clipPath", "mask", "pattern", "symbol", "title", "desc", "metadata"):
+            return
+        matrix = matrix @ _parse_transform(element.get("transform"))
+        fill_rule = _style(element, "fill-rule", fill_rule)
+        filled = filled and _style(element, "fill", "") != "none" and _style(element, "display", "") != "none"
+        polygons = _element_polygons(element) if filled else []
+        if polygons:
+            transformed = [np.round((np.c_[polygon, np.ones(len(polygon))] @ matrix.T)[:, :2]).astype(np.int32)
+                           for polygon in polygons]
+            if fill_rule == "evenodd":
+                shape = np.zeros_like(mask)
+                cv2.fillPoly(shape, transformed, 255)
+                np.maximum(mask, shape, out=mask)
+            else:
+                for polygon in transformed:
+                    cv2.fillPoly(mask, [polygon], 255)
+        for child in element:
+            visit(child, matrix, fill_rule, filled)
+
+    visit(root, base, "nonzero", True)
+    return mask
+
+
+# Dispatch --------------------------------------------------------------------------
+
+def detect_mask_format(data: bytes, file_name: str = None) -> str:
+    """Guesses the import format ("coco", "svg", "voc" or "image") of mask file contents."""
+    extension = os.path.splitext(file_name or "")[1].lower()
+    if extension == ".json":
+        return "coco"
+    if extension == ".svg":
+        return "svg"
+    head = data[:512].lstrip()
+    if head.startswith((b"{", b"[")):
+        return "coco"
+    if head.startswith(b"<") and b"<svg" in data[:4096]:
+        return "svg"
+    try:
+        with Image.open(io.BytesIO(data)) as image:
+            return "voc" if is_voc_image(image) else "image"
+    except OSError:
+        return "image"
+
+
+def import_mask(mask: MaskInput, size: Tuple[int, int], mask_format: str = "auto", file_name: str = None,
+                image_name: str = None) -> np.ndarray:
+    """
+    Loads a mask in any supported format.
+
+    Args:
+        mask (MaskInput): A path or encoded bytes in one of IMPORT_FORMATS; arrays and PIL images are
+            passed to `load_mask`.
+        size (tuple): (width, height) of the image the mask applies to.
+        mask_format (str, optional): One of IMPORT_FORMATS; "auto" guesses from the file name and contents.
+            Defaults to "auto".
+        file_name (str, optional): Name of uploaded bytes, used by "auto".
+        image_name (str, optional): Image to pick from COCO documents listing several images.
+
+    Returns:
+        np.ndarray: uint8 (height, width) mask, 255 on the watermark.
+
+    Raises:
+        FileNotFoundError: If mask is a path that does not exist.
+        ValueError: If the format is unknown, the contents invalid or the size does not match the image.
+    """
+    if mask_format not in IMPORT_FORMATS:
+        raise ValueError(f"Unknown mask format '{mask_format}'. Expected one of: {', '.join(IMPORT_FORMATS)}")
+    if not isinstance(mask, (str, bytes, bytearray)):
+        return load_mask(mask, size)
+    if isinstance(mask, str):
+        if not os.path.exists(mask):
+            raise FileNotFoundError(f"Mask not found: {mask}")
+        file_name = file_name or mask
+        with open(mask, "rb") as f:
+            mask = f.read()
+    if mask_format == "auto":
+        mask_format = detect_mask_format(mask, file_name)
+
+    if mask_format == "coco":
+        return read_coco(mask, size, image_name=image_name)
+    if mask_format == "svg":
+        return read_svg(mask, size)
+    if mask_format == "voc":
+        try:
+            with Image.open(io.BytesIO(mask)) as image:
+                image.load()
+                result = read_voc(image)
+        except OSError as e:
+            raise ValueError(f"Could not decode mask image: {e}") from e
+        return load_mask(result, size)
+    return load_mask(mask, size)
+
+
+def export_mask(mask: np.ndarray, mask_format: str = "png", file_name: str = None, score: float = None) -> bytes:
+    """
+    Encodes a mask in one of EXPORT_FORMATS.
+
+    Args:
+        mask (np.ndarray): (H, W) mask; non-zero pixels are the watermark.
+        mask_format (str, optional): "png" (binary image), "coco" (RLE), "coco_polygons", "voc" or "svg".
+            Defaults to "png".
+        file_name (str, optional): Image file name recorded in COCO documents.
+        score (float, optional): Detection confidence recorded in COCO documents.
+
+    Returns:
+        bytes: The encoded mask.
+
+    Raises:
+        ValueError: If the format is unknown.
+    """
+    if mask_format == "png":
+        buffer = io.BytesIO()
+        Image.fromarray(_binary(mask) * 255).save(buffer, format="PNG")
+        return buffer.getvalue()
+    if mask_format in ("coco", "coco_polygons"):
+        document = to_coco(mask, file_name or "image.png", polygons=mask_format == "coco_polygons", score=score)
+        return json.dumps(document).encode("utf-8")
+    if mask_format == "voc":
+        return to_voc_png(mask)
+    if mask_format == "svg":
+        return to_svg(mask).encode("utf-8")
+    raise ValueError(f"Unknown mask format '{mask_format}'. Expected one of: {', '.join(EXPORT_FORMATS)}")
+
+
+def format_for_mask_path(path: str, default: str = "png") -> str:
+    """Export format for an output path: .json is COCO RLE, .svg is SVG, anything else the default."""
+    extension = os.path.splitext(path)[1].lower()
+    return {".json": "coco", ".svg": "svg"}.get(extension, default)
@@ -0,0 +1,62 @@
+from PIL import Image
+import io
+import numpy as np
+import os
+from typing import Union
+
+MaskInput = Union[str, bytes, np.ndarray, Image.Image]
+
+
+def load_mask(mask: MaskInput, size: tuple) -> np.ndarray:
+    """
+    Loads a watermark mask and checks it matches the image it belongs to.
+
+    Args:
+        mask (str | bytes | np.ndarray | PIL.Image.Image): Path to a mask image (e.g. a binary PNG),
+            the encoded bytes of one, a 2-D array, or a PIL image. White / non-zero pixels
+            mark the watermark.
+        size (tuple): (width, height) of the image the mask applies to, as in PIL's `Image.size`.
+
+    Returns:
+        np.ndarray: uint8 array of shape (height, width) with 255 for watermark pixels and 0 elsewhere.
+
+    Raises:
+        FileNotFoundError: If mask is a path that does not exist.
+        ValueError: If the mask has an unsupported shape/type or its size does not match the image.
+    """
+    if isinstance(mask, str):
+        if not os.path.exists(mask):
+            raise FileNotFoundError(f"Mask not found: {mask}")
+        with Image.open(mask) as mask_image:
+            mask = np.asarray(mask_image.convert('L'))
+    elif isinstance(mask, (bytes, bytearray)):
+        try:
+            with Image.open(io.BytesIO(mask)) as mask_image:
+                mask = np.asarray(mask_image.convert('L'))
+        except OSError as e:
+            raise ValueError(f"Could not decode mask image: {e}") from e
+    elif isinstance(mask, Image.Image):
+        mask = np.asarray(mask.convert('L'))
+    elif isinstance(mask, np.ndarray):
+        if mask.ndim == 3:
+            # Treat any non-zero channel as part of the mask.
+            mask = mask.max(axis=2)
+        if mask.ndim != 2:
+            raise ValueError(f"Mask array must be 2-D (H, W) or 3-D (H, W, C), got shape {mask.shape}")
+    else:
+        raise ValueError(f"Unsupported mask type: {type(mask).__name__}")
+
+    width, height = size
+    if mask.shape != (height, width):
+        raise ValueError(
+            f"Mask size {mask.shape[1]}x{mask.shape[0]} does not match image size {width}x{height}"
+        )
+
+    if mask.dtype == bool:
+        binary = mask
+    elif mask.dtype == np.uint8:
+        # Threshold at mid-grey: anti-aliased PNG edges count as inside where they are more than half covered.
+        binary = mask > 127
+    else:
+        binary = mask > 0
+    return binary.astype(np.uint8) * 255
@@ -0,0 +1,217 @@
+"""
+Geometric mask specifications: rectangles, polygons and ellipses given as JSON.
+
+Users often know roughly where a watermark is ("bottom-right, 200x80") without
+having a mask image. A region specification is a JSON object, a list of them,
+or {"shapes": [...]}; each shape is rasterised at the image's resolution and
+the shapes are combined into one mask.
+
+    {"type": "rect", "anchor": "bottom_right", "x": 10, "y": 10, "width": 200, "height": 80}
+    {"type": "rect", "units": "relative", "x": 0.7, "y": 0.85, "width": 0.3, "height": 0.15}
+    {"type": "polygon", "points": [[0, 0], [120, 0], [0, 60]]}
+    {"type": "ellipse", "cx": 0.5, "cy": 0.5, "rx": 0.2, "ry": 0.1, "angle": 30, "units": "relative"}
+
+Coordinates are pixels by default, or fractions of the image width (x) and
+height (y) with "units": "relative". They are measured from the shape's
+`anchor` corner inwards (default top_left), so {"anchor": "bottom_right",
+"x": 10, "y": 10} is 10px from the right and bottom edges; a rectangle's x/y
+place its own corner on that side.
+"""
+
+import json
+import os
+from dataclasses import dataclass
+from typing import List, Sequence, Tuple, Union
+
+import cv2
+import numpy as np
+
+from watermark_remover.utils.masks import MaskInput, load_mask
+
+ANCHORS = ("top_left", "top_right", "bottom_left", "bottom_right")
+SHAPE_TYPES = ("rect", "polygon", "ellipse")
+UNITS = ("px", "relative")
+
+RegionSpec = Union[str, dict, Sequence[dict]]
+
+
+@dataclass
+class Region:
+    # One of SHAPE_TYPES.
+    type: str
+    # (N, 2) float points for polygons; rect: x, y, width, height; ellipse: cx, cy, rx, ry.
+    values: np.ndarray
+    anchor: str = "top_left"
+    units: str = "px"
+    # Ellipse rotation in degrees, clockwise.
+    angle: float = 0.0
+
+    def _scale(self, size: Tuple[int, int]) -> np.ndarray:
+        """Values in pixels, measured from the anchor corner."""
+        if self.units == "px":
+            return self.values.astype(np.float64)
+        width, height = size
+        return self.values * (np.array([width, height]) if self.type == "polygon" else
+                              np.array([width, height, width, height]))
+
+    def _from_anchor(self, x: np.ndarray, y: np.ndarray, size: Tuple[int, int]) -> Tuple[np.ndarray, np.ndarray]:
+        """Converts anchor-relative coordinates to image coordinates."""
+        width, height = size
+        vertical, horizontal = self.anchor.split("_")
+        return (width - x if horizontal == "right" else x), (height - y if vertical == "bottom" else y)
+
+    def draw(self, mask: np.ndarray) -> np.ndarray:
+        """Rasterises the shape into a uint8 (H, W) mask in place (255 inside) and returns the mask."""
+        size = (mask.shape[1], mask.shape[0])
+        values = self._scale(size)
+        if self.type == "rect":
+            x, y, w, h = values
+            x0, y0 = self._from_anchor(x, y, size)
+            # x/y place the rectangle's corner on the anchor side; it extends away from the anchor.
+            x1 = x0 + w if self.anchor.endswith("left") else x0 - w
+            y1 = y0 + h if self.anchor.startswith("top") else y0 - h
+            left, right = sorted((int(round(x0)), int(round(x1))))
+            top, bottom = sorted((int(round(y0)), int(round(y1))))
+            mask[max(top, 0):max(bottom, 0), max(left, 0):max(right, 0)] = 255
+        elif self.type == "polygon":
+            x, y = self._from_anchor(values[:, 0], values[:, 1], size)
+            points = np.round(np.stack([x, y], axis=1)).astype(np.int32)
+            cv2.fillPoly(mask, [points], 255)
+        else:
+            cx, cy, rx, ry = values
+            cx, cy = self._from_anchor(cx, cy, size)
+            cv2.ellipse(mask, (int(round(cx)), int(round(cy))), (int(round(rx)), int(round(ry))),
+                        self.angle, 0, 360, 255, thickness=-1)
+        return mask
+
+
+def _number(shape: dict, key: str, minimum: float = None) -> float:
+    if key not in shape:
+        raise ValueError(f"Region of type '{shape.get('type')}' needs '{key}'")
+    try:
+        value = float(shape[key])
+    except (TypeError, ValueError):
+        raise ValueError(f"Region '{key}' must be a number, got {shape[key]!r}") from None
+    if minimum is not None and value < minimum:
+        raise ValueError(f"Region '{key}' must be at least {minimum}, got {value}")
+    return value
+
+
+def parse_region(shape: dict) -> Region:
+    """
+    Validates one shape description.
+
+    Raises:
+        ValueError: If the type, anchor or units are unknown or a coordinate is missing or invalid.
+    """
+    if not isinstance(shape, dict):
+        raise ValueError(f"Region must be a JSON object, got {type(shape).__name__}")
+    kind = str(shape.get("type", "")).lower()
+    if kind == "rectangle":
+        kind = "rect"
+    if kind not in SHAPE_TYPES:
+        raise ValueError(f"Unknown region type '{shape.get('type')}'. Expected one of: {', '.join(SHAPE_TYPES)}")
+    anchor = shape.get("anchor", "top_left")
+    if anchor not in ANCHORS:
+        raise ValueError(f"Unknown region anchor '{anchor}'. Expected one of: {', '.join(ANCHORS)}")
+    units = shape.get("units", "px")
+    if units not in UNITS:
+        raise ValueError(f"Unknown region units '{units}'. Expected one of: {', '.join(UNITS)}")
+
+    angle = 0.0
+    if kind == "rect":
+        values = [_number(shape, "x"), _number(shape, "y"), _number(shape, "width", 0), _number(shape, "height", 0)]
+    elif kind == "ellipse":
+        values = [_number(shape, "cx"), _number(shape, "cy"), _number(shape, "rx", 0), _number(shape, "ry", 0)]
+        angle = _number(shape, "angle") if "angle" in shape else 0.0
+    else:
+        try:
+            values = np.asarray(shape.get("points"), dtype=np.float64)
+        except (TypeError, ValueError):
+            raise ValueError("Polygon 'points' must be a list of [x, y] pairs") from None
+        if values.ndim != 2 or values.shape[1] != 2 or len(values) < 3:
+            raise ValueError("Polygon 'points' must be a list of at least 3 [x, y] pairs")
+    return Region(type=kind, values=np.asarray(values, dtype=np.float64), anchor=anchor, units=units, angle=angle)
+
+
+def parse_regions(spec: RegionSpec) -> List[Region]:
+    """
+    Parses a region specification.
+
+    Args:
+        spec (str | dict | Sequence[dict]): JSON text, a path to a .json file, a shape,
+            a list of shapes, or {"shapes": [...]}.
+
+    Returns:
+        List[Region]: The validated shapes.
+
+    Raises:
+        FileNotFoundError: If spec names a .json file that does not exist.
+        ValueError: If the JSON is invalid or a shape is (see `parse_region`).
+    """
+    if isinstance(spec, str):
+        text = spec.strip()
+        if text.lower().endswith(".json") and not text.startswith(("{", "[")):
+            if not os.path.exists(text):
+                raise FileNotFoundError(f"Region file not found: {text}")
+            with open(text, encoding="utf-8") as f:
+                text = f.read()
+        try:
+            spec = json.loads(text)
+        except json.JSONDecodeError as e:
+            raise ValueError(f"Invalid region JSON: {e}") from e
+    if isinstance(spec, dict) and "shapes" in spec:
+        spec = spec["shapes"]
+    if not isinstance(spec, (dict, list, tuple)):
+        raise ValueError(f"Region specification must be a shape or a list of shapes, got {type(spec).__name__}")
+    shapes = [spec] if isinstance(spec, dict) else list(spec)
+    if not shapes:
+        raise ValueError("Region specification contains no shapes")
+    return [parse_region(shape) for shape in shapes]
+
+
+def region_mask(spec: Union[RegionSpec, Sequence[Region]], size: Tuple[int, int]) -> np.ndarray:
+    """
+    Rasterises a region specification to a mask.
+
+    Args:
+        spec: A specification accepted by `parse_regions`, or already parsed regions.
+            Several specifications (e.g. repeated CLI flags) can be given as a list of strings.
+        size (tuple): (width, height) of the image, as in PIL's `Image.size`.
+
+    Returns:
+        np.ndarray: uint8 (height, width) mask, 255 inside any shape.
+
+    Raises:
+        FileNotFoundError, ValueError: As `parse_regions`.
+    """
+    if isinstance(spec, (list, tuple)) and spec and all(isinstance(item, (str, Region)) for item in spec):
+        regions = [region for item in spec
+                   for region in ([item] if isinstance(item, Region) else parse_regions(item))]
+    else:
+        regions = parse_regions(spec)
+
+    width, height = size
+    mask = np.zeros((height, width), dtype=np.uint8)
+    for region in regions:
+        region.draw(mask)
+    return mask
+
+
+def add_regions(mask: MaskInput, spec: Union[RegionSpec, Sequence[Region]], size: Tuple[int, int]) -> np.ndarray:
+    """
+    Rasterises a region specification and combines it with an optional mask.
+
+    Args:
+        mask (MaskInput): Mask to extend (see `load_mask`), or None to use the regions alone.
+        spec: See `region_mask`.
+        size (tuple): (width, height) of the image.
+
+    Returns:
+        np.ndarray: uint8 (height, width) mask, 255 inside the mask or any shape.
+
+    Raises:
+        FileNotFoundError, ValueError: As `load_mask` and `parse_regions`.
+    """
+    regions = region_mask(spec, size)
+    return regions if mask is None else np.maximum(load_mask(mask, size), regions)